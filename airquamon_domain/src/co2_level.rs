@@ -0,0 +1,110 @@
+/// Air quality bands derived from the CO2 concentration, ordered from best to worst.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Co2Level {
+    Excellent,
+    Good,
+    Moderate,
+    Poor,
+    Unhealthy,
+}
+
+impl Co2Level {
+    /// Whether the air should be flagged to the user, e.g. highlighted on the display.
+    pub fn is_bad(self) -> bool {
+        self >= Co2Level::Moderate
+    }
+}
+
+/// Inclusive upper bounds in ppm of each band. Anything above `poor` is unhealthy.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Co2Thresholds {
+    pub excellent: u16,
+    pub good: u16,
+    pub moderate: u16,
+    pub poor: u16,
+}
+
+impl Default for Co2Thresholds {
+    fn default() -> Self {
+        Self {
+            excellent: 600,
+            good: 800,
+            moderate: 1000,
+            poor: 1500,
+        }
+    }
+}
+
+impl Co2Thresholds {
+    /// Classifies a single reading without any hysteresis.
+    pub fn level(&self, co2: u16) -> Co2Level {
+        if co2 <= self.excellent {
+            Co2Level::Excellent
+        } else if co2 <= self.good {
+            Co2Level::Good
+        } else if co2 <= self.moderate {
+            Co2Level::Moderate
+        } else if co2 <= self.poor {
+            Co2Level::Poor
+        } else {
+            Co2Level::Unhealthy
+        }
+    }
+
+    /// The highest concentration that isn't bad air, see [`Co2Level::is_bad`].
+    pub fn bad_air_threshold(&self) -> u16 {
+        self.good
+    }
+}
+
+/// Classifies consecutive readings, only moving to a better band once the CO2 concentration
+/// has dropped `hysteresis` ppm below its threshold so that noise around a threshold
+/// doesn't make the level flap.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Co2Classifier {
+    thresholds: Co2Thresholds,
+    hysteresis: u16,
+    level: Option<Co2Level>,
+}
+
+impl Default for Co2Classifier {
+    fn default() -> Self {
+        Self::new(Co2Thresholds::default(), 50)
+    }
+}
+
+impl Co2Classifier {
+    pub fn new(thresholds: Co2Thresholds, hysteresis: u16) -> Self {
+        Self {
+            thresholds,
+            hysteresis,
+            level: None,
+        }
+    }
+
+    pub fn thresholds(&self) -> &Co2Thresholds {
+        &self.thresholds
+    }
+
+    /// The level of the last classified reading.
+    pub fn level(&self) -> Option<Co2Level> {
+        self.level
+    }
+
+    pub fn classify(&mut self, co2: u16) -> Co2Level {
+        let raw = self.thresholds.level(co2);
+        let level = match self.level {
+            Some(current) if raw < current => self
+                .thresholds
+                .level(co2.saturating_add(self.hysteresis))
+                .min(current),
+            _ => raw,
+        };
+        self.level = Some(level);
+        level
+    }
+
+    pub fn reset(&mut self) {
+        self.level = None;
+    }
+}
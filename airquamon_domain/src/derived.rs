@@ -0,0 +1,138 @@
+//! Comfort metrics derived from temperature (°C) and relative humidity (%).
+
+use crate::Data;
+use libm::{expf, fabsf, logf, sqrtf};
+
+// Magnus formula coefficients over water (Sonntag 1990), valid from -45 °C to 60 °C.
+const MAGNUS_A: f32 = 17.62;
+const MAGNUS_B: f32 = 243.12;
+const MAGNUS_C: f32 = 6.112;
+
+/// Saturation vapour pressure in hPa.
+fn saturation_vapour_pressure(temperature: f32) -> f32 {
+    MAGNUS_C * expf(MAGNUS_A * temperature / (MAGNUS_B + temperature))
+}
+
+/// Dew point in °C.
+pub fn dew_point(temperature: f32, humidity: f32) -> f32 {
+    let gamma = logf(humidity / 100.0) + MAGNUS_A * temperature / (MAGNUS_B + temperature);
+    MAGNUS_B * gamma / (MAGNUS_A - gamma)
+}
+
+/// Absolute humidity in g/m³.
+pub fn absolute_humidity(temperature: f32, humidity: f32) -> f32 {
+    // 216.7 g·K/(hPa·m³) follows from the specific gas constant of water vapour
+    let vapour_pressure = saturation_vapour_pressure(temperature) * humidity / 100.0;
+    216.7 * vapour_pressure / (273.15 + temperature)
+}
+
+/// Humidex as defined by Environment Canada, a dimensionless value comparable to °C.
+pub fn humidex(temperature: f32, humidity: f32) -> f32 {
+    let dew_point_kelvin = dew_point(temperature, humidity) + 273.15;
+    let vapour_pressure = 6.11 * expf(5417.753 * (1.0 / 273.16 - 1.0 / dew_point_kelvin));
+    temperature + 0.5555 * (vapour_pressure - 10.0)
+}
+
+/// Heat index in °C, following the NWS algorithm (Rothfusz regression with adjustments).
+pub fn heat_index(temperature: f32, humidity: f32) -> f32 {
+    let t = temperature * 9.0 / 5.0 + 32.0;
+    let rh = humidity;
+
+    let simple = 0.5 * (t + 61.0 + (t - 68.0) * 1.2 + rh * 0.094);
+    let fahrenheit = if (simple + t) / 2.0 < 80.0 {
+        simple
+    } else {
+        let mut hi = -42.379 + 2.049_015_2 * t + 10.143_332 * rh
+            - 0.224_755_4 * t * rh
+            - 0.006_837_83 * t * t
+            - 0.054_817_17 * rh * rh
+            + 0.001_228_74 * t * t * rh
+            + 0.000_852_82 * t * rh * rh
+            - 0.000_001_99 * t * t * rh * rh;
+        if rh < 13.0 && (80.0..=112.0).contains(&t) {
+            hi -= (13.0 - rh) / 4.0 * sqrtf((17.0 - fabsf(t - 95.0)) / 17.0);
+        } else if rh > 85.0 && (80.0..=87.0).contains(&t) {
+            hi += (rh - 85.0) / 10.0 * (87.0 - t) / 5.0;
+        }
+        hi
+    };
+
+    (fahrenheit - 32.0) * 5.0 / 9.0
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Comfort {
+    Comfortable,
+    TooCold,
+    TooWarm,
+    TooDry,
+    TooHumid,
+}
+
+/// Inclusive temperature (°C) and relative humidity (%) ranges considered comfortable.
+/// Temperature takes precedence when both are out of range.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct ComfortZone {
+    pub min_temperature: f32,
+    pub max_temperature: f32,
+    pub min_humidity: f32,
+    pub max_humidity: f32,
+}
+
+impl Default for ComfortZone {
+    fn default() -> Self {
+        Self {
+            min_temperature: 20.0,
+            max_temperature: 26.0,
+            min_humidity: 30.0,
+            max_humidity: 60.0,
+        }
+    }
+}
+
+impl ComfortZone {
+    pub fn classify(&self, temperature: f32, humidity: f32) -> Comfort {
+        if temperature < self.min_temperature {
+            Comfort::TooCold
+        } else if temperature > self.max_temperature {
+            Comfort::TooWarm
+        } else if humidity < self.min_humidity {
+            Comfort::TooDry
+        } else if humidity > self.max_humidity {
+            Comfort::TooHumid
+        } else {
+            Comfort::Comfortable
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct DerivedMetrics {
+    /// °C
+    pub dew_point: f32,
+    /// g/m³
+    pub absolute_humidity: f32,
+    pub humidex: f32,
+    /// °C
+    pub heat_index: f32,
+    pub comfort: Comfort,
+}
+
+impl DerivedMetrics {
+    pub fn new(data: &Data, comfort_zone: &ComfortZone) -> Self {
+        let (temperature, humidity) = (data.temperature, data.humidity);
+        Self {
+            dew_point: dew_point(temperature, humidity),
+            absolute_humidity: absolute_humidity(temperature, humidity),
+            humidex: humidex(temperature, humidity),
+            heat_index: heat_index(temperature, humidity),
+            comfort: comfort_zone.classify(temperature, humidity),
+        }
+    }
+}
+
+impl From<&Data> for DerivedMetrics {
+    fn from(data: &Data) -> Self {
+        Self::new(data, &ComfortZone::default())
+    }
+}
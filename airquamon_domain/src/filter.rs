@@ -0,0 +1,259 @@
+//! Smoothing of noisy readings, e.g. before they are displayed. Every filter works on all
+//! fields of a [`Measurement`] independently and leaves missing fields alone.
+//!
+//! Filters are composed with [`Filter::then`]:
+//!
+//! ```
+//! use airquamon_domain::filter::{Ema, Filter, Median, SpikeRejection};
+//!
+//! let mut smoothing = SpikeRejection::default().then(Median::<3>::new()).then(Ema::new(0.5));
+//! ```
+
+use crate::{Field, Measurement};
+
+const FIELDS: usize = Field::ALL.len();
+
+pub trait Filter {
+    fn filter(&mut self, measurement: Measurement) -> Measurement;
+
+    /// Forgets the previous readings, e.g. after the sensor restarted.
+    fn reset(&mut self);
+
+    /// Feeds the output of this filter into `next`.
+    fn then<F>(self, next: F) -> Chain<Self, F>
+    where
+        Self: Sized,
+        F: Filter,
+    {
+        Chain { first: self, next }
+    }
+}
+
+/// Two filters applied one after the other, see [`Filter::then`].
+#[derive(Clone, Debug)]
+pub struct Chain<A, B> {
+    first: A,
+    next: B,
+}
+
+impl<A, B> Filter for Chain<A, B>
+where
+    A: Filter,
+    B: Filter,
+{
+    fn filter(&mut self, measurement: Measurement) -> Measurement {
+        self.next.filter(self.first.filter(measurement))
+    }
+
+    fn reset(&mut self) {
+        self.first.reset();
+        self.next.reset();
+    }
+}
+
+/// Replaces every field present in `measurement` with `filter(field, value)`.
+fn filter_fields(
+    mut measurement: Measurement,
+    mut filter: impl FnMut(Field, f32) -> f32,
+) -> Measurement {
+    for field in Field::ALL {
+        if let Some(value) = measurement.value(field) {
+            measurement.set_value(field, filter(field, value));
+        }
+    }
+    measurement
+}
+
+/// Exponential moving average, the smaller `alpha` the smoother and the slower to follow
+/// changes.
+#[derive(Clone, Debug)]
+pub struct Ema {
+    alpha: f32,
+    averages: [Option<f32>; FIELDS],
+}
+
+impl Ema {
+    /// `alpha` is the weight of the latest reading in 0..=1, 1 doesn't smooth at all.
+    pub fn new(alpha: f32) -> Self {
+        Self {
+            alpha: alpha.clamp(0.0, 1.0),
+            averages: [None; FIELDS],
+        }
+    }
+
+    pub fn alpha(&self) -> f32 {
+        self.alpha
+    }
+}
+
+impl Filter for Ema {
+    fn filter(&mut self, measurement: Measurement) -> Measurement {
+        filter_fields(measurement, |field, value| {
+            let average = &mut self.averages[field as usize];
+            let smoothed = match *average {
+                Some(previous) => previous + self.alpha * (value - previous),
+                None => value,
+            };
+            *average = Some(smoothed);
+            smoothed
+        })
+    }
+
+    fn reset(&mut self) {
+        self.averages = [None; FIELDS];
+    }
+}
+
+/// Median of the last `N` readings, removes short spikes without smearing steps.
+#[derive(Clone, Debug)]
+pub struct Median<const N: usize> {
+    windows: [Ring<N>; FIELDS],
+}
+
+#[derive(Copy, Clone, Debug)]
+struct Ring<const N: usize> {
+    values: [f32; N],
+    len: usize,
+    next: usize,
+}
+
+impl<const N: usize> Ring<N> {
+    const EMPTY: Self = Self {
+        values: [0.0; N],
+        len: 0,
+        next: 0,
+    };
+
+    fn push(&mut self, value: f32) {
+        self.values[self.next] = value;
+        self.next = (self.next + 1) % N;
+        self.len = (self.len + 1).min(N);
+    }
+
+    /// Median of the values pushed so far, the mean of the middle two for an even number.
+    fn median(&self) -> f32 {
+        let mut sorted = self.values;
+        let sorted = &mut sorted[..self.len];
+        sorted.sort_unstable_by(f32::total_cmp);
+        let middle = self.len / 2;
+        if middle * 2 < self.len {
+            sorted[middle]
+        } else {
+            (sorted[middle - 1] + sorted[middle]) / 2.0
+        }
+    }
+}
+
+impl<const N: usize> Median<N> {
+    pub fn new() -> Self {
+        Self {
+            windows: [Ring::EMPTY; FIELDS],
+        }
+    }
+}
+
+impl<const N: usize> Default for Median<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const N: usize> Filter for Median<N> {
+    fn filter(&mut self, measurement: Measurement) -> Measurement {
+        if N == 0 {
+            return measurement;
+        }
+        filter_fields(measurement, |field, value| {
+            let window = &mut self.windows[field as usize];
+            window.push(value);
+            window.median()
+        })
+    }
+
+    fn reset(&mut self) {
+        self.windows = [Ring::EMPTY; FIELDS];
+    }
+}
+
+/// Holds the previous value when a reading jumps by more than a field's maximum change,
+/// e.g. a single CO2 reading 500 ppm above the others. A jump that persists for more than
+/// `max_rejections` readings is taken to be real and followed.
+#[derive(Clone, Debug)]
+pub struct SpikeRejection {
+    max_changes: [f32; FIELDS],
+    max_rejections: u8,
+    accepted: [Option<f32>; FIELDS],
+    rejections: [u8; FIELDS],
+}
+
+impl SpikeRejection {
+    /// Maximum changes between readings a minute apart: 200 ppm CO2, 2 °C, 10 %RH,
+    /// 50 µg/m³ particulate matter, 100 index points and 5 hPa, 2 rejections.
+    pub fn new() -> Self {
+        let mut max_changes = [0.0; FIELDS];
+        for field in Field::ALL {
+            max_changes[field as usize] = match field {
+                Field::Co2 => 200.0,
+                Field::Temperature => 2.0,
+                Field::Humidity => 10.0,
+                Field::Pm1_0 | Field::Pm2_5 | Field::Pm10 => 50.0,
+                Field::VocIndex | Field::NoxIndex => 100.0,
+                Field::Pressure => 5.0,
+            };
+        }
+        Self {
+            max_changes,
+            max_rejections: 2,
+            accepted: [None; FIELDS],
+            rejections: [0; FIELDS],
+        }
+    }
+
+    /// `f32::INFINITY` disables spike rejection for `field`.
+    pub fn with_max_change(mut self, field: Field, max_change: f32) -> Self {
+        self.max_changes[field as usize] = max_change;
+        self
+    }
+
+    pub fn with_max_rejections(mut self, max_rejections: u8) -> Self {
+        self.max_rejections = max_rejections;
+        self
+    }
+
+    pub fn max_change(&self, field: Field) -> f32 {
+        self.max_changes[field as usize]
+    }
+}
+
+impl Default for SpikeRejection {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Filter for SpikeRejection {
+    fn filter(&mut self, measurement: Measurement) -> Measurement {
+        filter_fields(measurement, |field, value| {
+            let index = field as usize;
+            match self.accepted[index] {
+                Some(accepted)
+                    if libm::fabsf(value - accepted) > self.max_changes[index]
+                        && self.rejections[index] < self.max_rejections =>
+                {
+                    self.rejections[index] += 1;
+                    accepted
+                }
+                _ => {
+                    self.accepted[index] = Some(value);
+                    self.rejections[index] = 0;
+                    value
+                }
+            }
+        })
+    }
+
+    fn reset(&mut self) {
+        self.accepted = [None; FIELDS];
+        self.rejections = [0; FIELDS];
+    }
+}
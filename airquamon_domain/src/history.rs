@@ -0,0 +1,140 @@
+use crate::{Field, Measurement, Timestamp};
+use heapless::{HistoryBuffer, Vec};
+
+/// Selects which of the stored measurements a query looks at.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Window {
+    All,
+    /// The most recent `n` measurements.
+    Last(usize),
+    /// Measurements taken at or after the given time.
+    Since(Timestamp),
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Statistics {
+    /// Number of measurements in the window that had a value for the field.
+    pub count: usize,
+    pub min: f32,
+    pub max: f32,
+    pub mean: f32,
+}
+
+/// The last `N` measurements, oldest ones are overwritten once full.
+pub struct History<const N: usize> {
+    buffer: HistoryBuffer<Measurement, N>,
+}
+
+impl<const N: usize> Default for History<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const N: usize> History<N> {
+    pub const fn new() -> Self {
+        Self {
+            buffer: HistoryBuffer::new(),
+        }
+    }
+
+    pub fn push(&mut self, measurement: Measurement) {
+        self.buffer.write(measurement);
+    }
+
+    pub fn clear(&mut self) {
+        self.buffer.clear();
+    }
+
+    pub fn len(&self) -> usize {
+        self.buffer.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.buffer.len() == 0
+    }
+
+    pub fn capacity(&self) -> usize {
+        N
+    }
+
+    pub fn latest(&self) -> Option<&Measurement> {
+        self.buffer.recent()
+    }
+
+    /// Measurements in the window, oldest first.
+    pub fn iter(&self, window: Window) -> impl Iterator<Item = &Measurement> + '_ {
+        let skip = match window {
+            Window::Last(n) => self.len().saturating_sub(n),
+            Window::All | Window::Since(_) => 0,
+        };
+        self.buffer
+            .oldest_ordered()
+            .skip(skip)
+            .filter(move |measurement| match window {
+                Window::Since(since) => measurement.timestamp >= since,
+                Window::All | Window::Last(_) => true,
+            })
+    }
+
+    /// Values of `field` in the window, oldest first, skipping measurements where it is missing.
+    pub fn values(&self, field: Field, window: Window) -> impl Iterator<Item = f32> + '_ {
+        self.iter(window)
+            .filter_map(move |measurement| measurement.value(field))
+    }
+
+    pub fn statistics(&self, field: Field, window: Window) -> Option<Statistics> {
+        let mut values = self.values(field, window);
+        let first = values.next()?;
+        let mut statistics = Statistics {
+            count: 1,
+            min: first,
+            max: first,
+            mean: 0.0,
+        };
+        let mut sum = first;
+        for value in values {
+            statistics.count += 1;
+            statistics.min = statistics.min.min(value);
+            statistics.max = statistics.max.max(value);
+            sum += value;
+        }
+        statistics.mean = sum / statistics.count as f32;
+        Some(statistics)
+    }
+
+    pub fn min(&self, field: Field, window: Window) -> Option<f32> {
+        self.statistics(field, window)
+            .map(|statistics| statistics.min)
+    }
+
+    pub fn max(&self, field: Field, window: Window) -> Option<f32> {
+        self.statistics(field, window)
+            .map(|statistics| statistics.max)
+    }
+
+    pub fn mean(&self, field: Field, window: Window) -> Option<f32> {
+        self.statistics(field, window)
+            .map(|statistics| statistics.mean)
+    }
+
+    /// The `percentile` (0 to 100) of `field` in the window, linearly interpolated
+    /// between the closest ranks.
+    pub fn percentile(&self, field: Field, window: Window, percentile: f32) -> Option<f32> {
+        let mut values: Vec<f32, N> = self.values(field, window).collect();
+        if values.is_empty() {
+            return None;
+        }
+        values.sort_unstable_by(f32::total_cmp);
+
+        let rank = percentile.clamp(0.0, 100.0) / 100.0 * (values.len() - 1) as f32;
+        let lower = rank as usize;
+        let upper = (lower + 1).min(values.len() - 1);
+        let fraction = rank - lower as f32;
+        Some(values[lower] + (values[upper] - values[lower]) * fraction)
+    }
+
+    pub fn median(&self, field: Field, window: Window) -> Option<f32> {
+        self.percentile(field, window, 50.0)
+    }
+}
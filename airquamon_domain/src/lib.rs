@@ -1,8 +1,307 @@
 #![no_std]
 
-#[derive(Copy, Clone)]
+use core::ops::{Add, RangeInclusive};
+use core::time::Duration;
+
+mod co2_level;
+pub use co2_level::{Co2Classifier, Co2Level, Co2Thresholds};
+
+pub mod derived;
+
+pub mod filter;
+
+mod history;
+pub use history::{History, Statistics, Window};
+
+mod trend;
+pub use trend::{Direction, Trend, TrendEstimator};
+
+/// Raw values of a single reading.
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
 pub struct Data {
+    /// CO2 concentration in ppm
     pub co2: u16,
+    /// Temperature in °C
     pub temperature: f32,
+    /// Relative humidity in %
     pub humidity: f32,
 }
+
+/// Mass concentrations of particulate matter in µg/m³ by particle diameter.
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct ParticulateMatter {
+    /// Particles up to 1.0 µm
+    pub pm1_0: f32,
+    /// Particles up to 2.5 µm
+    pub pm2_5: f32,
+    /// Particles up to 10 µm
+    pub pm10: f32,
+}
+
+/// Sensirion VOC and NOx indices, 1 to 500. Relative to the average of the past day
+/// at 100 for VOC and 1 for NOx, higher is worse.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct GasIndices {
+    pub voc: u16,
+    pub nox: u16,
+}
+
+/// A single value of a reading.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Field {
+    Co2,
+    Temperature,
+    Humidity,
+    Pm1_0,
+    Pm2_5,
+    Pm10,
+    VocIndex,
+    NoxIndex,
+    Pressure,
+}
+
+impl Field {
+    pub const ALL: [Field; 9] = [
+        Field::Co2,
+        Field::Temperature,
+        Field::Humidity,
+        Field::Pm1_0,
+        Field::Pm2_5,
+        Field::Pm10,
+        Field::VocIndex,
+        Field::NoxIndex,
+        Field::Pressure,
+    ];
+}
+
+/// Milliseconds since the epoch of the clock that produced it.
+/// For a monotonic clock that is usually boot, for an RTC the Unix epoch.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Timestamp(u64);
+
+impl Timestamp {
+    pub const fn from_millis(millis: u64) -> Self {
+        Self(millis)
+    }
+
+    pub const fn as_millis(self) -> u64 {
+        self.0
+    }
+
+    /// Time elapsed since `earlier`, zero if `earlier` is in the future.
+    pub fn duration_since(self, earlier: Timestamp) -> Duration {
+        Duration::from_millis(self.0.saturating_sub(earlier.0))
+    }
+}
+
+impl Add<Duration> for Timestamp {
+    type Output = Timestamp;
+
+    fn add(self, rhs: Duration) -> Self::Output {
+        Self(self.0.saturating_add(rhs.as_millis() as u64))
+    }
+}
+
+pub trait Clock {
+    fn now(&self) -> Timestamp;
+}
+
+impl<F> Clock for F
+where
+    F: Fn() -> Timestamp,
+{
+    fn now(&self) -> Timestamp {
+        self()
+    }
+}
+
+/// How far a single field of a reading can be trusted.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum Quality {
+    #[default]
+    Valid,
+    /// The value is present but shouldn't be relied on, e.g. out of the sensor's range. It is
+    /// kept for logging but isn't shown, recorded or smoothed.
+    Suspect,
+    /// The sensor didn't produce a value, whatever is in `Data` is a placeholder.
+    Missing,
+    /// The sensor only just started or woke up and its readings haven't stabilised yet.
+    WarmingUp,
+    /// The sensor keeps reporting the same value, it is likely frozen.
+    Stale,
+}
+
+impl Quality {
+    /// Whether the value can be shown and recorded, only valid readings can.
+    pub fn is_usable(self) -> bool {
+        self == Quality::Valid
+    }
+}
+
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct QualityFlags {
+    pub co2: Quality,
+    pub temperature: Quality,
+    pub humidity: Quality,
+}
+
+impl QualityFlags {
+    pub const VALID: QualityFlags = QualityFlags {
+        co2: Quality::Valid,
+        temperature: Quality::Valid,
+        humidity: Quality::Valid,
+    };
+
+    /// For sensors that don't measure any of CO2, temperature and humidity.
+    pub const MISSING: QualityFlags = QualityFlags {
+        co2: Quality::Missing,
+        temperature: Quality::Missing,
+        humidity: Quality::Missing,
+    };
+
+    /// Whether any of CO2, temperature and humidity has `quality`.
+    pub fn contains(&self, quality: Quality) -> bool {
+        [self.co2, self.temperature, self.humidity].contains(&quality)
+    }
+}
+
+/// Identifies the sensor a reading came from, e.g. its serial number.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct SourceId(pub u64);
+
+impl SourceId {
+    pub const UNKNOWN: SourceId = SourceId(0);
+}
+
+/// A reading together with when and where it was taken and how trustworthy each field is.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Measurement {
+    pub timestamp: Timestamp,
+    pub source: SourceId,
+    pub data: Data,
+    pub quality: QualityFlags,
+    /// Only measured by particulate matter sensors.
+    pub particulate_matter: Option<ParticulateMatter>,
+    /// Only measured by gas sensors, once their algorithm has warmed up.
+    pub gas_indices: Option<GasIndices>,
+    /// Ambient pressure in hPa, only measured by barometric sensors.
+    pub pressure: Option<f32>,
+}
+
+impl Measurement {
+    /// Creates a measurement with every field marked valid and without the fields only some
+    /// sensors measure, e.g. particulate matter.
+    pub const fn new(timestamp: Timestamp, source: SourceId, data: Data) -> Self {
+        Self {
+            timestamp,
+            source,
+            data,
+            quality: QualityFlags::VALID,
+            particulate_matter: None,
+            gas_indices: None,
+            pressure: None,
+        }
+    }
+
+    pub fn co2(&self) -> Option<u16> {
+        self.quality.co2.is_usable().then_some(self.data.co2)
+    }
+
+    pub fn temperature(&self) -> Option<f32> {
+        self.quality
+            .temperature
+            .is_usable()
+            .then_some(self.data.temperature)
+    }
+
+    pub fn humidity(&self) -> Option<f32> {
+        self.quality
+            .humidity
+            .is_usable()
+            .then_some(self.data.humidity)
+    }
+
+    /// The value of `field` as `f32`, `None` if it is missing.
+    pub fn value(&self, field: Field) -> Option<f32> {
+        match field {
+            Field::Co2 => self.co2().map(f32::from),
+            Field::Temperature => self.temperature(),
+            Field::Humidity => self.humidity(),
+            Field::Pm1_0 => self.particulate_matter.map(|pm| pm.pm1_0),
+            Field::Pm2_5 => self.particulate_matter.map(|pm| pm.pm2_5),
+            Field::Pm10 => self.particulate_matter.map(|pm| pm.pm10),
+            Field::VocIndex => self.gas_indices.map(|gas| gas.voc.into()),
+            Field::NoxIndex => self.gas_indices.map(|gas| gas.nox.into()),
+            Field::Pressure => self.pressure,
+        }
+    }
+
+    /// Replaces the value of `field`, e.g. with a smoothed one. Does nothing if the field is
+    /// missing. CO2 and the gas indices are rounded.
+    pub fn set_value(&mut self, field: Field, value: f32) {
+        if self.value(field).is_none() {
+            return;
+        }
+        match field {
+            Field::Co2 => self.data.co2 = libm::roundf(value) as u16,
+            Field::Temperature => self.data.temperature = value,
+            Field::Humidity => self.data.humidity = value,
+            Field::Pm1_0 | Field::Pm2_5 | Field::Pm10 => {
+                if let Some(pm) = &mut self.particulate_matter {
+                    match field {
+                        Field::Pm1_0 => pm.pm1_0 = value,
+                        Field::Pm2_5 => pm.pm2_5 = value,
+                        _ => pm.pm10 = value,
+                    }
+                }
+            }
+            Field::VocIndex | Field::NoxIndex => {
+                if let Some(gas) = &mut self.gas_indices {
+                    let index = libm::roundf(value) as u16;
+                    if field == Field::VocIndex {
+                        gas.voc = index;
+                    } else {
+                        gas.nox = index;
+                    }
+                }
+            }
+            Field::Pressure => self.pressure = Some(value),
+        }
+    }
+
+    /// Marks values that can't be real as missing (NaN, infinite) and values outside of what
+    /// indoor sensors can measure as suspect, e.g. 65535 ppm CO2 from a misbehaving sensor.
+    pub fn validated(mut self) -> Self {
+        fn check(quality: &mut Quality, value: f32, range: RangeInclusive<f32>) {
+            if !value.is_finite() {
+                *quality = Quality::Missing;
+            } else if !range.contains(&value) && *quality == Quality::Valid {
+                *quality = Quality::Suspect;
+            }
+        }
+        check(
+            &mut self.quality.co2,
+            self.data.co2.into(),
+            300.0..=40_000.0,
+        );
+        check(
+            &mut self.quality.temperature,
+            self.data.temperature,
+            -40.0..=85.0,
+        );
+        check(&mut self.quality.humidity, self.data.humidity, 0.0..=100.0);
+        self.particulate_matter = self
+            .particulate_matter
+            .filter(|pm| [pm.pm1_0, pm.pm2_5, pm.pm10].iter().all(|v| v.is_finite()));
+        self.pressure = self.pressure.filter(|pressure| pressure.is_finite());
+        self
+    }
+
+    pub fn age(&self, now: Timestamp) -> Duration {
+        now.duration_since(self.timestamp)
+    }
+
+    pub fn is_fresh(&self, now: Timestamp, max_age: Duration) -> bool {
+        self.age(now) <= max_age
+    }
+}
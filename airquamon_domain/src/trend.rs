@@ -0,0 +1,109 @@
+use crate::{Field, History, Window};
+use core::time::Duration;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Direction {
+    Rising,
+    Stable,
+    Falling,
+}
+
+/// Least squares fit of a field over a window of the history.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Trend {
+    pub direction: Direction,
+    /// Change per minute in the field's unit, e.g. ppm per minute for CO2.
+    pub slope: f32,
+    /// The fitted value at the time of the latest measurement, less noisy than the reading itself.
+    pub current: f32,
+    /// Number of measurements the fit is based on.
+    pub samples: usize,
+}
+
+impl Trend {
+    /// Time until `threshold` is crossed if the trend continues.
+    /// `None` if the value is stable, moving away from the threshold, already past it or
+    /// changing too slowly to ever get there.
+    pub fn time_to_threshold(&self, threshold: f32) -> Option<Duration> {
+        let remaining = threshold - self.current;
+        let heading_towards = match self.direction {
+            Direction::Rising => remaining >= 0.0,
+            Direction::Falling => remaining <= 0.0,
+            Direction::Stable => false,
+        };
+        if !heading_towards {
+            return None;
+        }
+        Duration::try_from_secs_f32(remaining / self.slope * 60.0).ok()
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct TrendEstimator {
+    pub field: Field,
+    pub window: Window,
+    /// Slopes (per minute) within ± tolerance are considered stable.
+    pub tolerance: f32,
+    /// Fewer measurements than this in the window don't produce a trend.
+    pub min_samples: usize,
+}
+
+impl TrendEstimator {
+    /// CO2 over the last 10 measurements, changes below 2 ppm per minute are noise.
+    pub fn co2() -> Self {
+        Self {
+            field: Field::Co2,
+            window: Window::Last(10),
+            tolerance: 2.0,
+            min_samples: 3,
+        }
+    }
+
+    pub fn estimate<const N: usize>(&self, history: &History<N>) -> Option<Trend> {
+        let latest = history.latest()?.timestamp;
+        // minutes relative to the latest measurement, keeps the sums small enough for f32
+        let points = || {
+            history.iter(self.window).filter_map(move |measurement| {
+                let minutes =
+                    -(latest.duration_since(measurement.timestamp).as_millis() as f32) / 60_000.0;
+                measurement.value(self.field).map(|value| (minutes, value))
+            })
+        };
+
+        let (mut count, mut sum_x, mut sum_y) = (0usize, 0.0f32, 0.0f32);
+        for (x, y) in points() {
+            count += 1;
+            sum_x += x;
+            sum_y += y;
+        }
+        if count < self.min_samples.max(2) {
+            return None;
+        }
+        let (mean_x, mean_y) = (sum_x / count as f32, sum_y / count as f32);
+
+        let (mut covariance, mut variance) = (0.0f32, 0.0f32);
+        for (x, y) in points() {
+            covariance += (x - mean_x) * (y - mean_y);
+            variance += (x - mean_x) * (x - mean_x);
+        }
+        if variance == 0.0 {
+            // all measurements taken at the same time
+            return None;
+        }
+
+        let slope = covariance / variance;
+        let direction = if slope > self.tolerance {
+            Direction::Rising
+        } else if slope < -self.tolerance {
+            Direction::Falling
+        } else {
+            Direction::Stable
+        };
+        Some(Trend {
+            direction,
+            slope,
+            current: mean_y - slope * mean_x,
+            samples: count,
+        })
+    }
+}
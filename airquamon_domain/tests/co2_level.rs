@@ -0,0 +1,98 @@
+use airquamon_domain::{Co2Classifier, Co2Level, Co2Thresholds};
+
+fn classifier() -> Co2Classifier {
+    Co2Classifier::new(Co2Thresholds::default(), 50)
+}
+
+#[test]
+fn thresholds_are_inclusive_upper_bounds() {
+    // (CO2 ppm, level)
+    let table = [
+        (0, Co2Level::Excellent),
+        (600, Co2Level::Excellent),
+        (601, Co2Level::Good),
+        (800, Co2Level::Good),
+        (801, Co2Level::Moderate),
+        (1000, Co2Level::Moderate),
+        (1001, Co2Level::Poor),
+        (1500, Co2Level::Poor),
+        (1501, Co2Level::Unhealthy),
+        (u16::MAX, Co2Level::Unhealthy),
+    ];
+    let thresholds = Co2Thresholds::default();
+    for (co2, expected) in table {
+        assert_eq!(thresholds.level(co2), expected, "{co2} ppm");
+    }
+}
+
+#[test]
+fn bad_air_starts_above_the_good_band() {
+    let thresholds = Co2Thresholds {
+        excellent: 500,
+        good: 700,
+        moderate: 900,
+        poor: 1200,
+    };
+
+    let threshold = thresholds.bad_air_threshold();
+
+    assert_eq!(threshold, 700);
+    assert!(!thresholds.level(threshold).is_bad());
+    assert!(thresholds.level(threshold + 1).is_bad());
+}
+
+#[test]
+fn moves_to_a_worse_band_immediately() {
+    let mut classifier = classifier();
+
+    assert_eq!(classifier.classify(790), Co2Level::Good);
+    assert_eq!(classifier.classify(801), Co2Level::Moderate);
+    assert_eq!(classifier.level(), Some(Co2Level::Moderate));
+}
+
+#[test]
+fn holds_the_band_until_co2_drops_below_the_hysteresis() {
+    // (CO2 ppm, level) of consecutive readings
+    let readings = [
+        (850, Co2Level::Moderate),
+        (800, Co2Level::Moderate),
+        (760, Co2Level::Moderate),
+        (751, Co2Level::Moderate),
+        (750, Co2Level::Good),
+        (790, Co2Level::Good),
+        (801, Co2Level::Moderate),
+    ];
+    let mut classifier = classifier();
+    for (co2, expected) in readings {
+        assert_eq!(classifier.classify(co2), expected, "{co2} ppm");
+    }
+}
+
+#[test]
+fn drops_several_bands_at_once() {
+    let mut classifier = classifier();
+    classifier.classify(2000);
+
+    assert_eq!(classifier.classify(1480), Co2Level::Unhealthy);
+    assert_eq!(classifier.classify(1440), Co2Level::Poor);
+    assert_eq!(classifier.classify(500), Co2Level::Excellent);
+}
+
+#[test]
+fn reset_forgets_the_previous_band() {
+    let mut classifier = classifier();
+    classifier.classify(850);
+
+    classifier.reset();
+
+    assert_eq!(classifier.level(), None);
+    assert_eq!(classifier.classify(780), Co2Level::Good);
+}
+
+#[test]
+fn zero_hysteresis_follows_the_thresholds() {
+    let mut classifier = Co2Classifier::new(Co2Thresholds::default(), 0);
+    classifier.classify(801);
+
+    assert_eq!(classifier.classify(800), Co2Level::Good);
+}
@@ -0,0 +1,100 @@
+use airquamon_domain::derived::{
+    absolute_humidity, dew_point, heat_index, humidex, Comfort, ComfortZone, DerivedMetrics,
+};
+use airquamon_domain::Data;
+
+fn assert_close(actual: f32, expected: f32, tolerance: f32) {
+    assert!(
+        (actual - expected).abs() <= tolerance,
+        "expected {expected} ± {tolerance}, got {actual}"
+    );
+}
+
+fn fahrenheit_to_celsius(fahrenheit: f32) -> f32 {
+    (fahrenheit - 32.0) * 5.0 / 9.0
+}
+
+#[test]
+fn dew_point_matches_reference_table() {
+    // (temperature °C, relative humidity %, dew point °C)
+    let table = [
+        (0.0, 50.0, -9.2),
+        (10.0, 100.0, 10.0),
+        (20.0, 50.0, 9.3),
+        (25.0, 60.0, 16.7),
+        (30.0, 80.0, 26.2),
+    ];
+    for (temperature, humidity, expected) in table {
+        assert_close(dew_point(temperature, humidity), expected, 0.1);
+    }
+}
+
+#[test]
+fn absolute_humidity_matches_reference_table() {
+    // (temperature °C, relative humidity %, absolute humidity g/m³)
+    let table = [
+        (0.0, 100.0, 4.85),
+        (20.0, 50.0, 8.65),
+        (25.0, 60.0, 13.8),
+        (30.0, 80.0, 24.3),
+    ];
+    for (temperature, humidity, expected) in table {
+        assert_close(absolute_humidity(temperature, humidity), expected, 0.1);
+    }
+}
+
+#[test]
+fn humidex_matches_environment_canada_example() {
+    // 30 °C with a dew point of 15 °C has a humidex of 34
+    let humidity = 100.0 * 17.05 / 42.43;
+    assert_close(dew_point(30.0, humidity), 15.0, 0.1);
+    assert_close(humidex(30.0, humidity), 34.0, 0.5);
+}
+
+#[test]
+fn heat_index_matches_nws_chart() {
+    // (temperature °F, relative humidity %, heat index °F)
+    let table = [
+        (80.0, 40.0, 80.0),
+        (90.0, 40.0, 91.0),
+        (100.0, 40.0, 109.0),
+        (86.0, 50.0, 88.0),
+        (90.0, 50.0, 95.0),
+        (96.0, 50.0, 108.0),
+    ];
+    for (temperature, humidity, expected) in table {
+        assert_close(
+            heat_index(fahrenheit_to_celsius(temperature), humidity),
+            fahrenheit_to_celsius(expected),
+            0.6,
+        );
+    }
+}
+
+#[test]
+fn heat_index_is_close_to_temperature_when_mild() {
+    assert_close(heat_index(21.0, 50.0), 21.0, 1.0);
+}
+
+#[test]
+fn comfort_zone_classification() {
+    let zone = ComfortZone::default();
+    assert_eq!(zone.classify(22.0, 45.0), Comfort::Comfortable);
+    assert_eq!(zone.classify(18.0, 45.0), Comfort::TooCold);
+    assert_eq!(zone.classify(28.0, 20.0), Comfort::TooWarm);
+    assert_eq!(zone.classify(22.0, 20.0), Comfort::TooDry);
+    assert_eq!(zone.classify(22.0, 70.0), Comfort::TooHumid);
+}
+
+#[test]
+fn derived_metrics_from_data() {
+    let data = Data {
+        co2: 600,
+        temperature: 20.0,
+        humidity: 50.0,
+    };
+    let metrics = DerivedMetrics::from(&data);
+    assert_close(metrics.dew_point, 9.3, 0.1);
+    assert_close(metrics.absolute_humidity, 8.65, 0.1);
+    assert_eq!(metrics.comfort, Comfort::Comfortable);
+}
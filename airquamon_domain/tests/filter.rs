@@ -0,0 +1,176 @@
+use airquamon_domain::filter::{Ema, Filter, Median, SpikeRejection};
+use airquamon_domain::{
+    Data, Field, GasIndices, Measurement, ParticulateMatter, Quality, SourceId, Timestamp,
+};
+
+fn assert_close(actual: f32, expected: f32, tolerance: f32) {
+    assert!(
+        (actual - expected).abs() <= tolerance,
+        "expected {expected} ± {tolerance}, got {actual}"
+    );
+}
+
+fn measurement(co2: u16, temperature: f32, humidity: f32) -> Measurement {
+    Measurement::new(
+        Timestamp::from_millis(0),
+        SourceId::UNKNOWN,
+        Data {
+            co2,
+            temperature,
+            humidity,
+        },
+    )
+}
+
+/// CO2 of each reading after filtering.
+fn filter_co2(filter: &mut impl Filter, readings: &[u16]) -> Vec<u16> {
+    readings
+        .iter()
+        .map(|&co2| filter.filter(measurement(co2, 20.0, 40.0)).data.co2)
+        .collect()
+}
+
+#[test]
+fn ema_smooths_towards_new_readings() {
+    let mut ema = Ema::new(0.5);
+
+    assert_eq!(
+        filter_co2(&mut ema, &[800, 900, 900, 900]),
+        [800, 850, 875, 888]
+    );
+}
+
+#[test]
+fn ema_with_alpha_1_passes_readings_through() {
+    let mut ema = Ema::new(1.0);
+
+    assert_eq!(filter_co2(&mut ema, &[800, 900, 700]), [800, 900, 700]);
+}
+
+#[test]
+fn ema_clamps_alpha() {
+    assert_eq!(Ema::new(2.0).alpha(), 1.0);
+    assert_eq!(Ema::new(-1.0).alpha(), 0.0);
+}
+
+#[test]
+fn median_removes_single_spikes() {
+    let mut median = Median::<3>::new();
+
+    assert_eq!(
+        filter_co2(&mut median, &[800, 810, 1500, 820, 830]),
+        [800, 805, 810, 820, 830]
+    );
+}
+
+#[test]
+fn median_follows_steps_after_half_the_window() {
+    let mut median = Median::<3>::new();
+
+    assert_eq!(
+        filter_co2(&mut median, &[800, 800, 800, 1000, 1000, 1000]),
+        [800, 800, 800, 800, 1000, 1000]
+    );
+}
+
+#[test]
+fn spike_rejection_holds_the_previous_value_for_single_spikes() {
+    let mut spikes = SpikeRejection::new();
+
+    assert_eq!(
+        filter_co2(&mut spikes, &[800, 1500, 810, 820]),
+        [800, 800, 810, 820]
+    );
+}
+
+#[test]
+fn spike_rejection_follows_persistent_jumps() {
+    let mut spikes = SpikeRejection::new().with_max_rejections(2);
+
+    assert_eq!(
+        filter_co2(&mut spikes, &[800, 1500, 1500, 1500, 1510]),
+        [800, 800, 800, 1500, 1510]
+    );
+}
+
+#[test]
+fn spike_rejection_limits_are_configurable_per_field() {
+    let mut spikes = SpikeRejection::new()
+        .with_max_change(Field::Co2, f32::INFINITY)
+        .with_max_change(Field::Temperature, 0.5);
+
+    spikes.filter(measurement(800, 20.0, 40.0));
+    let filtered = spikes.filter(measurement(1500, 21.0, 45.0));
+
+    assert_eq!(filtered.data.co2, 1500);
+    assert_eq!(filtered.data.temperature, 20.0);
+    assert_eq!(filtered.data.humidity, 45.0);
+    assert_eq!(spikes.max_change(Field::Humidity), 10.0);
+}
+
+#[test]
+fn filters_every_field() {
+    let mut ema = Ema::new(0.5);
+    let reading = |value: f32| {
+        let mut measurement = measurement(value as u16, value, value);
+        measurement.particulate_matter = Some(ParticulateMatter {
+            pm1_0: value,
+            pm2_5: value,
+            pm10: value,
+        });
+        measurement.gas_indices = Some(GasIndices {
+            voc: value as u16,
+            nox: value as u16,
+        });
+        measurement.pressure = Some(value);
+        measurement
+    };
+
+    ema.filter(reading(10.0));
+    let filtered = ema.filter(reading(20.0));
+
+    for field in Field::ALL {
+        assert_eq!(filtered.value(field), Some(15.0), "{field:?}");
+    }
+}
+
+#[test]
+fn leaves_missing_fields_alone() {
+    let mut ema = Ema::new(0.5);
+    ema.filter(measurement(800, 20.0, 40.0));
+
+    let mut reading = measurement(0, 30.0, 50.0);
+    reading.quality.co2 = Quality::Missing;
+    let filtered = ema.filter(reading);
+
+    assert_eq!(filtered.data.co2, 0);
+    assert_eq!(filtered.quality.co2, Quality::Missing);
+    assert_eq!(filtered.particulate_matter, None);
+    assert_eq!(filtered.pressure, None);
+    assert_close(filtered.data.temperature, 25.0, 1e-4);
+
+    // the missing reading didn't pull the average towards 0
+    let filtered = ema.filter(measurement(800, 30.0, 50.0));
+    assert_eq!(filtered.data.co2, 800);
+}
+
+#[test]
+fn filters_compose() {
+    let mut pipeline = SpikeRejection::new()
+        .then(Median::<3>::new())
+        .then(Ema::new(0.5));
+
+    let filtered = filter_co2(&mut pipeline, &[800, 2000, 800, 800]);
+
+    assert_eq!(filtered, [800, 800, 800, 800]);
+}
+
+#[test]
+fn reset_forgets_previous_readings() {
+    let mut pipeline = Median::<3>::new().then(Ema::new(0.1));
+    filter_co2(&mut pipeline, &[400, 400, 400]);
+
+    pipeline.reset();
+
+    assert_eq!(filter_co2(&mut pipeline, &[1000]), [1000]);
+}
@@ -0,0 +1,199 @@
+use airquamon_domain::{
+    Data, Field, History, Measurement, Quality, SourceId, Statistics, Timestamp, Window,
+};
+
+fn assert_close(actual: f32, expected: f32, tolerance: f32) {
+    assert!(
+        (actual - expected).abs() <= tolerance,
+        "expected {expected} ± {tolerance}, got {actual}"
+    );
+}
+
+/// A measurement taken `minute` minutes after boot.
+fn measurement(minute: u64, co2: u16) -> Measurement {
+    Measurement::new(
+        Timestamp::from_millis(minute * 60_000),
+        SourceId::UNKNOWN,
+        Data {
+            co2,
+            temperature: 20.0,
+            humidity: 40.0,
+        },
+    )
+}
+
+fn history<const N: usize>(co2: &[u16]) -> History<N> {
+    let mut history = History::new();
+    for (minute, &co2) in co2.iter().enumerate() {
+        history.push(measurement(minute as u64, co2));
+    }
+    history
+}
+
+#[test]
+fn empty_history_has_no_statistics() {
+    let history = History::<4>::new();
+
+    assert!(history.is_empty());
+    assert_eq!(history.latest(), None);
+    assert_eq!(history.statistics(Field::Co2, Window::All), None);
+    assert_eq!(history.percentile(Field::Co2, Window::All, 50.0), None);
+}
+
+#[test]
+fn statistics_over_windows() {
+    let history = history::<8>(&[400, 800, 600, 500, 700]);
+
+    // (window, count, min, max, mean)
+    let table = [
+        (Window::All, 5, 400.0, 800.0, 600.0),
+        (Window::Last(2), 2, 500.0, 700.0, 600.0),
+        (Window::Last(10), 5, 400.0, 800.0, 600.0),
+        (
+            Window::Since(Timestamp::from_millis(120_000)),
+            3,
+            500.0,
+            700.0,
+            600.0,
+        ),
+        (
+            Window::Since(Timestamp::from_millis(90_000)),
+            3,
+            500.0,
+            700.0,
+            600.0,
+        ),
+    ];
+    for (window, count, min, max, mean) in table {
+        assert_eq!(
+            history.statistics(Field::Co2, window),
+            Some(Statistics {
+                count,
+                min,
+                max,
+                mean
+            }),
+            "{window:?}"
+        );
+    }
+    assert_eq!(history.statistics(Field::Co2, Window::Last(0)), None);
+    assert_eq!(
+        history.statistics(Field::Co2, Window::Since(Timestamp::from_millis(600_000))),
+        None
+    );
+}
+
+#[test]
+fn percentile_interpolates_between_ranks() {
+    let history = history::<8>(&[500, 100, 400, 200, 300]);
+
+    // (percentile, CO2 ppm)
+    let table = [
+        (0.0, 100.0),
+        (25.0, 200.0),
+        (50.0, 300.0),
+        (90.0, 460.0),
+        (100.0, 500.0),
+        (-10.0, 100.0),
+        (150.0, 500.0),
+    ];
+    for (percentile, expected) in table {
+        let actual = history
+            .percentile(Field::Co2, Window::All, percentile)
+            .unwrap();
+        assert_close(actual, expected, 1e-3);
+    }
+}
+
+#[test]
+fn median_of_an_even_number_of_values_is_the_mean_of_the_middle_two() {
+    let history = history::<8>(&[400, 1000, 600, 500]);
+
+    assert_eq!(history.median(Field::Co2, Window::All), Some(550.0));
+    assert_eq!(history.median(Field::Co2, Window::Last(1)), Some(500.0));
+}
+
+#[test]
+fn overwrites_the_oldest_measurements_once_full() {
+    let history = history::<3>(&[400, 500, 600, 700, 800]);
+
+    assert_eq!(history.len(), 3);
+    assert_eq!(history.capacity(), 3);
+    assert_eq!(history.latest().unwrap().data.co2, 800);
+    assert_eq!(
+        history.values(Field::Co2, Window::All).collect::<Vec<_>>(),
+        [600.0, 700.0, 800.0]
+    );
+    assert_eq!(history.min(Field::Co2, Window::All), Some(600.0));
+    assert_eq!(history.max(Field::Co2, Window::Last(2)), Some(800.0));
+    assert_eq!(
+        history.percentile(Field::Co2, Window::All, 0.0),
+        Some(600.0)
+    );
+}
+
+#[test]
+fn clear_removes_all_measurements() {
+    let mut history = history::<3>(&[400, 500]);
+
+    history.clear();
+
+    assert!(history.is_empty());
+    assert_eq!(history.mean(Field::Co2, Window::All), None);
+}
+
+#[test]
+fn skips_values_that_are_not_valid() {
+    let mut history = History::<8>::new();
+    for (minute, (co2, quality)) in [
+        (500, Quality::Valid),
+        (10_000, Quality::Missing),
+        (400, Quality::WarmingUp),
+        (600, Quality::Valid),
+        (600, Quality::Stale),
+        (65_535, Quality::Suspect),
+    ]
+    .into_iter()
+    .enumerate()
+    {
+        let mut measurement = measurement(minute as u64, co2);
+        measurement.quality.co2 = quality;
+        history.push(measurement);
+    }
+
+    assert_eq!(
+        history.statistics(Field::Co2, Window::All),
+        Some(Statistics {
+            count: 2,
+            min: 500.0,
+            max: 600.0,
+            mean: 550.0
+        })
+    );
+    assert_eq!(history.median(Field::Co2, Window::All), Some(550.0));
+    assert_eq!(history.statistics(Field::Co2, Window::Last(1)), None);
+    // the other fields of the same measurements are still valid
+    assert_eq!(
+        history
+            .statistics(Field::Temperature, Window::All)
+            .unwrap()
+            .count,
+        6
+    );
+}
+
+#[test]
+fn skips_fields_the_sensor_does_not_measure() {
+    let mut history = history::<4>(&[500, 600]);
+    let mut measurement = measurement(2, 700);
+    measurement.pressure = Some(1013.0);
+    history.push(measurement);
+
+    assert_eq!(
+        history
+            .values(Field::Pressure, Window::All)
+            .collect::<Vec<_>>(),
+        [1013.0]
+    );
+    assert_eq!(history.statistics(Field::Pm2_5, Window::All), None);
+}
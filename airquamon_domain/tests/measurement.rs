@@ -0,0 +1,190 @@
+use airquamon_domain::{
+    Data, Field, GasIndices, Measurement, ParticulateMatter, Quality, SourceId, Timestamp,
+};
+use core::time::Duration;
+
+fn measurement(co2: u16, temperature: f32, humidity: f32) -> Measurement {
+    Measurement::new(
+        Timestamp::from_millis(60_000),
+        SourceId::UNKNOWN,
+        Data {
+            co2,
+            temperature,
+            humidity,
+        },
+    )
+}
+
+#[test]
+fn validation_marks_values_that_cant_be_real_missing() {
+    for value in [f32::NAN, f32::INFINITY, f32::NEG_INFINITY] {
+        let validated = measurement(800, value, value).validated();
+
+        assert_eq!(validated.quality.temperature, Quality::Missing);
+        assert_eq!(validated.quality.humidity, Quality::Missing);
+        assert_eq!(validated.temperature(), None);
+        assert_eq!(validated.quality.co2, Quality::Valid);
+    }
+}
+
+#[test]
+fn validation_marks_values_out_of_range_suspect() {
+    // (co2, temperature, humidity, co2 quality, temperature quality, humidity quality)
+    let table = [
+        (
+            300,
+            -40.0,
+            0.0,
+            Quality::Valid,
+            Quality::Valid,
+            Quality::Valid,
+        ),
+        (
+            40_000,
+            85.0,
+            100.0,
+            Quality::Valid,
+            Quality::Valid,
+            Quality::Valid,
+        ),
+        (
+            299,
+            -40.1,
+            -0.1,
+            Quality::Suspect,
+            Quality::Suspect,
+            Quality::Suspect,
+        ),
+        (
+            40_001,
+            85.1,
+            100.1,
+            Quality::Suspect,
+            Quality::Suspect,
+            Quality::Suspect,
+        ),
+        (
+            0,
+            21.0,
+            40.0,
+            Quality::Suspect,
+            Quality::Valid,
+            Quality::Valid,
+        ),
+        (
+            u16::MAX,
+            21.0,
+            40.0,
+            Quality::Suspect,
+            Quality::Valid,
+            Quality::Valid,
+        ),
+    ];
+    for (co2, temperature, humidity, co2_quality, temperature_quality, humidity_quality) in table {
+        let validated = measurement(co2, temperature, humidity).validated();
+
+        assert_eq!(validated.quality.co2, co2_quality, "{co2}");
+        assert_eq!(
+            validated.quality.temperature, temperature_quality,
+            "{temperature}"
+        );
+        assert_eq!(validated.quality.humidity, humidity_quality, "{humidity}");
+        // suspect values are kept for logging
+        assert_eq!(validated.data.co2, co2);
+    }
+}
+
+#[test]
+fn validation_keeps_worse_qualities() {
+    let mut reading = measurement(0, 21.0, 40.0);
+    reading.quality.co2 = Quality::WarmingUp;
+
+    assert_eq!(reading.validated().quality.co2, Quality::WarmingUp);
+}
+
+#[test]
+fn validation_drops_optional_fields_that_cant_be_real() {
+    let mut reading = measurement(800, 21.0, 40.0);
+    reading.particulate_matter = Some(ParticulateMatter {
+        pm1_0: 1.0,
+        pm2_5: f32::NAN,
+        pm10: 3.0,
+    });
+    reading.pressure = Some(f32::INFINITY);
+
+    let validated = reading.validated();
+
+    assert_eq!(validated.particulate_matter, None);
+    assert_eq!(validated.pressure, None);
+}
+
+#[test]
+fn set_value_rounds_co2_and_gas_indices() {
+    let mut reading = measurement(800, 21.0, 40.0);
+    reading.gas_indices = Some(GasIndices { voc: 100, nox: 1 });
+
+    // (field, value, expected)
+    let table = [
+        (Field::Co2, 812.5, 813.0),
+        (Field::Co2, 812.49, 812.0),
+        (Field::VocIndex, 120.6, 121.0),
+        (Field::NoxIndex, 1.4, 1.0),
+        (Field::Temperature, 21.25, 21.25),
+        (Field::Humidity, 40.5, 40.5),
+    ];
+    for (field, value, expected) in table {
+        reading.set_value(field, value);
+        assert_eq!(reading.value(field), Some(expected), "{field:?}");
+    }
+}
+
+#[test]
+fn set_value_saturates_co2() {
+    let mut reading = measurement(800, 21.0, 40.0);
+
+    reading.set_value(Field::Co2, 70_000.0);
+    assert_eq!(reading.data.co2, u16::MAX);
+    reading.set_value(Field::Co2, -5.0);
+    assert_eq!(reading.data.co2, 0);
+}
+
+#[test]
+fn set_value_leaves_missing_fields_alone() {
+    let mut reading = measurement(800, 21.0, 40.0);
+    reading.quality.co2 = Quality::Missing;
+
+    reading.set_value(Field::Co2, 900.0);
+    reading.set_value(Field::Pressure, 1013.0);
+    reading.set_value(Field::Pm2_5, 5.0);
+
+    assert_eq!(reading.data.co2, 800);
+    assert_eq!(reading.pressure, None);
+    assert_eq!(reading.particulate_matter, None);
+}
+
+#[test]
+fn is_fresh_up_to_the_max_age() {
+    let reading = measurement(800, 21.0, 40.0);
+    let max_age = Duration::from_secs(120);
+
+    // (now in ms, fresh)
+    let table = [
+        (60_000, true),
+        (180_000, true),
+        (180_001, false),
+        // from the future, e.g. after the clock was set back
+        (0, true),
+    ];
+    for (now, fresh) in table {
+        assert_eq!(
+            reading.is_fresh(Timestamp::from_millis(now), max_age),
+            fresh,
+            "{now}"
+        );
+    }
+    assert_eq!(reading.age(Timestamp::from_millis(0)), Duration::ZERO);
+    assert_eq!(
+        reading.age(Timestamp::from_millis(180_000)),
+        Duration::from_secs(120)
+    );
+}
@@ -0,0 +1,164 @@
+use airquamon_domain::{
+    Data, Direction, Field, History, Measurement, SourceId, Timestamp, Trend, TrendEstimator,
+    Window,
+};
+use core::time::Duration;
+
+fn assert_close(actual: f32, expected: f32, tolerance: f32) {
+    assert!(
+        (actual - expected).abs() <= tolerance,
+        "expected {expected} ± {tolerance}, got {actual}"
+    );
+}
+
+/// One reading a minute.
+fn history(co2: &[u16]) -> History<16> {
+    let mut history = History::new();
+    for (minute, &co2) in co2.iter().enumerate() {
+        history.push(Measurement::new(
+            Timestamp::from_millis(minute as u64 * 60_000),
+            SourceId::UNKNOWN,
+            Data {
+                co2,
+                temperature: 20.0,
+                humidity: 40.0,
+            },
+        ));
+    }
+    history
+}
+
+fn trend(direction: Direction, slope: f32, current: f32) -> Trend {
+    Trend {
+        direction,
+        slope,
+        current,
+        samples: 10,
+    }
+}
+
+#[test]
+fn estimates_direction_and_slope() {
+    // (readings, direction, ppm per minute, fitted latest value)
+    let table: [(&[u16], Direction, f32, f32); 5] = [
+        (&[500, 510, 520, 530, 540], Direction::Rising, 10.0, 540.0),
+        (&[900, 880, 860, 840], Direction::Falling, -20.0, 840.0),
+        (&[600, 601, 599, 600, 601], Direction::Stable, 0.1, 600.4),
+        // a single spike barely moves the fit
+        (&[500, 500, 700, 500, 500], Direction::Stable, 0.0, 540.0),
+        (&[700, 703, 706], Direction::Rising, 3.0, 706.0),
+    ];
+    for (readings, direction, slope, current) in table {
+        let trend = TrendEstimator::co2().estimate(&history(readings)).unwrap();
+        assert_eq!(trend.direction, direction, "{readings:?}");
+        assert_close(trend.slope, slope, 1e-3);
+        assert_close(trend.current, current, 1e-2);
+        assert_eq!(trend.samples, readings.len());
+    }
+}
+
+#[test]
+fn only_fits_the_window() {
+    // falling for a long time, rising over the last 10 minutes
+    let readings: Vec<u16> = (0..6)
+        .map(|minute| 1000 - minute * 50)
+        .chain((0..10).map(|minute| 750 + minute * 5))
+        .collect();
+
+    let trend = TrendEstimator::co2().estimate(&history(&readings)).unwrap();
+
+    assert_eq!(trend.direction, Direction::Rising);
+    assert_close(trend.slope, 5.0, 1e-3);
+    assert_eq!(trend.samples, 10);
+}
+
+#[test]
+fn needs_enough_samples() {
+    assert_eq!(TrendEstimator::co2().estimate(&history(&[])), None);
+    assert_eq!(TrendEstimator::co2().estimate(&history(&[500, 600])), None);
+
+    let estimator = TrendEstimator {
+        min_samples: 5,
+        ..TrendEstimator::co2()
+    };
+    assert_eq!(estimator.estimate(&history(&[500, 510, 520, 530])), None);
+}
+
+#[test]
+fn needs_measurements_at_different_times() {
+    let mut history = History::<4>::new();
+    for co2 in [500, 600, 700] {
+        history.push(Measurement::new(
+            Timestamp::from_millis(0),
+            SourceId::UNKNOWN,
+            Data {
+                co2,
+                temperature: 20.0,
+                humidity: 40.0,
+            },
+        ));
+    }
+
+    assert_eq!(TrendEstimator::co2().estimate(&history), None);
+}
+
+#[test]
+fn estimates_other_fields() {
+    let estimator = TrendEstimator {
+        field: Field::Temperature,
+        window: Window::All,
+        tolerance: 0.1,
+        min_samples: 3,
+    };
+
+    let trend = estimator.estimate(&history(&[500, 500, 500])).unwrap();
+
+    assert_eq!(trend.direction, Direction::Stable);
+    assert_close(trend.current, 20.0, 1e-3);
+}
+
+#[test]
+fn time_to_threshold() {
+    // (trend, threshold, expected time)
+    let table = [
+        (trend(Direction::Rising, 10.0, 700.0), 800.0, Some(600)),
+        (trend(Direction::Rising, 10.0, 800.0), 800.0, Some(0)),
+        (trend(Direction::Rising, 10.0, 900.0), 800.0, None),
+        (trend(Direction::Falling, -20.0, 1000.0), 800.0, Some(600)),
+        (trend(Direction::Falling, -20.0, 700.0), 800.0, None),
+        (trend(Direction::Stable, 1.0, 700.0), 800.0, None),
+    ];
+    for (trend, threshold, expected) in table {
+        assert_eq!(
+            trend.time_to_threshold(threshold),
+            expected.map(Duration::from_secs),
+            "{trend:?}"
+        );
+    }
+}
+
+#[test]
+fn time_to_threshold_is_none_when_it_would_never_get_there() {
+    // possible with a tolerance of 0
+    assert_eq!(
+        trend(Direction::Rising, 1e-30, 400.0).time_to_threshold(800.0),
+        None
+    );
+    assert_eq!(
+        trend(Direction::Rising, 0.0, 400.0).time_to_threshold(800.0),
+        None
+    );
+    assert_eq!(
+        trend(Direction::Falling, -0.0, 900.0).time_to_threshold(800.0),
+        None
+    );
+
+    let estimator = TrendEstimator {
+        tolerance: 0.0,
+        ..TrendEstimator::co2()
+    };
+    let trend = estimator
+        .estimate(&history(&[600, 600, 600, 600, 601]))
+        .unwrap();
+    assert!(trend.time_to_threshold(f32::MAX).is_none());
+}
@@ -1,4 +1,4 @@
-use airquamon_domain::Data;
+use airquamon_domain::{Co2Classifier, Data, Measurement, SourceId, Timestamp};
 use clap::Parser;
 use display_themes::Theme;
 #[cfg(feature = "theme1")]
@@ -8,8 +8,12 @@ use display_themes::Theme2 as ThemeImpl;
 #[cfg(feature = "theme3")]
 use display_themes::Theme3 as ThemeImpl;
 use embedded_graphics::prelude::*;
-use embedded_graphics_simulator::{OutputSettingsBuilder, SimulatorDisplay, Window};
+use embedded_graphics_simulator::{
+    OutputSettingsBuilder, SimulatorDisplay, SimulatorEvent, Window,
+};
 use epd_waveshare::color::TriColor;
+use sensor::{ReplayError, ReplaySensor, Sensor};
+use std::{path::PathBuf, thread};
 
 /// Simulate theme 2
 #[derive(Parser, Debug)]
@@ -26,24 +30,86 @@ struct Args {
     /// Relative humidity in %
     #[arg(short = 'r', long, default_value_t = 60.5)]
     humidity: f32,
+
+    /// Replay a recording (CSV with timestamp in ms, CO2, temperature and humidity per line)
+    /// instead of showing a single measurement
+    #[arg(long)]
+    replay: Option<PathBuf>,
+
+    /// How many times faster than recorded the replay runs
+    #[arg(short, long, default_value_t = 60.0, value_parser = parse_speed)]
+    speed: f32,
+
+    /// Start the replay again once the recording ends
+    #[arg(short, long = "loop")]
+    looping: bool,
+}
+
+fn parse_speed(speed: &str) -> Result<f32, String> {
+    let speed: f32 = speed.parse().map_err(|e| format!("{e}"))?;
+    if speed.is_finite() && speed > 0.0 {
+        Ok(speed)
+    } else {
+        Err("must be a positive number".into())
+    }
 }
 
 fn main() -> Result<(), core::convert::Infallible> {
     let args = Args::parse();
 
     let mut display = SimulatorDisplay::<TriColor>::new(Size::new(296, 128));
+    let mut theme = ThemeImpl::new();
+    let mut co2_classifier = Co2Classifier::default();
+    let output_settings = OutputSettingsBuilder::new().scale(2).build();
 
-    let data = Data {
-        co2: args.co2,
-        temperature: args.temperature,
-        humidity: args.humidity,
+    let Some(path) = args.replay else {
+        let measurement = Measurement::new(
+            Timestamp::default(),
+            SourceId::UNKNOWN,
+            Data {
+                co2: args.co2,
+                temperature: args.temperature,
+                humidity: args.humidity,
+            },
+        );
+        let co2_level = measurement.co2().map(|co2| co2_classifier.classify(co2));
+        theme.draw(&measurement, co2_level, &mut display)?;
+        Window::new("Airquamon Simulator", &output_settings).show_static(&display);
+        return Ok(());
     };
 
-    let mut theme = ThemeImpl::new();
-    theme.draw(&data, &mut display)?;
-
-    let output_settings = OutputSettingsBuilder::new().scale(2).build();
-    Window::new("Airquamon Simulator", &output_settings).show_static(&display);
+    let recording = std::fs::read_to_string(&path).expect("failed reading recording");
+    let mut sensor = ReplaySensor::from_csv(&recording)
+        .with_looping(args.looping)
+        .with_time_scale(1.0 / args.speed);
+    let mut window = Window::new("Airquamon Simulator", &output_settings);
+    let mut previous = Timestamp::default();
 
-    Ok(())
+    loop {
+        match sensor.measure() {
+            Ok(measurement) => {
+                thread::sleep(measurement.timestamp.duration_since(previous));
+                previous = measurement.timestamp;
+                println!("{measurement:?}");
+                display.clear(TriColor::White)?;
+                let co2_level = measurement.co2().map(|co2| co2_classifier.classify(co2));
+                theme.draw(&measurement, co2_level, &mut display)?;
+            }
+            Err(ReplayError::End) => {
+                theme.draw_message("End of recording", &mut display)?;
+                window.show_static(&display);
+                return Ok(());
+            }
+            Err(ReplayError::Parse { line }) => {
+                panic!("{}:{line}: invalid measurement", path.display())
+            }
+        }
+        window.update(&display);
+        if window
+            .events()
+            .any(|event| matches!(event, SimulatorEvent::Quit))
+        {
+            return Ok(());
+        }
+    }
 }
@@ -1,8 +1,16 @@
 #![no_std]
 
-use airquamon_domain::Data;
+use airquamon_domain::{Co2Level, Measurement, Quality};
 use core::fmt;
-use embedded_graphics::prelude::*;
+use embedded_graphics::{
+    mono_font::{
+        iso_8859_1::{FONT_10X20, FONT_6X10},
+        MonoTextStyleBuilder,
+    },
+    prelude::*,
+    text::{Alignment, Baseline, Text, TextStyleBuilder},
+};
+use epd_waveshare::color::TriColor;
 
 mod theme_1;
 pub use theme_1::Theme1;
@@ -17,12 +25,83 @@ pub trait Theme<COLOR>
 where
     COLOR: PixelColor,
 {
+    /// Draws `measurement`, highlighting its CO2 value by `co2_level`. The level is classified
+    /// by the caller, so the display agrees with anything else that acts on it, e.g. alerts.
     fn draw<DRAWTARGET>(
         &mut self,
-        data: &Data,
+        measurement: &Measurement,
+        co2_level: Option<Co2Level>,
         display: &mut DRAWTARGET,
     ) -> Result<(), DRAWTARGET::Error>
     where
         DRAWTARGET: DrawTarget<Color = COLOR> + OriginDimensions,
         DRAWTARGET::Error: fmt::Debug;
+
+    /// Draws a message instead of a measurement, e.g. to report a fault.
+    fn draw_message<DRAWTARGET>(
+        &mut self,
+        message: &str,
+        display: &mut DRAWTARGET,
+    ) -> Result<(), DRAWTARGET::Error>
+    where
+        DRAWTARGET: DrawTarget<Color = COLOR> + OriginDimensions,
+        DRAWTARGET::Error: fmt::Debug;
+}
+
+/// Centered message shared by the themes.
+fn draw_message<DRAWTARGET>(
+    message: &str,
+    display: &mut DRAWTARGET,
+) -> Result<(), DRAWTARGET::Error>
+where
+    DRAWTARGET: DrawTarget<Color = TriColor> + OriginDimensions,
+{
+    display.clear(TriColor::White)?;
+
+    let style = MonoTextStyleBuilder::new()
+        .font(&FONT_10X20)
+        .text_color(TriColor::Chromatic)
+        .background_color(TriColor::White)
+        .build();
+    let text_style = TextStyleBuilder::new()
+        .alignment(Alignment::Center)
+        .baseline(Baseline::Middle)
+        .build();
+
+    Text::with_text_style(message, display.bounding_box().center(), style, text_style)
+        .draw(display)?;
+    Ok(())
+}
+
+/// Notice at the bottom of the display when the sensor's readings can't be trusted,
+/// e.g. right after it started.
+fn draw_status<DRAWTARGET>(
+    measurement: &Measurement,
+    display: &mut DRAWTARGET,
+) -> Result<(), DRAWTARGET::Error>
+where
+    DRAWTARGET: DrawTarget<Color = TriColor> + OriginDimensions,
+{
+    let status = if measurement.quality.contains(Quality::WarmingUp) {
+        "warming up"
+    } else if measurement.quality.contains(Quality::Stale) {
+        "sensor stale"
+    } else {
+        return Ok(());
+    };
+
+    let style = MonoTextStyleBuilder::new()
+        .font(&FONT_6X10)
+        .text_color(TriColor::Chromatic)
+        .background_color(TriColor::White)
+        .build();
+    let text_style = TextStyleBuilder::new()
+        .alignment(Alignment::Center)
+        .baseline(Baseline::Bottom)
+        .build();
+
+    let size = display.size();
+    let position = Point::new(size.width as i32 / 2, size.height as i32 - 2);
+    Text::with_text_style(status, position, style, text_style).draw(display)?;
+    Ok(())
 }
@@ -1,5 +1,5 @@
 use crate::Theme;
-use airquamon_domain::Data;
+use airquamon_domain::{Co2Level, Measurement};
 use core::fmt;
 use core::fmt::Write;
 use embedded_graphics::{
@@ -26,7 +26,8 @@ impl Theme1 {
 impl Theme<TriColor> for Theme1 {
     fn draw<DRAWTARGET>(
         &mut self,
-        data: &Data,
+        measurement: &Measurement,
+        co2_level: Option<Co2Level>,
         display: &mut DRAWTARGET,
     ) -> Result<(), DRAWTARGET::Error>
     where
@@ -37,7 +38,9 @@ impl Theme<TriColor> for Theme1 {
         write!(
             self.display_text,
             "CO2: {0} ppm | {1:#.2} °C | {2:#.2} %",
-            data.co2, data.temperature, data.humidity
+            OrDashes(measurement.co2()),
+            OrDashes(measurement.temperature()),
+            OrDashes(measurement.humidity())
         )
         .expect("Error occurred while trying to write in String");
         let _ = Line::new(
@@ -49,14 +52,47 @@ impl Theme<TriColor> for Theme1 {
         )
         .into_styled(PrimitiveStyle::with_stroke(TriColor::Chromatic, 4))
         .draw(display);
-        draw_text(display, &self.display_text, 5, 10)?;
-        Ok(())
+        let text_color = if co2_level.is_some_and(Co2Level::is_bad) {
+            TriColor::Chromatic
+        } else {
+            TriColor::Black
+        };
+        draw_text(display, &self.display_text, text_color, 5, 10)?;
+        crate::draw_status(measurement, display)
+    }
+
+    fn draw_message<DRAWTARGET>(
+        &mut self,
+        message: &str,
+        display: &mut DRAWTARGET,
+    ) -> Result<(), DRAWTARGET::Error>
+    where
+        DRAWTARGET: DrawTarget<Color = TriColor> + OriginDimensions,
+        DRAWTARGET::Error: fmt::Debug,
+    {
+        crate::draw_message(message, display)
+    }
+}
+
+/// Formats a missing value as `--`.
+struct OrDashes<T>(Option<T>);
+
+impl<T> fmt::Display for OrDashes<T>
+where
+    T: fmt::Display,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.0 {
+            Some(value) => value.fmt(f),
+            None => f.write_str("--"),
+        }
     }
 }
 
 fn draw_text<DRAWTARGET>(
     display: &mut DRAWTARGET,
     text: &str,
+    color: TriColor,
     x: i32,
     y: i32,
 ) -> Result<(), DRAWTARGET::Error>
@@ -65,7 +101,7 @@ where
 {
     let style = MonoTextStyleBuilder::new()
         .font(&FONT_8X13_BOLD)
-        .text_color(TriColor::Black)
+        .text_color(color)
         .background_color(TriColor::White)
         .build();
 
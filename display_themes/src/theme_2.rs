@@ -1,5 +1,5 @@
 use crate::Theme;
-use airquamon_domain::Data;
+use airquamon_domain::{Co2Level, Measurement};
 use core::fmt;
 use core::fmt::Write;
 use embedded_graphics::{
@@ -45,7 +45,7 @@ impl<T> View for Value<T> {
     }
 }
 
-struct CO2(u16);
+struct CO2(Option<u16>, Option<Co2Level>);
 
 impl Value<CO2> {
     fn new(value: CO2, position: Point, size: Size) -> Self {
@@ -68,7 +68,7 @@ impl Drawable for Value<CO2> {
         let border = self.bounds.into_styled(border_style);
         border.draw(display)?;
 
-        let value_text_color = if self.value.0 > 800 {
+        let value_text_color = if self.value.1.is_some_and(Co2Level::is_bad) {
             TriColor::Chromatic
         } else {
             TriColor::Black
@@ -80,9 +80,12 @@ impl Drawable for Value<CO2> {
             .background_color(TriColor::White)
             .build();
 
-        let mut value_text: String<4> = String::new();
-        write!(value_text, "{0}", self.value.0)
-            .expect("Error occurred while trying to write in String");
+        let mut value_text: String<5> = String::new();
+        match self.value.0 {
+            Some(co2) => write!(value_text, "{0}", co2),
+            None => write!(value_text, "--"),
+        }
+        .expect("Error occurred while trying to write in String");
 
         let text = Text::with_alignment(
             &value_text,
@@ -115,7 +118,7 @@ impl Drawable for Value<CO2> {
     }
 }
 
-struct Temperature(f32);
+struct Temperature(Option<f32>);
 
 impl Value<Temperature> {
     fn new(value: Temperature, position: Point, size: Size) -> Self {
@@ -144,9 +147,12 @@ impl Drawable for Value<Temperature> {
             .background_color(TriColor::White)
             .build();
 
-        let mut value_text: String<4> = String::new();
-        write!(value_text, "{0:#.1}", self.value.0)
-            .expect("Error occurred while trying to write in String");
+        let mut value_text: String<5> = String::new();
+        match self.value.0 {
+            Some(value) => write!(value_text, "{0:#.1}", value),
+            None => write!(value_text, "--"),
+        }
+        .expect("Error occurred while trying to write in String");
 
         let text = Text::with_alignment(
             &value_text,
@@ -176,7 +182,7 @@ impl Drawable for Value<Temperature> {
     }
 }
 
-struct Humidity(f32);
+struct Humidity(Option<f32>);
 
 impl Value<Humidity> {
     fn new(value: Humidity, position: Point, size: Size) -> Self {
@@ -205,9 +211,12 @@ impl Drawable for Value<Humidity> {
             .background_color(TriColor::White)
             .build();
 
-        let mut value_text: String<4> = String::new();
-        write!(value_text, "{0:#.1}", self.value.0)
-            .expect("Error occurred while trying to write in String");
+        let mut value_text: String<5> = String::new();
+        match self.value.0 {
+            Some(value) => write!(value_text, "{0:#.1}", value),
+            None => write!(value_text, "--"),
+        }
+        .expect("Error occurred while trying to write in String");
 
         let text = Text::with_alignment(
             &value_text,
@@ -242,7 +251,8 @@ impl Drawable for Value<Humidity> {
 impl Theme<TriColor> for Theme2 {
     fn draw<DRAWTARGET>(
         &mut self,
-        data: &Data,
+        measurement: &Measurement,
+        co2_level: Option<Co2Level>,
         display: &mut DRAWTARGET,
     ) -> Result<(), DRAWTARGET::Error>
     where
@@ -255,10 +265,14 @@ impl Theme<TriColor> for Theme2 {
 
         let box_size = Size::new(80, 80);
 
-        let co2 = Value::<CO2>::new(CO2(data.co2), Point::zero(), box_size);
-        let temperature =
-            Value::<Temperature>::new(Temperature(data.temperature), Point::zero(), box_size);
-        let humidity = Value::<Humidity>::new(Humidity(data.humidity), Point::zero(), box_size);
+        let co2 = Value::<CO2>::new(CO2(measurement.co2(), co2_level), Point::zero(), box_size);
+        let temperature = Value::<Temperature>::new(
+            Temperature(measurement.temperature()),
+            Point::zero(),
+            box_size,
+        );
+        let humidity =
+            Value::<Humidity>::new(Humidity(measurement.humidity()), Point::zero(), box_size);
 
         LinearLayout::horizontal(Chain::new(co2).append(temperature).append(humidity))
             // .with_spacing(FixedMargin(4))
@@ -266,6 +280,18 @@ impl Theme<TriColor> for Theme2 {
             .align_to(&display_area, horizontal::Center, vertical::Center)
             .draw(display)?;
 
-        Ok(())
+        crate::draw_status(measurement, display)
+    }
+
+    fn draw_message<DRAWTARGET>(
+        &mut self,
+        message: &str,
+        display: &mut DRAWTARGET,
+    ) -> Result<(), DRAWTARGET::Error>
+    where
+        DRAWTARGET: DrawTarget<Color = TriColor> + OriginDimensions,
+        DRAWTARGET::Error: fmt::Debug,
+    {
+        crate::draw_message(message, display)
     }
 }
@@ -1,6 +1,6 @@
 #![no_std]
 
-use airquamon_domain::Data;
+use airquamon_domain::{Co2Level, Measurement};
 use core::fmt;
 use display_themes::Theme;
 use embedded_graphics::prelude::*;
@@ -65,7 +65,14 @@ where
 pub trait DisplayTheme {
     type Error;
 
-    fn draw(&mut self, data: &Data) -> Result<(), Self::Error>;
+    /// Draws `measurement` with its CO2 value highlighted by `co2_level`.
+    fn draw(
+        &mut self,
+        measurement: &Measurement,
+        co2_level: Option<Co2Level>,
+    ) -> Result<(), Self::Error>;
+
+    fn draw_message(&mut self, message: &str) -> Result<(), Self::Error>;
 }
 
 impl<SPI, EPD, DRAWTARGET, DELAY, THEME> DisplayTheme
@@ -81,8 +88,25 @@ where
 {
     type Error = SPI::Error;
 
-    fn draw(&mut self, data: &Data) -> Result<(), Self::Error> {
-        let _ = self.theme.draw(data, &mut self.draw_target);
+    fn draw(
+        &mut self,
+        measurement: &Measurement,
+        co2_level: Option<Co2Level>,
+    ) -> Result<(), Self::Error> {
+        let _ = self
+            .theme
+            .draw(measurement, co2_level, &mut self.draw_target);
+        draw_to_epd(
+            &mut self.spi,
+            &mut self.epd,
+            &mut self.draw_target,
+            &mut self.delay,
+        )?;
+        Ok(())
+    }
+
+    fn draw_message(&mut self, message: &str) -> Result<(), Self::Error> {
+        let _ = self.theme.draw_message(message, &mut self.draw_target);
         draw_to_epd(
             &mut self.spi,
             &mut self.epd,
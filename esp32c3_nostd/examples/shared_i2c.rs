@@ -0,0 +1,70 @@
+#![no_std]
+#![no_main]
+
+use esp_backtrace as _;
+
+use airquamon_domain::Timestamp;
+use core::{cell::RefCell, time::Duration};
+use embedded_hal_bus::i2c::RefCellDevice;
+use esp32c3_hal::{
+    clock::ClockControl, gpio::IO, i2c::I2C, peripherals::Peripherals, prelude::*,
+    systimer::SystemTimer, Delay,
+};
+use log::{info, warn};
+use sensor::{Bmx280Sensor, MeasurementMode, PressureCompensated, Scd4xSensor, Sensor, StartUp};
+
+/// The weather changes the pressure slowly, updating the SCD41 every 10 minutes is plenty.
+const PRESSURE_INTERVAL: Duration = Duration::from_secs(10 * 60);
+
+#[entry]
+fn main() -> ! {
+    let peripherals = Peripherals::take();
+    let system = peripherals.SYSTEM.split();
+    let clocks = ClockControl::boot_defaults(system.clock_control).freeze();
+    let mut delay = Delay::new(&clocks);
+
+    esp_println::logger::init_logger_from_env();
+    info!("Logger is setup");
+
+    let io = IO::new(peripherals.GPIO, peripherals.IO_MUX);
+    let i2c_scl = io.pins.gpio0;
+    let i2c_sda = io.pins.gpio1;
+
+    // both sensors share the bus, each gets its own device borrowing it
+    let i2c = RefCell::new(I2C::new(
+        peripherals.I2C0,
+        i2c_sda,
+        i2c_scl,
+        100u32.kHz(),
+        &clocks,
+    ));
+
+    let mut co2_sensor = Scd4xSensor::new(RefCellDevice::new(&i2c), delay, uptime);
+    match co2_sensor.start_up() {
+        Ok(sensor_info) => info!("CO2 sensor started: {:?}", sensor_info),
+        Err(e) => warn!("CO2 sensor failed starting up: {:?}", e),
+    }
+    co2_sensor
+        .set_mode(MeasurementMode::SingleShot)
+        .expect("failed setting sensor measurement mode");
+
+    let mut pressure_sensor = Bmx280Sensor::new(RefCellDevice::new(&i2c), delay, uptime);
+    match pressure_sensor.start_up() {
+        Ok(sensor_info) => info!("Pressure sensor started: {:?}", sensor_info),
+        Err(e) => warn!("Pressure sensor failed starting up: {:?}", e),
+    }
+
+    let mut sensor = PressureCompensated::new(co2_sensor, pressure_sensor, PRESSURE_INTERVAL);
+
+    loop {
+        match sensor.measure() {
+            Ok(measurement) => info!("{:?}", measurement),
+            Err(e) => warn!("failed reading sensor: {:?}", e),
+        }
+        delay.delay_ms(60000u32);
+    }
+}
+
+fn uptime() -> Timestamp {
+    Timestamp::from_millis(SystemTimer::now() * 1000 / SystemTimer::TICKS_PER_SECOND)
+}
@@ -1,7 +1,15 @@
 #![no_std]
 #![no_main]
 
-use core::cell::RefCell;
+use airquamon_domain::{
+    filter::{Ema, Filter, Median, SpikeRejection},
+    Co2Classifier, Co2Level, Co2Thresholds, Field, History, Timestamp, TrendEstimator, Window,
+};
+use core::{
+    cell::RefCell,
+    fmt::{Arguments, Debug, Write},
+    time::Duration,
+};
 use critical_section::Mutex;
 use display_themes::Theme2;
 use epd_display::{Display, DisplayTheme};
@@ -21,15 +29,73 @@ use esp32c3_hal::{
         master::{Spi, SpiBusController},
         SpiMode,
     },
+    systimer::SystemTimer,
     Delay,
 };
 use esp_backtrace as _;
-use log::info;
-use sensor::{MockSensor, Scd4xSensor, Sensor};
+use heapless::String;
+use log::{info, warn};
+use sensor::{
+    Calibrate, Compensation, MeasurementMode, MockSensor, PowerManagement, PowerState, Retry,
+    RetryPolicy, Scd4xSensor, Sensor, StabilityMonitor, StabilityPolicy, StartUp,
+};
+#[cfg(feature = "fault-injection")]
+use sensor::{FaultInjector, FaultRates};
+
+/// Settings read from `cfg.toml` at build time, see `cfg.toml.example`.
+#[toml_cfg::toml_config]
+pub struct Config {
+    #[default(4.0)]
+    sensor_temperature_offset: f32,
+    #[default(0)]
+    sensor_altitude: u16,
+    // 0 if unknown, the altitude is used instead
+    #[default(0)]
+    sensor_ambient_pressure: u16,
+    #[default(false)]
+    sensor_persist_settings: bool,
+    #[default(true)]
+    sensor_automatic_self_calibration: bool,
+    // CO2 concentration in ppm of the fresh air used for forced recalibration
+    #[default(420)]
+    calibration_reference_co2: u16,
+    // power the sensor down between measurements, e.g. on battery
+    #[default(false)]
+    sensor_power_down: bool,
+    // inclusive upper bounds in ppm of the CO2 bands, anything above good is bad air
+    #[default(600)]
+    co2_excellent: u16,
+    #[default(800)]
+    co2_good: u16,
+    #[default(1000)]
+    co2_moderate: u16,
+    #[default(1500)]
+    co2_poor: u16,
+    #[default(50)]
+    co2_hysteresis: u16,
+}
 
 static BOOT_BUTTON: Mutex<RefCell<Option<Gpio9<Input<PullDown>>>>> = Mutex::new(RefCell::new(None));
 static BUTTON: Mutex<RefCell<Option<Gpio3<Input<PullUp>>>>> = Mutex::new(RefCell::new(None));
 
+/// How long the sensor has to measure in the reference environment before it is recalibrated.
+const CALIBRATION_DURATION: Duration = Duration::from_secs(3 * 60);
+
+/// Faults seen in the field, injected often enough to show up within a few minutes.
+#[cfg(feature = "fault-injection")]
+const FAULT_RATES: FaultRates = FaultRates {
+    error: 0.1,
+    timeout: 0.1,
+    stuck: 0.05,
+    // longer than StabilityPolicy::stale_measurements so frozen readings get flagged stale
+    stuck_measurements: 12,
+    nan_temperature: 0.1,
+    out_of_range_co2: 0.1,
+};
+
+/// One hour of measurements at the 60 s measurement interval.
+const HISTORY_CAPACITY: usize = 60;
+
 #[entry]
 fn main() -> ! {
     let peripherals = Peripherals::take();
@@ -45,15 +111,6 @@ fn main() -> ! {
     info!("Logger is setup");
 
     let io = IO::new(peripherals.GPIO, peripherals.IO_MUX);
-    let i2c_scl = io.pins.gpio0;
-    let i2c_sda = io.pins.gpio1;
-
-    let i2c = I2C::new(peripherals.I2C0, i2c_sda, i2c_scl, 100u32.kHz(), &clocks);
-
-    info!("Connecting to sensor");
-    let mut sensor = Scd4xSensor::new(i2c, delay);
-    // let mut sensor = MockSensor::new(500, 19f32, 69f32);
-
     let mosi = io.pins.gpio4;
     let sck = io.pins.gpio5;
     let cs = io.pins.gpio6.into_push_pull_output();
@@ -61,21 +118,6 @@ fn main() -> ! {
     let rst = io.pins.gpio18.into_push_pull_output();
     let busy = io.pins.gpio19.into_pull_down_input();
 
-    let mut boot_button = io.pins.gpio9.into_pull_down_input();
-    boot_button.listen(Event::FallingEdge);
-
-    let mut button = io.pins.gpio3.into_pull_up_input();
-    button.listen(Event::RisingEdge);
-
-    critical_section::with(|cs| BOOT_BUTTON.borrow_ref_mut(cs).replace(boot_button));
-    critical_section::with(|cs| BUTTON.borrow_ref_mut(cs).replace(button));
-
-    interrupt::enable(peripherals::Interrupt::GPIO, interrupt::Priority::Priority3).unwrap();
-
-    unsafe {
-        riscv::interrupt::enable();
-    }
-
     let spi_controller = SpiBusController::from_spi(Spi::new_no_cs_no_miso(
         peripherals.SPI2,
         sck,
@@ -94,24 +136,264 @@ fn main() -> ! {
     let mut draw_target = Display2in9b::default();
     draw_target.set_rotation(DisplayRotation::Rotate270);
 
+    // the only classifier, its levels feed both the display and the alerts
+    let mut co2_classifier = Co2Classifier::new(
+        Co2Thresholds {
+            excellent: CONFIG.co2_excellent,
+            good: CONFIG.co2_good,
+            moderate: CONFIG.co2_moderate,
+            poor: CONFIG.co2_poor,
+        },
+        CONFIG.co2_hysteresis,
+    );
     let mut display = Display::new(spi, epd, draw_target, delay, Theme2::new());
 
+    let i2c_scl = io.pins.gpio0;
+    let i2c_sda = io.pins.gpio1;
+
+    let i2c = I2C::new(peripherals.I2C0, i2c_sda, i2c_scl, 100u32.kHz(), &clocks);
+
+    info!("Connecting to sensor");
+    let mut sensor = Scd4xSensor::new(i2c, delay, uptime);
+    // configuring a faulty sensor would fail as well, stay on the fault screen until it recovers
+    let mut fault_shown = false;
+    let sensor_info = loop {
+        match sensor.start_up() {
+            Ok(sensor_info) => break sensor_info,
+            Err(e) => {
+                warn!("Sensor failed starting up: {:?}", e);
+                if !fault_shown {
+                    display.draw_message("Sensor fault").expect("draw failed");
+                    fault_shown = true;
+                }
+                delay.delay_ms(60000u32);
+            }
+        }
+    };
+    info!("Sensor started: {:?}", sensor_info);
+    let compensation = Compensation {
+        temperature_offset: CONFIG.sensor_temperature_offset,
+        altitude: CONFIG.sensor_altitude,
+        ambient_pressure: (CONFIG.sensor_ambient_pressure != 0)
+            .then_some(CONFIG.sensor_ambient_pressure),
+    };
+    info!("Configuring sensor compensation: {:?}", compensation);
+    sensor
+        .set_compensation(&compensation, CONFIG.sensor_persist_settings)
+        .expect("failed configuring sensor compensation");
+    let automatic_self_calibration = sensor
+        .automatic_self_calibration()
+        .expect("failed reading sensor automatic self-calibration");
+    if automatic_self_calibration != CONFIG.sensor_automatic_self_calibration {
+        sensor
+            .set_automatic_self_calibration(CONFIG.sensor_automatic_self_calibration)
+            .expect("failed configuring sensor automatic self-calibration");
+    }
+    info!(
+        "Sensor automatic self-calibration enabled: {}",
+        CONFIG.sensor_automatic_self_calibration
+    );
+    // measuring once a minute, the sensor can idle in between
+    if let Err(e) = sensor.set_mode(MeasurementMode::SingleShot) {
+        warn!(
+            "failed setting sensor measurement mode, measuring periodically: {:?}",
+            e
+        );
+    }
+    // let mut sensor = MockSensor::new(uptime, 500, 19f32, 69f32);
+
+    let mut boot_button = io.pins.gpio9.into_pull_down_input();
+    boot_button.listen(Event::FallingEdge);
+
+    let mut button = io.pins.gpio3.into_pull_up_input();
+    button.listen(Event::RisingEdge);
+    // holding the button while booting starts a forced recalibration
+    let calibration_requested = embedded_hal::digital::InputPin::is_low(&button).unwrap();
+
+    critical_section::with(|cs| BOOT_BUTTON.borrow_ref_mut(cs).replace(boot_button));
+    critical_section::with(|cs| BUTTON.borrow_ref_mut(cs).replace(button));
+
+    interrupt::enable(peripherals::Interrupt::GPIO, interrupt::Priority::Priority3).unwrap();
+
+    unsafe {
+        riscv::interrupt::enable();
+    }
+
+    if calibration_requested {
+        // the recalibration needs the sensor to have been measuring periodically
+        match sensor.set_mode(MeasurementMode::Periodic) {
+            Ok(()) => calibrate(&mut sensor, &mut display, CONFIG.calibration_reference_co2),
+            Err(e) => {
+                warn!(
+                    "Calibration: failed setting sensor measurement mode: {:?}",
+                    e
+                );
+                show_message(&mut display, format_args!("Calibration failed"));
+            }
+        }
+        if let Err(e) = sensor.set_mode(MeasurementMode::SingleShot) {
+            warn!(
+                "failed setting sensor measurement mode, measuring periodically: {:?}",
+                e
+            );
+        }
+        // leave the outcome on the display before it shows measurements again
+        delay.delay_ms(30_000u32);
+    }
+    #[cfg(feature = "fault-injection")]
+    let sensor = FaultInjector::new(sensor, FAULT_RATES, SystemTimer::now());
+    let sensor = Retry::new(sensor, delay, RetryPolicy::default());
+    // flags the first readings after start up and readings of a frozen sensor
+    let mut sensor = StabilityMonitor::new(sensor, StabilityPolicy::default());
+    info!(
+        "Sensor supply current: {0} µA active, {1} µA idle, {2} µA powered down",
+        sensor.supply_current(PowerState::Active),
+        sensor.supply_current(PowerState::Idle),
+        sensor.supply_current(PowerState::PowerDown)
+    );
+
+    let mut history = History::<HISTORY_CAPACITY>::new();
+    let co2_trend = TrendEstimator::co2();
+    // the display highlights anything above the good band
+    let bad_air_threshold = co2_classifier.thresholds().bad_air_threshold() as f32;
+    // readings jump around by ±30 ppm, which would redraw the display for nothing
+    let mut smoothing = SpikeRejection::new()
+        .then(Median::<3>::new())
+        .then(Ema::new(0.5));
+
     loop {
-        let data = sensor.measure().expect("failed reading sensor");
+        let measurement = match sensor.measure() {
+            Ok(measurement) => smoothing.filter(measurement.validated()),
+            Err(e) => {
+                warn!("failed reading sensor: {:?}", e);
+                delay.delay_ms(60000u32);
+                continue;
+            }
+        };
+        let data = measurement.data;
 
         info!(
-            "CO2: {0}, Temperature: {1:#.2} °C, Humidity: {2:#.2} RH",
-            data.co2, data.temperature, data.humidity
+            "[{0} ms] CO2: {1}, Temperature: {2:#.2} °C, Humidity: {3:#.2} RH, Quality: {4:?}",
+            measurement.timestamp.as_millis(),
+            data.co2,
+            data.temperature,
+            data.humidity,
+            measurement.quality
         );
 
+        // classified once, so the display and the alerts agree on the level
+        let co2_level = measurement.co2().map(|co2| co2_classifier.classify(co2));
+        if let Some(level) = co2_level.filter(|level| level.is_bad()) {
+            warn!("CO2 level {0:?}, time to ventilate", level);
+        }
+
+        history.push(measurement);
+        if let Some(co2) = history.statistics(Field::Co2, Window::All) {
+            info!(
+                "CO2 over the last {0} measurements: min {1}, max {2}, mean {3:#.0}",
+                co2.count, co2.min, co2.max, co2.mean
+            );
+        }
+        if let Some(trend) = co2_trend.estimate(&history) {
+            info!(
+                "CO2 trend: {0:?} at {1:#.1} ppm/min",
+                trend.direction, trend.slope
+            );
+            let eta = trend
+                .time_to_threshold(bad_air_threshold)
+                .filter(|_| !co2_level.is_some_and(Co2Level::is_bad));
+            if let Some(eta) = eta {
+                warn!(
+                    "CO2 expected to exceed {0} ppm in {1} min",
+                    bad_air_threshold,
+                    eta.as_secs() / 60
+                );
+            }
+        }
+
         info!("updating display");
-        display.draw(&data).expect("draw failed");
+        display.draw(&measurement, co2_level).expect("draw failed");
+
+        if CONFIG.sensor_power_down {
+            // the next measurement wakes the sensor up again
+            if let Err(e) = sensor.set_power_state(PowerState::PowerDown) {
+                warn!("failed powering sensor down: {:?}", e);
+            }
+        }
 
         info!("Sleeping");
         delay.delay_ms(60000u32);
     }
 }
 
+/// Guides through a forced recalibration against fresh air with a known CO2 concentration,
+/// showing the instructions and the outcome on the display.
+fn calibrate<S, D>(sensor: &mut S, display: &mut D, reference: u16)
+where
+    S: Sensor + Calibrate,
+    <S as Sensor>::Error: Debug,
+    <S as Calibrate>::Error: Debug,
+    D: DisplayTheme,
+    D::Error: Debug,
+{
+    info!(
+        "Calibration: place the sensor in fresh air ({0} ppm) for the next {1} minutes",
+        reference,
+        CALIBRATION_DURATION.as_secs() / 60
+    );
+    let start = uptime();
+    let mut minutes_shown = None;
+    loop {
+        let elapsed = uptime().duration_since(start);
+        let Some(remaining) = CALIBRATION_DURATION.checked_sub(elapsed) else {
+            break;
+        };
+        // the display takes seconds to refresh, only count down the minutes
+        let minutes = (remaining.as_secs() + 59) / 60;
+        if minutes_shown != Some(minutes) {
+            show_message(
+                display,
+                format_args!("Fresh air {0} ppm: {1} min", reference, minutes),
+            );
+            minutes_shown = Some(minutes);
+        }
+        match sensor.measure() {
+            Ok(measurement) => info!("Calibration: CO2 {0} ppm", measurement.data.co2),
+            Err(e) => warn!("Calibration: failed reading sensor: {:?}", e),
+        }
+    }
+    match sensor.forced_recalibration(reference) {
+        Ok(correction) => {
+            info!("Calibration: corrected CO2 by {0} ppm", correction);
+            show_message(display, format_args!("Corrected: {0:+} ppm", correction));
+        }
+        Err(e) => {
+            warn!("Calibration: forced recalibration failed: {:?}", e);
+            show_message(display, format_args!("Calibration failed"));
+        }
+    }
+}
+
+/// Draws a short message, the display fits about 29 characters.
+fn show_message<D>(display: &mut D, message: Arguments)
+where
+    D: DisplayTheme,
+    D::Error: Debug,
+{
+    let mut text = String::<32>::new();
+    if write!(text, "{}", message).is_err() {
+        warn!("message too long for the display: {}", message);
+    }
+    if let Err(e) = display.draw_message(&text) {
+        warn!("failed drawing message: {:?}", e);
+    }
+}
+
+/// Monotonic time since boot.
+fn uptime() -> Timestamp {
+    Timestamp::from_millis(SystemTimer::now() * 1000 / SystemTimer::TICKS_PER_SECOND)
+}
+
 #[interrupt]
 fn GPIO() {
     critical_section::with(|cs| {
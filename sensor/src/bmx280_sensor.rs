@@ -0,0 +1,315 @@
+use crate::{ErrorKind, Sensor, SensorError, SensorInfo, SensorKind, StartUp};
+use airquamon_domain::{Clock, Data, Measurement, Quality, SourceId};
+use embedded_hal::{delay::DelayUs, i2c::I2c};
+
+/// SDO pulled low, 0x77 when pulled high.
+pub const BMX280_DEFAULT_ADDRESS: u8 = 0x76;
+
+// Registers from the BMP280 and BME280 datasheets
+const CALIBRATION: u8 = 0x88;
+const CALIBRATION_HUMIDITY_H1: u8 = 0xa1;
+const CHIP_ID: u8 = 0xd0;
+const RESET: u8 = 0xe0;
+const CALIBRATION_HUMIDITY: u8 = 0xe1;
+const CTRL_HUM: u8 = 0xf2;
+const STATUS: u8 = 0xf3;
+const CTRL_MEAS: u8 = 0xf4;
+const DATA: u8 = 0xf7;
+
+const RESET_COMMAND: u8 = 0xb6;
+const CHIP_ID_BMP280: u8 = 0x58;
+const CHIP_ID_BME280: u8 = 0x60;
+const STATUS_MEASURING: u8 = 0x08;
+/// 1x oversampling of temperature and pressure, forced mode.
+const CTRL_MEAS_FORCED: u8 = 0b001 << 5 | 0b001 << 2 | 0b01;
+/// 1x oversampling of humidity.
+const CTRL_HUM_1X: u8 = 0b001;
+/// Longest measurement time with 1x oversampling of everything is 9.3 ms.
+const MEASUREMENT_TIME_MS: u32 = 10;
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum Bmx280Error<E> {
+    I2c(E),
+    /// The chip ID isn't the one of a BMP280 or BME280.
+    UnknownChip(u8),
+    /// The measurement didn't finish in time.
+    Timeout,
+}
+
+impl<E> SensorError for Bmx280Error<E> {
+    fn kind(&self) -> ErrorKind {
+        match self {
+            Bmx280Error::I2c(_) => ErrorKind::Bus,
+            Bmx280Error::UnknownChip(_) => ErrorKind::Other,
+            Bmx280Error::Timeout => ErrorKind::Timeout,
+        }
+    }
+}
+
+/// Factory calibration read from the sensor's non-volatile memory.
+#[derive(Copy, Clone, Debug, Default)]
+struct Calibration {
+    t1: u16,
+    t2: i16,
+    t3: i16,
+    p1: u16,
+    p2: i16,
+    p3: i16,
+    p4: i16,
+    p5: i16,
+    p6: i16,
+    p7: i16,
+    p8: i16,
+    p9: i16,
+    h1: u8,
+    h2: i16,
+    h3: u8,
+    h4: i16,
+    h5: i16,
+    h6: i8,
+}
+
+impl Calibration {
+    /// `buf` starts at the temperature and pressure calibration registers.
+    fn parse(buf: &[u8; 24]) -> Self {
+        let u16_at = |i: usize| u16::from_le_bytes([buf[i], buf[i + 1]]);
+        let i16_at = |i: usize| i16::from_le_bytes([buf[i], buf[i + 1]]);
+        Self {
+            t1: u16_at(0),
+            t2: i16_at(2),
+            t3: i16_at(4),
+            p1: u16_at(6),
+            p2: i16_at(8),
+            p3: i16_at(10),
+            p4: i16_at(12),
+            p5: i16_at(14),
+            p6: i16_at(16),
+            p7: i16_at(18),
+            p8: i16_at(20),
+            p9: i16_at(22),
+            ..Self::default()
+        }
+    }
+
+    /// BME280 only, `buf` holds the registers from 0xe1, `h1` is read from 0xa1.
+    fn with_humidity(mut self, h1: u8, buf: &[u8; 7]) -> Self {
+        self.h1 = h1;
+        self.h2 = i16::from_le_bytes([buf[0], buf[1]]);
+        self.h3 = buf[2];
+        // 12-bit values sharing the nibbles of 0xe5
+        self.h4 = i16::from(buf[3] as i8) << 4 | i16::from(buf[4] & 0x0f);
+        self.h5 = i16::from(buf[5] as i8) << 4 | i16::from(buf[4] >> 4);
+        self.h6 = buf[6] as i8;
+        self
+    }
+
+    /// Returns the fine temperature used by the other compensations and the temperature
+    /// in 0.01 °C, from the datasheet's integer compensation.
+    fn temperature(&self, adc_t: i32) -> (i32, i32) {
+        let t1 = i32::from(self.t1);
+        let var1 = (((adc_t >> 3) - (t1 << 1)) * i32::from(self.t2)) >> 11;
+        let var2 = (((((adc_t >> 4) - t1) * ((adc_t >> 4) - t1)) >> 12) * i32::from(self.t3)) >> 14;
+        let t_fine = var1 + var2;
+        (t_fine, (t_fine * 5 + 128) >> 8)
+    }
+
+    /// Pressure in Pa as Q24.8.
+    fn pressure(&self, adc_p: i32, t_fine: i32) -> u32 {
+        let mut var1 = i64::from(t_fine) - 128_000;
+        let mut var2 = var1 * var1 * i64::from(self.p6);
+        var2 += (var1 * i64::from(self.p5)) << 17;
+        var2 += i64::from(self.p4) << 35;
+        var1 = ((var1 * var1 * i64::from(self.p3)) >> 8) + ((var1 * i64::from(self.p2)) << 12);
+        var1 = (((1i64 << 47) + var1) * i64::from(self.p1)) >> 33;
+        if var1 == 0 {
+            // avoids dividing by zero
+            return 0;
+        }
+        let mut p = 1_048_576 - i64::from(adc_p);
+        p = (((p << 31) - var2) * 3125) / var1;
+        var1 = (i64::from(self.p9) * (p >> 13) * (p >> 13)) >> 25;
+        var2 = (i64::from(self.p8) * p) >> 19;
+        (((p + var1 + var2) >> 8) + (i64::from(self.p7) << 4)) as u32
+    }
+
+    /// Relative humidity in % as Q22.10.
+    fn humidity(&self, adc_h: i32, t_fine: i32) -> u32 {
+        let mut v = t_fine - 76_800;
+        v = (((adc_h << 14) - (i32::from(self.h4) << 20) - (i32::from(self.h5) * v) + 16_384)
+            >> 15)
+            * (((((((v * i32::from(self.h6)) >> 10)
+                * (((v * i32::from(self.h3)) >> 11) + 32_768))
+                >> 10)
+                + 2_097_152)
+                * i32::from(self.h2)
+                + 8_192)
+                >> 14);
+        v -= ((((v >> 15) * (v >> 15)) >> 7) * i32::from(self.h1)) >> 4;
+        (v.clamp(0, 419_430_400) >> 12) as u32
+    }
+}
+
+/// Bosch BMP280 pressure and temperature sensor or BME280, which also measures humidity.
+///
+/// Measures on demand in forced mode with the datasheet's weather monitoring settings,
+/// sleeping in between. CO2 is reported as missing, as is humidity on a BMP280.
+pub struct Bmx280Sensor<I2C, DELAY, CLOCK> {
+    i2c: I2C,
+    delay: DELAY,
+    clock: CLOCK,
+    address: u8,
+    /// Read on start up or before the first measurement.
+    chip: Option<(SensorKind, Calibration)>,
+}
+
+impl<I2C, DELAY, CLOCK> Bmx280Sensor<I2C, DELAY, CLOCK>
+where
+    I2C: I2c,
+    DELAY: DelayUs,
+    CLOCK: Clock,
+{
+    pub fn new(i2c: I2C, delay: DELAY, clock: CLOCK) -> Self {
+        Self {
+            i2c,
+            delay,
+            clock,
+            address: BMX280_DEFAULT_ADDRESS,
+            chip: None,
+        }
+    }
+
+    pub fn with_address(mut self, address: u8) -> Self {
+        self.address = address;
+        self
+    }
+
+    pub fn reset(&mut self) -> Result<(), Bmx280Error<I2C::Error>> {
+        self.write_register(RESET, RESET_COMMAND)?;
+        // start-up time
+        self.delay.delay_ms(2);
+        Ok(())
+    }
+
+    fn read_registers(
+        &mut self,
+        register: u8,
+        buf: &mut [u8],
+    ) -> Result<(), Bmx280Error<I2C::Error>> {
+        self.i2c
+            .write_read(self.address, &[register], buf)
+            .map_err(Bmx280Error::I2c)
+    }
+
+    fn write_register(&mut self, register: u8, value: u8) -> Result<(), Bmx280Error<I2C::Error>> {
+        self.i2c
+            .write(self.address, &[register, value])
+            .map_err(Bmx280Error::I2c)
+    }
+
+    fn identify(&mut self) -> Result<(SensorKind, Calibration), Bmx280Error<I2C::Error>> {
+        let mut chip_id = [0];
+        self.read_registers(CHIP_ID, &mut chip_id)?;
+        let kind = match chip_id[0] {
+            CHIP_ID_BMP280 => SensorKind::Bmp280,
+            CHIP_ID_BME280 => SensorKind::Bme280,
+            id => return Err(Bmx280Error::UnknownChip(id)),
+        };
+
+        let mut buf = [0; 24];
+        self.read_registers(CALIBRATION, &mut buf)?;
+        let mut calibration = Calibration::parse(&buf);
+        if kind == SensorKind::Bme280 {
+            let mut h1 = [0];
+            self.read_registers(CALIBRATION_HUMIDITY_H1, &mut h1)?;
+            let mut buf = [0; 7];
+            self.read_registers(CALIBRATION_HUMIDITY, &mut buf)?;
+            calibration = calibration.with_humidity(h1[0], &buf);
+        }
+        let chip = (kind, calibration);
+        self.chip = Some(chip);
+        Ok(chip)
+    }
+
+    fn wait_for_measurement(&mut self) -> Result<(), Bmx280Error<I2C::Error>> {
+        self.delay.delay_ms(MEASUREMENT_TIME_MS);
+        for _ in 0..10 {
+            let mut status = [0];
+            self.read_registers(STATUS, &mut status)?;
+            if status[0] & STATUS_MEASURING == 0 {
+                return Ok(());
+            }
+            self.delay.delay_ms(1);
+        }
+        Err(Bmx280Error::Timeout)
+    }
+}
+
+impl<I2C, DELAY, CLOCK> Sensor for Bmx280Sensor<I2C, DELAY, CLOCK>
+where
+    I2C: I2c,
+    DELAY: DelayUs,
+    CLOCK: Clock,
+{
+    type Error = Bmx280Error<I2C::Error>;
+
+    fn measure(&mut self) -> Result<Measurement, Self::Error> {
+        let (kind, calibration) = match self.chip {
+            Some(chip) => chip,
+            None => self.identify()?,
+        };
+        if kind == SensorKind::Bme280 {
+            // only takes effect once ctrl_meas is written
+            self.write_register(CTRL_HUM, CTRL_HUM_1X)?;
+        }
+        self.write_register(CTRL_MEAS, CTRL_MEAS_FORCED)?;
+        self.wait_for_measurement()?;
+
+        let mut buf = [0; 8];
+        self.read_registers(DATA, &mut buf)?;
+        let adc_20_bit = |i: usize| {
+            i32::from(buf[i]) << 12 | i32::from(buf[i + 1]) << 4 | i32::from(buf[i + 2]) >> 4
+        };
+        let (t_fine, temperature) = calibration.temperature(adc_20_bit(3));
+        let pressure = calibration.pressure(adc_20_bit(0), t_fine);
+
+        let mut measurement = Measurement::new(
+            self.clock.now(),
+            SourceId::UNKNOWN,
+            Data {
+                co2: 0,
+                temperature: temperature as f32 / 100.0,
+                humidity: 0.0,
+            },
+        );
+        measurement.quality.co2 = Quality::Missing;
+        if kind == SensorKind::Bme280 {
+            let adc_h = i32::from(buf[6]) << 8 | i32::from(buf[7]);
+            measurement.data.humidity = calibration.humidity(adc_h, t_fine) as f32 / 1024.0;
+        } else {
+            measurement.quality.humidity = Quality::Missing;
+        }
+        // Pa to hPa
+        measurement.pressure = Some(pressure as f32 / 256.0 / 100.0);
+        Ok(measurement)
+    }
+}
+
+impl<I2C, DELAY, CLOCK> StartUp for Bmx280Sensor<I2C, DELAY, CLOCK>
+where
+    I2C: I2c,
+    DELAY: DelayUs,
+    CLOCK: Clock,
+{
+    type Error = Bmx280Error<I2C::Error>;
+
+    /// Resets the sensor and reads its calibration. It has no serial number.
+    fn start_up(&mut self) -> Result<SensorInfo, Self::Error> {
+        self.reset()?;
+        let (kind, _) = self.identify()?;
+        Ok(SensorInfo {
+            kind,
+            serial_number: SourceId::UNKNOWN.0,
+            firmware_version: None,
+        })
+    }
+}
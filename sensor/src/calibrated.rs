@@ -0,0 +1,174 @@
+use crate::{sensirion, Sensor};
+use airquamon_domain::{Measurement, Quality};
+
+/// Length of a stored [`CalibrationProfile`] in bytes.
+pub const CALIBRATION_PROFILE_LENGTH: usize = 26;
+
+const PROFILE_VERSION: u8 = 1;
+
+/// Linear correction `value * gain + offset` of a single field.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Correction {
+    pub gain: f32,
+    pub offset: f32,
+}
+
+impl Correction {
+    pub const IDENTITY: Correction = Correction {
+        gain: 1.0,
+        offset: 0.0,
+    };
+
+    pub const fn offset(offset: f32) -> Self {
+        Self { gain: 1.0, offset }
+    }
+
+    /// The correction mapping two values measured by the sensor onto the reference values
+    /// measured at the same time, e.g. in a cold and a warm room. `None` if both measured
+    /// values are the same.
+    pub fn two_point(measured: (f32, f32), reference: (f32, f32)) -> Option<Self> {
+        let span = measured.1 - measured.0;
+        if span == 0.0 || !span.is_finite() {
+            return None;
+        }
+        let gain = (reference.1 - reference.0) / span;
+        Some(Self {
+            gain,
+            offset: reference.0 - measured.0 * gain,
+        })
+    }
+
+    pub fn apply(&self, value: f32) -> f32 {
+        value * self.gain + self.offset
+    }
+}
+
+impl Default for Correction {
+    fn default() -> Self {
+        Self::IDENTITY
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum ProfileError {
+    Length,
+    /// The profile was stored in a format this version doesn't know.
+    Version(u8),
+    Checksum,
+}
+
+/// Corrections for a single device, e.g. to make up for the heat of its enclosure.
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct CalibrationProfile {
+    pub co2: Correction,
+    pub temperature: Correction,
+    pub humidity: Correction,
+}
+
+impl CalibrationProfile {
+    pub const IDENTITY: CalibrationProfile = CalibrationProfile {
+        co2: Correction::IDENTITY,
+        temperature: Correction::IDENTITY,
+        humidity: Correction::IDENTITY,
+    };
+
+    /// Reads a profile stored with [`CalibrationProfile::to_bytes`]: a version byte, gain and
+    /// offset of CO2, temperature and humidity as little endian `f32` and a CRC-8 of the rest.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, ProfileError> {
+        let bytes: &[u8; CALIBRATION_PROFILE_LENGTH] =
+            bytes.try_into().map_err(|_| ProfileError::Length)?;
+        let (data, checksum) = bytes.split_at(CALIBRATION_PROFILE_LENGTH - 1);
+        if sensirion::crc8(data) != checksum[0] {
+            return Err(ProfileError::Checksum);
+        }
+        if data[0] != PROFILE_VERSION {
+            return Err(ProfileError::Version(data[0]));
+        }
+
+        let mut values = data[1..]
+            .chunks_exact(4)
+            .map(|chunk| f32::from_le_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]));
+        let mut correction = || Correction {
+            gain: values.next().unwrap_or(1.0),
+            offset: values.next().unwrap_or(0.0),
+        };
+        Ok(Self {
+            co2: correction(),
+            temperature: correction(),
+            humidity: correction(),
+        })
+    }
+
+    pub fn to_bytes(&self) -> [u8; CALIBRATION_PROFILE_LENGTH] {
+        let mut bytes = [0; CALIBRATION_PROFILE_LENGTH];
+        bytes[0] = PROFILE_VERSION;
+        let values = [self.co2, self.temperature, self.humidity]
+            .into_iter()
+            .flat_map(|correction| [correction.gain, correction.offset]);
+        for (chunk, value) in bytes[1..].chunks_exact_mut(4).zip(values) {
+            chunk.copy_from_slice(&value.to_le_bytes());
+        }
+        bytes[CALIBRATION_PROFILE_LENGTH - 1] =
+            sensirion::crc8(&bytes[..CALIBRATION_PROFILE_LENGTH - 1]);
+        bytes
+    }
+}
+
+/// Applies a device's [`CalibrationProfile`] to the measurements of the wrapped sensor.
+/// Missing values are left as they are, CO2 is rounded and kept within 0..=65535 ppm.
+pub struct Calibrated<S> {
+    sensor: S,
+    profile: CalibrationProfile,
+}
+
+impl<S> Calibrated<S>
+where
+    S: Sensor,
+{
+    pub fn new(sensor: S, profile: CalibrationProfile) -> Self {
+        Self { sensor, profile }
+    }
+
+    pub fn profile(&self) -> &CalibrationProfile {
+        &self.profile
+    }
+
+    pub fn set_profile(&mut self, profile: CalibrationProfile) {
+        self.profile = profile;
+    }
+
+    pub fn inner(&self) -> &S {
+        &self.sensor
+    }
+
+    pub fn inner_mut(&mut self) -> &mut S {
+        &mut self.sensor
+    }
+
+    pub fn into_inner(self) -> S {
+        self.sensor
+    }
+}
+
+impl<S> Sensor for Calibrated<S>
+where
+    S: Sensor,
+{
+    type Error = S::Error;
+
+    fn measure(&mut self) -> Result<Measurement, Self::Error> {
+        let mut measurement = self.sensor.measure()?;
+        let (data, quality) = (&mut measurement.data, measurement.quality);
+        if quality.co2 != Quality::Missing {
+            // casting saturates at the bounds of u16
+            data.co2 = libm::roundf(self.profile.co2.apply(data.co2.into())) as u16;
+        }
+        if quality.temperature != Quality::Missing {
+            data.temperature = self.profile.temperature.apply(data.temperature);
+        }
+        if quality.humidity != Quality::Missing {
+            data.humidity = self.profile.humidity.apply(data.humidity);
+        }
+        Ok(measurement)
+    }
+}
@@ -0,0 +1,185 @@
+use crate::{ErrorKind, Sensor, SensorError};
+use airquamon_domain::{Data, Field, Measurement, QualityFlags};
+
+/// A [`Sensor`] whose error type is erased, so sensors with different errors can be combined.
+pub trait ErasedSensor {
+    /// Measures, keeping only the kind of the error if it fails.
+    fn try_measure(&mut self) -> Result<Measurement, ErrorKind>;
+}
+
+impl<S> ErasedSensor for S
+where
+    S: Sensor,
+    S::Error: SensorError,
+{
+    fn try_measure(&mut self) -> Result<Measurement, ErrorKind> {
+        self.measure().map_err(|e| e.kind())
+    }
+}
+
+/// Where a field comes from, the indices of the sources in order of preference.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Route<'a> {
+    pub field: Field,
+    pub sources: &'a [usize],
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum CompositeError {
+    /// None of the sources could be measured.
+    AllFailed,
+}
+
+impl SensorError for CompositeError {
+    fn kind(&self) -> ErrorKind {
+        match self {
+            CompositeError::AllFailed => ErrorKind::Other,
+        }
+    }
+}
+
+/// Which source each field of the last measurement came from and which sources failed.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct CompositeReport<const N: usize> {
+    sources: [Option<usize>; Field::ALL.len()],
+    errors: [Option<ErrorKind>; N],
+}
+
+impl<const N: usize> CompositeReport<N> {
+    const EMPTY: Self = Self {
+        sources: [None; Field::ALL.len()],
+        errors: [None; N],
+    };
+
+    /// Index of the source `field` was taken from, `None` if no source had it.
+    pub fn source(&self, field: Field) -> Option<usize> {
+        self.sources[field as usize]
+    }
+
+    /// Whether measuring the source at `index` failed.
+    pub fn failed(&self, index: usize) -> bool {
+        self.error(index).is_some()
+    }
+
+    /// Why measuring the source at `index` failed, `None` if it didn't.
+    pub fn error(&self, index: usize) -> Option<ErrorKind> {
+        self.errors.get(index).copied().flatten()
+    }
+
+    /// Indices of the sources that failed and why.
+    pub fn failures(&self) -> impl Iterator<Item = (usize, ErrorKind)> + '_ {
+        (0..N).filter_map(|index| Some((index, self.errors[index]?)))
+    }
+
+    /// Whether some, but not necessarily all, sources failed.
+    pub fn is_partial(&self) -> bool {
+        self.errors.iter().any(Option::is_some)
+    }
+}
+
+/// Merges the measurements of several sensors into one, e.g. CO2 from an SCD41 and
+/// temperature and humidity from a more accurate SHT4x.
+///
+/// Every source is measured once per measurement. Each routed field is taken from the first
+/// of its sources that measured it, falling back to the next one if a source failed or
+/// reported the field as missing. Fields without a route are missing. The measurement only
+/// fails if every source failed, [`CompositeSensor::report`] tells which ones did and why.
+///
+/// Timestamp and source ID are those of the first source that could be measured. Particulate
+/// matter and gas indices are grouped, the group is taken from the first source providing
+/// one of its fields and the other routed fields of the group are overwritten from theirs.
+pub struct CompositeSensor<'a, const N: usize> {
+    sources: [&'a mut dyn ErasedSensor; N],
+    routes: &'a [Route<'a>],
+    report: CompositeReport<N>,
+}
+
+impl<'a, const N: usize> CompositeSensor<'a, N> {
+    pub fn new(sources: [&'a mut dyn ErasedSensor; N], routes: &'a [Route<'a>]) -> Self {
+        Self {
+            sources,
+            routes,
+            report: CompositeReport::EMPTY,
+        }
+    }
+
+    /// Provenance and failures of the last measurement.
+    pub fn report(&self) -> &CompositeReport<N> {
+        &self.report
+    }
+}
+
+impl<'a, const N: usize> Sensor for CompositeSensor<'a, N> {
+    type Error = CompositeError;
+
+    fn measure(&mut self) -> Result<Measurement, Self::Error> {
+        let mut report = CompositeReport::EMPTY;
+        let mut measurements = [None; N];
+        for (index, source) in self.sources.iter_mut().enumerate() {
+            match source.try_measure() {
+                Ok(measurement) => measurements[index] = Some(measurement),
+                Err(kind) => report.errors[index] = Some(kind),
+            }
+        }
+
+        let first = measurements.iter().flatten().next();
+        let Some(first) = first else {
+            self.report = report;
+            return Err(CompositeError::AllFailed);
+        };
+        let mut measurement = Measurement::new(first.timestamp, first.source, Data::default());
+        measurement.quality = QualityFlags::MISSING;
+
+        for route in self.routes {
+            let source = route.sources.iter().find_map(|&index| {
+                let from = measurements.get(index)?.as_ref()?;
+                from.value(route.field).is_some().then_some((index, from))
+            });
+            if let Some((index, from)) = source {
+                copy_field(route.field, from, &mut measurement);
+                report.sources[route.field as usize] = Some(index);
+            }
+        }
+        self.report = report;
+        Ok(measurement)
+    }
+}
+
+/// Copies `field` including its quality, `from` has to have it.
+fn copy_field(field: Field, from: &Measurement, to: &mut Measurement) {
+    match field {
+        Field::Co2 => {
+            to.data.co2 = from.data.co2;
+            to.quality.co2 = from.quality.co2;
+        }
+        Field::Temperature => {
+            to.data.temperature = from.data.temperature;
+            to.quality.temperature = from.quality.temperature;
+        }
+        Field::Humidity => {
+            to.data.humidity = from.data.humidity;
+            to.quality.humidity = from.quality.humidity;
+        }
+        Field::Pm1_0 | Field::Pm2_5 | Field::Pm10 => {
+            if let Some(pm) = from.particulate_matter {
+                let to = to.particulate_matter.get_or_insert(pm);
+                match field {
+                    Field::Pm1_0 => to.pm1_0 = pm.pm1_0,
+                    Field::Pm2_5 => to.pm2_5 = pm.pm2_5,
+                    _ => to.pm10 = pm.pm10,
+                }
+            }
+        }
+        Field::VocIndex | Field::NoxIndex => {
+            if let Some(gas) = from.gas_indices {
+                let to = to.gas_indices.get_or_insert(gas);
+                if field == Field::VocIndex {
+                    to.voc = gas.voc;
+                } else {
+                    to.nox = gas.nox;
+                }
+            }
+        }
+        Field::Pressure => to.pressure = from.pressure,
+    }
+}
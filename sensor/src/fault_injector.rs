@@ -0,0 +1,147 @@
+use crate::{rng::Rng, ErrorKind, PowerManagement, PowerState, Sensor, SensorError};
+use airquamon_domain::{Data, Measurement};
+
+/// Probabilities in 0..=1 per measurement of each kind of fault.
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct FaultRates {
+    /// The measurement fails with [`FaultError::Injected`].
+    pub error: f32,
+    /// The measurement fails with [`FaultError::Timeout`].
+    pub timeout: f32,
+    /// The sensor gets stuck, repeating its last values for `stuck_measurements` measurements.
+    pub stuck: f32,
+    pub stuck_measurements: u16,
+    pub nan_temperature: f32,
+    /// CO2 reads 0 or 65535 ppm.
+    pub out_of_range_co2: f32,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum FaultError<E> {
+    Sensor(E),
+    Injected,
+    /// Returned straight away, unlike a real timeout no time passes.
+    Timeout,
+}
+
+impl<E> SensorError for FaultError<E>
+where
+    E: SensorError,
+{
+    fn kind(&self) -> ErrorKind {
+        match self {
+            FaultError::Sensor(e) => e.kind(),
+            FaultError::Injected => ErrorKind::Other,
+            FaultError::Timeout => ErrorKind::Timeout,
+        }
+    }
+}
+
+/// Injects faults seen in the field into the measurements of the wrapped sensor.
+/// The same seed and rates always inject the same faults.
+pub struct FaultInjector<S> {
+    sensor: S,
+    rates: FaultRates,
+    rng: Rng,
+    last: Option<Data>,
+    /// Measurements left for which the last values are repeated.
+    stuck_remaining: u16,
+}
+
+impl<S> FaultInjector<S>
+where
+    S: Sensor,
+{
+    pub fn new(sensor: S, rates: FaultRates, seed: u64) -> Self {
+        Self {
+            sensor,
+            rates,
+            rng: Rng::new(seed),
+            last: None,
+            stuck_remaining: 0,
+        }
+    }
+
+    pub fn rates(&self) -> &FaultRates {
+        &self.rates
+    }
+
+    pub fn set_rates(&mut self, rates: FaultRates) {
+        self.rates = rates;
+    }
+
+    pub fn inner(&self) -> &S {
+        &self.sensor
+    }
+
+    pub fn inner_mut(&mut self) -> &mut S {
+        &mut self.sensor
+    }
+
+    pub fn into_inner(self) -> S {
+        self.sensor
+    }
+
+    fn occurs(&mut self, rate: f32) -> bool {
+        self.rng.next_f32() < rate
+    }
+}
+
+impl<S> Sensor for FaultInjector<S>
+where
+    S: Sensor,
+{
+    type Error = FaultError<S::Error>;
+
+    fn measure(&mut self) -> Result<Measurement, Self::Error> {
+        if self.occurs(self.rates.error) {
+            return Err(FaultError::Injected);
+        }
+        if self.occurs(self.rates.timeout) {
+            return Err(FaultError::Timeout);
+        }
+        let mut measurement = self.sensor.measure().map_err(FaultError::Sensor)?;
+
+        if self.stuck_remaining == 0 && self.occurs(self.rates.stuck) {
+            self.stuck_remaining = self.rates.stuck_measurements;
+        }
+        match self.last {
+            Some(last) if self.stuck_remaining > 0 => {
+                self.stuck_remaining -= 1;
+                measurement.data = last;
+            }
+            _ => self.last = Some(measurement.data),
+        }
+
+        if self.occurs(self.rates.nan_temperature) {
+            measurement.data.temperature = f32::NAN;
+        }
+        if self.occurs(self.rates.out_of_range_co2) {
+            measurement.data.co2 = if self.rng.next_u64() & 1 == 0 {
+                0
+            } else {
+                u16::MAX
+            };
+        }
+        Ok(measurement)
+    }
+}
+
+impl<S> PowerManagement for FaultInjector<S>
+where
+    S: PowerManagement,
+{
+    type Error = S::Error;
+
+    fn power_state(&self) -> PowerState {
+        self.sensor.power_state()
+    }
+
+    fn set_power_state(&mut self, state: PowerState) -> Result<(), Self::Error> {
+        self.sensor.set_power_state(state)
+    }
+
+    fn supply_current(&self, state: PowerState) -> u32 {
+        self.sensor.supply_current(state)
+    }
+}
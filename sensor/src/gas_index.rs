@@ -0,0 +1,485 @@
+//! Port of Sensirion's gas index algorithm (v3.2), which turns the raw signals of the SGP40 and
+//! SGP41 into VOC and NOx indices. The structure and constants follow the reference
+//! implementation so the two can be compared side by side.
+
+use libm::{expf, sqrtf};
+
+const INITIAL_BLACKOUT: f32 = 45.0;
+const INDEX_GAIN: f32 = 230.0;
+const SRAW_STD_INITIAL: f32 = 50.0;
+const SRAW_STD_BONUS_VOC: f32 = 220.0;
+const SRAW_STD_NOX: f32 = 2000.0;
+const TAU_MEAN_HOURS: f32 = 12.0;
+const TAU_VARIANCE_HOURS: f32 = 12.0;
+const TAU_INITIAL_MEAN_VOC: f32 = 20.0;
+const TAU_INITIAL_MEAN_NOX: f32 = 1200.0;
+const INIT_DURATION_MEAN_VOC: f32 = 3600.0 * 0.75;
+const INIT_DURATION_MEAN_NOX: f32 = 3600.0 * 4.75;
+const INIT_TRANSITION_MEAN: f32 = 0.01;
+const TAU_INITIAL_VARIANCE: f32 = 2500.0;
+const INIT_DURATION_VARIANCE_VOC: f32 = 3600.0 * 1.45;
+const INIT_DURATION_VARIANCE_NOX: f32 = 3600.0 * 5.70;
+const INIT_TRANSITION_VARIANCE: f32 = 0.01;
+const GATING_THRESHOLD_VOC: f32 = 340.0;
+const GATING_THRESHOLD_NOX: f32 = 30.0;
+const GATING_THRESHOLD_INITIAL: f32 = 510.0;
+const GATING_THRESHOLD_TRANSITION: f32 = 0.09;
+const GATING_VOC_MAX_DURATION_MINUTES: f32 = 60.0 * 3.0;
+const GATING_NOX_MAX_DURATION_MINUTES: f32 = 60.0 * 12.0;
+const GATING_MAX_RATIO: f32 = 0.3;
+const SIGMOID_L: f32 = 500.0;
+const SIGMOID_K_VOC: f32 = -0.0065;
+const SIGMOID_X0_VOC: f32 = 213.0;
+const SIGMOID_K_NOX: f32 = -0.0101;
+const SIGMOID_X0_NOX: f32 = 614.0;
+const VOC_INDEX_OFFSET_DEFAULT: f32 = 100.0;
+const NOX_INDEX_OFFSET_DEFAULT: f32 = 1.0;
+const LP_TAU_FAST: f32 = 20.0;
+const LP_TAU_SLOW: f32 = 500.0;
+const LP_ALPHA: f32 = -0.2;
+const VOC_SRAW_MINIMUM: i32 = 20_000;
+const NOX_SRAW_MINIMUM: i32 = 10_000;
+const MEAN_VARIANCE_ESTIMATOR_GAMMA_SCALING: f32 = 64.0;
+const MEAN_VARIANCE_ESTIMATOR_ADDITIONAL_GAMMA_MEAN_SCALING: f32 = 8.0;
+const MEAN_VARIANCE_ESTIMATOR_FIX16_MAX: f32 = 32767.0;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum GasIndexKind {
+    Voc,
+    Nox,
+}
+
+/// Logistic function falling from 1 to 0 around `x0`.
+#[derive(Copy, Clone, Debug, Default)]
+struct Sigmoid {
+    k: f32,
+    x0: f32,
+}
+
+impl Sigmoid {
+    fn process(&self, sample: f32) -> f32 {
+        let x = self.k * (sample - self.x0);
+        if x < -50.0 {
+            1.0
+        } else if x > 50.0 {
+            0.0
+        } else {
+            1.0 / (1.0 + expf(x))
+        }
+    }
+}
+
+/// Tracks the mean and standard deviation of the raw signal, adapting quickly at first and
+/// gating out events so they don't shift the baseline.
+#[derive(Clone, Debug)]
+struct MeanVarianceEstimator {
+    initialized: bool,
+    mean: f32,
+    sraw_offset: f32,
+    std: f32,
+    gamma_mean: f32,
+    gamma_variance: f32,
+    gamma_initial_mean: f32,
+    gamma_initial_variance: f32,
+    current_gamma_mean: f32,
+    current_gamma_variance: f32,
+    uptime_gamma: f32,
+    uptime_gating: f32,
+    gating_duration_minutes: f32,
+    sigmoid: Sigmoid,
+}
+
+/// Low-pass filter that follows fast changes quickly and smooths out noise otherwise.
+#[derive(Clone, Debug, Default)]
+struct AdaptiveLowpass {
+    a1: f32,
+    a2: f32,
+    initialized: bool,
+    x1: f32,
+    x2: f32,
+    x3: f32,
+}
+
+/// Turns raw SGP4x signals into a VOC or NOx index.
+///
+/// Has to be fed every sampling interval, 1 s by default. Reports 0 during the first 45 s.
+#[derive(Clone, Debug)]
+pub struct GasIndexAlgorithm {
+    kind: GasIndexKind,
+    sampling_interval: f32,
+    index_offset: f32,
+    sraw_minimum: i32,
+    gating_max_duration_minutes: f32,
+    init_duration_mean: f32,
+    init_duration_variance: f32,
+    gating_threshold: f32,
+    index_gain: f32,
+    tau_mean_hours: f32,
+    tau_variance_hours: f32,
+    sraw_std_initial: f32,
+    uptime: f32,
+    sraw: f32,
+    gas_index: f32,
+    mean_variance_estimator: MeanVarianceEstimator,
+    mox_model_sraw_std: f32,
+    mox_model_sraw_mean: f32,
+    sigmoid_scaled_k: f32,
+    sigmoid_scaled_x0: f32,
+    sigmoid_scaled_offset_default: f32,
+    adaptive_lowpass: AdaptiveLowpass,
+}
+
+impl GasIndexAlgorithm {
+    /// Sampling every second.
+    pub fn new(kind: GasIndexKind) -> Self {
+        Self::with_sampling_interval(kind, 1.0)
+    }
+
+    /// `sampling_interval` in s. The NOx index needs 1 s, the VOC index supports up to 10 s.
+    pub fn with_sampling_interval(kind: GasIndexKind, sampling_interval: f32) -> Self {
+        let (
+            index_offset,
+            sraw_minimum,
+            gating_max_duration_minutes,
+            init_duration_mean,
+            init_duration_variance,
+            gating_threshold,
+        ) = match kind {
+            GasIndexKind::Voc => (
+                VOC_INDEX_OFFSET_DEFAULT,
+                VOC_SRAW_MINIMUM,
+                GATING_VOC_MAX_DURATION_MINUTES,
+                INIT_DURATION_MEAN_VOC,
+                INIT_DURATION_VARIANCE_VOC,
+                GATING_THRESHOLD_VOC,
+            ),
+            GasIndexKind::Nox => (
+                NOX_INDEX_OFFSET_DEFAULT,
+                NOX_SRAW_MINIMUM,
+                GATING_NOX_MAX_DURATION_MINUTES,
+                INIT_DURATION_MEAN_NOX,
+                INIT_DURATION_VARIANCE_NOX,
+                GATING_THRESHOLD_NOX,
+            ),
+        };
+        let mut algorithm = Self {
+            kind,
+            sampling_interval,
+            index_offset,
+            sraw_minimum,
+            gating_max_duration_minutes,
+            init_duration_mean,
+            init_duration_variance,
+            gating_threshold,
+            index_gain: INDEX_GAIN,
+            tau_mean_hours: TAU_MEAN_HOURS,
+            tau_variance_hours: TAU_VARIANCE_HOURS,
+            sraw_std_initial: SRAW_STD_INITIAL,
+            uptime: 0.0,
+            sraw: 0.0,
+            gas_index: 0.0,
+            mean_variance_estimator: MeanVarianceEstimator {
+                initialized: false,
+                mean: 0.0,
+                sraw_offset: 0.0,
+                std: 0.0,
+                gamma_mean: 0.0,
+                gamma_variance: 0.0,
+                gamma_initial_mean: 0.0,
+                gamma_initial_variance: 0.0,
+                current_gamma_mean: 0.0,
+                current_gamma_variance: 0.0,
+                uptime_gamma: 0.0,
+                uptime_gating: 0.0,
+                gating_duration_minutes: 0.0,
+                sigmoid: Sigmoid::default(),
+            },
+            mox_model_sraw_std: 0.0,
+            mox_model_sraw_mean: 0.0,
+            sigmoid_scaled_k: 0.0,
+            sigmoid_scaled_x0: 0.0,
+            sigmoid_scaled_offset_default: 0.0,
+            adaptive_lowpass: AdaptiveLowpass::default(),
+        };
+        algorithm.reset();
+        algorithm
+    }
+
+    pub fn kind(&self) -> GasIndexKind {
+        self.kind
+    }
+
+    /// Starts learning the baseline from scratch, e.g. after the sensor was off for a while.
+    pub fn reset(&mut self) {
+        self.uptime = 0.0;
+        self.sraw = 0.0;
+        self.gas_index = 0.0;
+        self.init_instances();
+    }
+
+    /// Processes a raw signal in ticks, returning the index from 1 to 500 or 0 during the
+    /// initial blackout.
+    pub fn process(&mut self, sraw: u16) -> u16 {
+        if self.uptime <= INITIAL_BLACKOUT {
+            self.uptime += self.sampling_interval;
+        } else {
+            let sraw = i32::from(sraw);
+            if sraw > 0 && sraw < 65_000 {
+                let sraw = sraw.clamp(self.sraw_minimum + 1, self.sraw_minimum + 32_767);
+                self.sraw = (sraw - self.sraw_minimum) as f32;
+            }
+            self.gas_index =
+                if self.kind == GasIndexKind::Voc || self.mean_variance_estimator.initialized {
+                    let index = self.mox_model_process(self.sraw);
+                    self.sigmoid_scaled_process(index)
+                } else {
+                    self.index_offset
+                };
+            self.gas_index = self.adaptive_lowpass_process(self.gas_index);
+            if self.gas_index < 0.5 {
+                self.gas_index = 0.5;
+            }
+            if self.sraw > 0.0 {
+                self.mean_variance_estimator_process(self.sraw);
+                self.mox_model_set_parameters(
+                    self.mean_variance_estimator.std,
+                    self.mean_variance_estimator_mean(),
+                );
+            }
+        }
+        (self.gas_index + 0.5) as u16
+    }
+
+    fn init_instances(&mut self) {
+        self.mean_variance_estimator_set_parameters();
+        self.mox_model_set_parameters(
+            self.mean_variance_estimator.std,
+            self.mean_variance_estimator_mean(),
+        );
+        match self.kind {
+            GasIndexKind::Voc => self.sigmoid_scaled_set_parameters(
+                SIGMOID_X0_VOC,
+                SIGMOID_K_VOC,
+                VOC_INDEX_OFFSET_DEFAULT,
+            ),
+            GasIndexKind::Nox => self.sigmoid_scaled_set_parameters(
+                SIGMOID_X0_NOX,
+                SIGMOID_K_NOX,
+                NOX_INDEX_OFFSET_DEFAULT,
+            ),
+        }
+        self.adaptive_lowpass_set_parameters();
+    }
+
+    fn mean_variance_estimator_set_parameters(&mut self) {
+        let interval = self.sampling_interval;
+        let interval_hours = interval / 3600.0;
+        let tau_initial_mean = match self.kind {
+            GasIndexKind::Voc => TAU_INITIAL_MEAN_VOC,
+            GasIndexKind::Nox => TAU_INITIAL_MEAN_NOX,
+        };
+        let estimator = &mut self.mean_variance_estimator;
+        estimator.initialized = false;
+        estimator.mean = 0.0;
+        estimator.sraw_offset = 0.0;
+        estimator.std = self.sraw_std_initial;
+        estimator.gamma_mean = (MEAN_VARIANCE_ESTIMATOR_ADDITIONAL_GAMMA_MEAN_SCALING
+            * MEAN_VARIANCE_ESTIMATOR_GAMMA_SCALING
+            * interval_hours)
+            / (self.tau_mean_hours + interval_hours);
+        estimator.gamma_variance = (MEAN_VARIANCE_ESTIMATOR_GAMMA_SCALING * interval_hours)
+            / (self.tau_variance_hours + interval_hours);
+        estimator.gamma_initial_mean = (MEAN_VARIANCE_ESTIMATOR_ADDITIONAL_GAMMA_MEAN_SCALING
+            * MEAN_VARIANCE_ESTIMATOR_GAMMA_SCALING
+            * interval)
+            / (tau_initial_mean + interval);
+        estimator.gamma_initial_variance =
+            (MEAN_VARIANCE_ESTIMATOR_GAMMA_SCALING * interval) / (TAU_INITIAL_VARIANCE + interval);
+        estimator.current_gamma_mean = 0.0;
+        estimator.current_gamma_variance = 0.0;
+        estimator.uptime_gamma = 0.0;
+        estimator.uptime_gating = 0.0;
+        estimator.gating_duration_minutes = 0.0;
+    }
+
+    fn mean_variance_estimator_mean(&self) -> f32 {
+        self.mean_variance_estimator.mean + self.mean_variance_estimator.sraw_offset
+    }
+
+    fn mean_variance_estimator_calculate_gamma(&mut self) {
+        let interval = self.sampling_interval;
+        let gas_index = self.gas_index;
+        let (init_duration_mean, init_duration_variance) =
+            (self.init_duration_mean, self.init_duration_variance);
+        let gating_threshold = self.gating_threshold;
+        let gating_max_duration_minutes = self.gating_max_duration_minutes;
+        let estimator = &mut self.mean_variance_estimator;
+
+        let uptime_limit = MEAN_VARIANCE_ESTIMATOR_FIX16_MAX - interval;
+        if estimator.uptime_gamma < uptime_limit {
+            estimator.uptime_gamma += interval;
+        }
+        if estimator.uptime_gating < uptime_limit {
+            estimator.uptime_gating += interval;
+        }
+
+        estimator.sigmoid = Sigmoid {
+            k: INIT_TRANSITION_MEAN,
+            x0: init_duration_mean,
+        };
+        let sigmoid_gamma_mean = estimator.sigmoid.process(estimator.uptime_gamma);
+        let gamma_mean = estimator.gamma_mean
+            + (estimator.gamma_initial_mean - estimator.gamma_mean) * sigmoid_gamma_mean;
+        let gating_threshold_mean = gating_threshold
+            + (GATING_THRESHOLD_INITIAL - gating_threshold)
+                * estimator.sigmoid.process(estimator.uptime_gating);
+        estimator.sigmoid = Sigmoid {
+            k: GATING_THRESHOLD_TRANSITION,
+            x0: gating_threshold_mean,
+        };
+        let sigmoid_gating_mean = estimator.sigmoid.process(gas_index);
+        estimator.current_gamma_mean = sigmoid_gating_mean * gamma_mean;
+
+        estimator.sigmoid = Sigmoid {
+            k: INIT_TRANSITION_VARIANCE,
+            x0: init_duration_variance,
+        };
+        let sigmoid_gamma_variance = estimator.sigmoid.process(estimator.uptime_gamma);
+        let gamma_variance = estimator.gamma_variance
+            + (estimator.gamma_initial_variance - estimator.gamma_variance)
+                * (sigmoid_gamma_variance - sigmoid_gamma_mean);
+        let gating_threshold_variance = gating_threshold
+            + (GATING_THRESHOLD_INITIAL - gating_threshold)
+                * estimator.sigmoid.process(estimator.uptime_gating);
+        estimator.sigmoid = Sigmoid {
+            k: GATING_THRESHOLD_TRANSITION,
+            x0: gating_threshold_variance,
+        };
+        let sigmoid_gating_variance = estimator.sigmoid.process(gas_index);
+        estimator.current_gamma_variance = sigmoid_gating_variance * gamma_variance;
+
+        estimator.gating_duration_minutes += (interval / 60.0)
+            * ((1.0 - sigmoid_gating_mean) * (1.0 + GATING_MAX_RATIO) - GATING_MAX_RATIO);
+        if estimator.gating_duration_minutes < 0.0 {
+            estimator.gating_duration_minutes = 0.0;
+        }
+        if estimator.gating_duration_minutes > gating_max_duration_minutes {
+            estimator.uptime_gating = 0.0;
+        }
+    }
+
+    fn mean_variance_estimator_process(&mut self, sraw: f32) {
+        if !self.mean_variance_estimator.initialized {
+            let estimator = &mut self.mean_variance_estimator;
+            estimator.initialized = true;
+            estimator.sraw_offset = sraw;
+            estimator.mean = 0.0;
+            return;
+        }
+
+        let estimator = &mut self.mean_variance_estimator;
+        if estimator.mean >= 100.0 || estimator.mean <= -100.0 {
+            estimator.sraw_offset += estimator.mean;
+            estimator.mean = 0.0;
+        }
+        let sraw = sraw - estimator.sraw_offset;
+        self.mean_variance_estimator_calculate_gamma();
+
+        let estimator = &mut self.mean_variance_estimator;
+        let delta_sgp = (sraw - estimator.mean) / MEAN_VARIANCE_ESTIMATOR_GAMMA_SCALING;
+        let c = if delta_sgp < 0.0 {
+            estimator.std - delta_sgp
+        } else {
+            estimator.std + delta_sgp
+        };
+        let additional_scaling = if c > 1440.0 {
+            (c / 1440.0) * (c / 1440.0)
+        } else {
+            1.0
+        };
+        estimator.std = sqrtf(
+            additional_scaling
+                * (MEAN_VARIANCE_ESTIMATOR_GAMMA_SCALING - estimator.current_gamma_variance),
+        ) * sqrtf(
+            estimator.std
+                * (estimator.std / (MEAN_VARIANCE_ESTIMATOR_GAMMA_SCALING * additional_scaling))
+                + ((estimator.current_gamma_variance * delta_sgp) / additional_scaling) * delta_sgp,
+        );
+        estimator.mean += (estimator.current_gamma_mean * delta_sgp)
+            / MEAN_VARIANCE_ESTIMATOR_ADDITIONAL_GAMMA_MEAN_SCALING;
+    }
+
+    fn mox_model_set_parameters(&mut self, sraw_std: f32, sraw_mean: f32) {
+        self.mox_model_sraw_std = sraw_std;
+        self.mox_model_sraw_mean = sraw_mean;
+    }
+
+    fn mox_model_process(&self, sraw: f32) -> f32 {
+        match self.kind {
+            GasIndexKind::Voc => {
+                ((sraw - self.mox_model_sraw_mean)
+                    / (-(self.mox_model_sraw_std + SRAW_STD_BONUS_VOC)))
+                    * self.index_gain
+            }
+            GasIndexKind::Nox => {
+                ((sraw - self.mox_model_sraw_mean) / SRAW_STD_NOX) * self.index_gain
+            }
+        }
+    }
+
+    fn sigmoid_scaled_set_parameters(&mut self, x0: f32, k: f32, offset_default: f32) {
+        self.sigmoid_scaled_k = k;
+        self.sigmoid_scaled_x0 = x0;
+        self.sigmoid_scaled_offset_default = offset_default;
+    }
+
+    fn sigmoid_scaled_process(&self, sample: f32) -> f32 {
+        let x = self.sigmoid_scaled_k * (sample - self.sigmoid_scaled_x0);
+        if x < -50.0 {
+            SIGMOID_L
+        } else if x > 50.0 {
+            0.0
+        } else if sample >= 0.0 {
+            let shift = if self.sigmoid_scaled_offset_default == 1.0 {
+                (500.0 / 499.0) * (1.0 - self.index_offset)
+            } else {
+                (SIGMOID_L - 5.0 * self.index_offset) / 4.0
+            };
+            (SIGMOID_L + shift) / (1.0 + expf(x)) - shift
+        } else {
+            (self.index_offset / self.sigmoid_scaled_offset_default) * (SIGMOID_L / (1.0 + expf(x)))
+        }
+    }
+
+    fn adaptive_lowpass_set_parameters(&mut self) {
+        let interval = self.sampling_interval;
+        self.adaptive_lowpass = AdaptiveLowpass {
+            a1: interval / (LP_TAU_FAST + interval),
+            a2: interval / (LP_TAU_SLOW + interval),
+            ..AdaptiveLowpass::default()
+        };
+    }
+
+    fn adaptive_lowpass_process(&mut self, sample: f32) -> f32 {
+        let interval = self.sampling_interval;
+        let lowpass = &mut self.adaptive_lowpass;
+        if !lowpass.initialized {
+            lowpass.x1 = sample;
+            lowpass.x2 = sample;
+            lowpass.x3 = sample;
+            lowpass.initialized = true;
+        }
+        lowpass.x1 = (1.0 - lowpass.a1) * lowpass.x1 + lowpass.a1 * sample;
+        lowpass.x2 = (1.0 - lowpass.a2) * lowpass.x2 + lowpass.a2 * sample;
+        let abs_delta = if lowpass.x1 > lowpass.x2 {
+            lowpass.x1 - lowpass.x2
+        } else {
+            lowpass.x2 - lowpass.x1
+        };
+        let f1 = expf(LP_ALPHA * abs_delta);
+        let tau_a = (LP_TAU_SLOW - LP_TAU_FAST) * f1 + LP_TAU_FAST;
+        let a3 = interval / (interval + tau_a);
+        lowpass.x3 = (1.0 - a3) * lowpass.x3 + a3 * sample;
+        lowpass.x3
+    }
+}
@@ -1,14 +1,200 @@
 #![no_std]
+#![allow(async_fn_in_trait)]
 
-use airquamon_domain::Data;
+use airquamon_domain::{Measurement, SourceId};
 
+mod bmx280_sensor;
+mod calibrated;
+mod composite_sensor;
+mod fault_injector;
+mod gas_index;
 mod mock_sensor;
+mod pms5003_sensor;
+mod pressure_compensated;
+mod replay_sensor;
+mod retry;
+mod rng;
+mod scd4x_async_sensor;
+mod scd4x_commands;
 mod scd4x_sensor;
+mod scenario_sensor;
+mod sensirion;
+mod sgp41_sensor;
+mod sht4x_sensor;
+mod sps30_sensor;
+mod stability_monitor;
+pub use bmx280_sensor::{Bmx280Error, Bmx280Sensor, BMX280_DEFAULT_ADDRESS};
+pub use calibrated::{
+    Calibrated, CalibrationProfile, Correction, ProfileError, CALIBRATION_PROFILE_LENGTH,
+};
+pub use composite_sensor::{CompositeError, CompositeReport, CompositeSensor, ErasedSensor, Route};
+pub use fault_injector::{FaultError, FaultInjector, FaultRates};
+pub use gas_index::{GasIndexAlgorithm, GasIndexKind};
 pub use mock_sensor::MockSensor;
-pub use scd4x_sensor::Scd4xSensor;
+pub use pms5003_sensor::{
+    FrameError, Pms5003Error, Pms5003Frame, Pms5003Mode, Pms5003Sensor, PMS5003_FRAME_LENGTH,
+};
+pub use pressure_compensated::PressureCompensated;
+pub use replay_sensor::{ReplayError, ReplaySensor};
+pub use retry::{Retry, RetryPolicy};
+pub use scd4x_async_sensor::AsyncScd4xSensor;
+pub use scd4x_sensor::{Compensation, MeasurementMode, Scd4xError, Scd4xSensor};
+pub use scenario_sensor::{Profile, Scenario, ScenarioError, ScenarioSensor, Signal};
+pub use sgp41_sensor::{Sgp41Error, Sgp41Sensor};
+pub use sht4x_sensor::{Heater, Precision, Sht4xError, Sht4xSensor, SHT4X_DEFAULT_ADDRESS};
+pub use sps30_sensor::{Sps30Error, Sps30Sensor};
+pub use stability_monitor::{StabilityMonitor, StabilityPolicy};
 
 pub trait Sensor {
     type Error;
 
-    fn measure(&mut self) -> Result<Data, Self::Error>;
+    fn measure(&mut self) -> Result<Measurement, Self::Error>;
+}
+
+/// Why a measurement failed, common to the errors of all sensors.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum ErrorKind {
+    /// Talking to the sensor failed, e.g. it didn't acknowledge on the I2C bus.
+    Bus,
+    /// The sensor's response was corrupted, e.g. its checksum didn't match.
+    Corrupted,
+    /// The sensor didn't have a measurement ready in time.
+    Timeout,
+    /// There was no measurement this time, the next one may succeed.
+    Unavailable,
+    /// The sensor won't produce any more measurements, e.g. a recording played back in full.
+    End,
+    Other,
+}
+
+/// An error of a [`Sensor`] that can tell its [`ErrorKind`], so errors of different sensors
+/// can be logged or handled alike.
+pub trait SensorError {
+    fn kind(&self) -> ErrorKind;
+}
+
+/// For sensors that can't fail, e.g. [`MockSensor`].
+impl SensorError for () {
+    fn kind(&self) -> ErrorKind {
+        ErrorKind::Other
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum SensorKind {
+    Mock,
+    /// Sensirion SCD40, SCD41 or SCD43 with firmware too old to report which one it is.
+    Scd4x,
+    /// Sensirion SCD40.
+    Scd40,
+    /// Sensirion SCD41.
+    Scd41,
+    /// Sensirion SCD43.
+    Scd43,
+    /// Sensirion SHT40, SHT41 or SHT45.
+    Sht4x,
+    /// Plantower PMS5003.
+    Pms5003,
+    /// Sensirion SPS30.
+    Sps30,
+    /// Sensirion SGP41.
+    Sgp41,
+    /// Bosch BMP280.
+    Bmp280,
+    /// Bosch BME280.
+    Bme280,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct SensorInfo {
+    pub kind: SensorKind,
+    pub serial_number: u64,
+    /// Major and minor version, if the sensor reports it.
+    pub firmware_version: Option<(u8, u8)>,
+}
+
+impl SensorInfo {
+    pub fn source_id(&self) -> SourceId {
+        SourceId(self.serial_number)
+    }
+}
+
+/// A sensor that can identify itself and check that it works before it is used.
+pub trait StartUp {
+    type Error;
+
+    /// Reads the sensor's identity and runs its self-test.
+    /// Measurements taken afterwards are tagged with [`SensorInfo::source_id`].
+    fn start_up(&mut self) -> Result<SensorInfo, Self::Error>;
+}
+
+/// A sensor that yields while it waits for a measurement instead of blocking.
+pub trait AsyncSensor {
+    type Error;
+
+    async fn measure(&mut self) -> Result<Measurement, Self::Error>;
+}
+
+/// Async counterpart of [`StartUp`].
+pub trait AsyncStartUp {
+    type Error;
+
+    async fn start_up(&mut self) -> Result<SensorInfo, Self::Error>;
+}
+
+/// A sensor whose CO2 readings can be recalibrated.
+pub trait Calibrate {
+    type Error;
+
+    /// Recalibrates the sensor against a known CO2 concentration `reference` in ppm,
+    /// returning the correction that was applied in ppm.
+    fn forced_recalibration(&mut self, reference: u16) -> Result<i16, Self::Error>;
+
+    /// Whether automatic self-calibration is enabled, which assumes the sensor is exposed
+    /// to fresh air at least once a week.
+    fn automatic_self_calibration(&mut self) -> Result<bool, Self::Error>;
+
+    fn set_automatic_self_calibration(&mut self, enabled: bool) -> Result<(), Self::Error>;
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum PowerState {
+    /// Measuring.
+    Active,
+    /// Ready to measure, responding to commands.
+    Idle,
+    /// Sleeping with the lowest supply current, has to be woken up before it responds again.
+    PowerDown,
+}
+
+/// A sensor that can save power between measurements, e.g. on battery.
+pub trait PowerManagement {
+    type Error;
+
+    fn power_state(&self) -> PowerState;
+
+    fn set_power_state(&mut self, state: PowerState) -> Result<(), Self::Error>;
+
+    /// Expected average supply current in `state` in µA, to budget the energy of a build.
+    fn supply_current(&self, state: PowerState) -> u32;
+}
+
+/// Async counterpart of [`PowerManagement`].
+pub trait AsyncPowerManagement {
+    type Error;
+
+    fn power_state(&self) -> PowerState;
+
+    async fn set_power_state(&mut self, state: PowerState) -> Result<(), Self::Error>;
+
+    /// Expected average supply current in `state` in µA.
+    fn supply_current(&self, state: PowerState) -> u32;
+}
+
+/// A sensor whose readings depend on the ambient pressure, e.g. NDIR CO2 sensors.
+pub trait PressureCompensation {
+    type Error;
+
+    /// Compensates subsequent measurements for the ambient `pressure` in hPa.
+    fn set_ambient_pressure(&mut self, pressure: u16) -> Result<(), Self::Error>;
 }
@@ -1,15 +1,18 @@
-use crate::Sensor;
-use airquamon_domain::Data;
-use embedded_hal::{delay::DelayUs, i2c::I2c};
-use scd4x::{Error, Scd4x};
+use crate::{AsyncSensor, Sensor, SensorInfo, SensorKind, StartUp};
+use airquamon_domain::{Clock, Data, Measurement, SourceId};
 
-pub struct MockSensor {
+pub struct MockSensor<CLOCK> {
+    clock: CLOCK,
     data: Data,
 }
 
-impl MockSensor {
-    pub fn new(co2: u16, temperature: f32, humidity: f32) -> Self {
+impl<CLOCK> MockSensor<CLOCK>
+where
+    CLOCK: Clock,
+{
+    pub fn new(clock: CLOCK, co2: u16, temperature: f32, humidity: f32) -> Self {
         Self {
+            clock,
             data: Data {
                 co2,
                 temperature,
@@ -19,10 +22,40 @@ impl MockSensor {
     }
 }
 
-impl Sensor for MockSensor {
+impl<CLOCK> Sensor for MockSensor<CLOCK>
+where
+    CLOCK: Clock,
+{
     type Error = ();
 
-    fn measure(&mut self) -> Result<Data, Self::Error> {
-        Ok(self.data)
+    fn measure(&mut self) -> Result<Measurement, Self::Error> {
+        Ok(Measurement::new(
+            self.clock.now(),
+            SourceId::UNKNOWN,
+            self.data,
+        ))
+    }
+}
+
+impl<CLOCK> AsyncSensor for MockSensor<CLOCK>
+where
+    CLOCK: Clock,
+{
+    type Error = ();
+
+    async fn measure(&mut self) -> Result<Measurement, Self::Error> {
+        Sensor::measure(self)
+    }
+}
+
+impl<CLOCK> StartUp for MockSensor<CLOCK> {
+    type Error = ();
+
+    fn start_up(&mut self) -> Result<SensorInfo, Self::Error> {
+        Ok(SensorInfo {
+            kind: SensorKind::Mock,
+            serial_number: SourceId::UNKNOWN.0,
+            firmware_version: None,
+        })
     }
 }
@@ -0,0 +1,207 @@
+use crate::{ErrorKind, Sensor, SensorError};
+use airquamon_domain::{Clock, Data, Measurement, ParticulateMatter, QualityFlags, SourceId};
+use embedded_io::blocking::{Read, ReadExactError, Write};
+
+const FRAME_START: [u8; 2] = [0x42, 0x4d];
+pub const PMS5003_FRAME_LENGTH: usize = 32;
+
+// Commands from the PMS5003 datasheet, the data byte follows the command
+const CHANGE_MODE: u8 = 0xe1;
+const READ_PASSIVE: u8 = 0xe2;
+const SLEEP: u8 = 0xe4;
+
+/// Data frame sent by the PMS5003, every second in active mode or when requested in passive
+/// mode.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct Pms5003Frame {
+    /// PM1.0, PM2.5 and PM10 in µg/m³ under standard particle conditions (CF=1), meant for
+    /// factory calibration.
+    pub standard: [u16; 3],
+    /// PM1.0, PM2.5 and PM10 in µg/m³ under atmospheric conditions.
+    pub atmospheric: [u16; 3],
+    /// Particles larger than 0.3, 0.5, 1.0, 2.5, 5.0 and 10 µm in 0.1 l of air.
+    pub particles: [u16; 6],
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum FrameError {
+    /// The frame doesn't start with 0x42 0x4d.
+    Start,
+    /// The frame length doesn't match a data frame.
+    Length,
+    Checksum,
+}
+
+impl Pms5003Frame {
+    pub fn parse(frame: &[u8; PMS5003_FRAME_LENGTH]) -> Result<Self, FrameError> {
+        if frame[..2] != FRAME_START {
+            return Err(FrameError::Start);
+        }
+        let word = |i: usize| u16::from_be_bytes([frame[2 * i], frame[2 * i + 1]]);
+        if usize::from(word(1)) != PMS5003_FRAME_LENGTH - 4 {
+            return Err(FrameError::Length);
+        }
+        // the checksum is the sum of all bytes before it
+        let checksum = frame[..PMS5003_FRAME_LENGTH - 2]
+            .iter()
+            .fold(0u16, |sum, &byte| sum.wrapping_add(byte.into()));
+        if checksum != word(15) {
+            return Err(FrameError::Checksum);
+        }
+        Ok(Self {
+            standard: [word(2), word(3), word(4)],
+            atmospheric: [word(5), word(6), word(7)],
+            particles: [word(8), word(9), word(10), word(11), word(12), word(13)],
+        })
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum Pms5003Error<E> {
+    Serial(E),
+    Frame(FrameError),
+    /// The serial port ended in the middle of a frame.
+    EndOfStream,
+}
+
+impl<E> From<ReadExactError<E>> for Pms5003Error<E> {
+    fn from(e: ReadExactError<E>) -> Self {
+        match e {
+            ReadExactError::UnexpectedEof => Pms5003Error::EndOfStream,
+            ReadExactError::Other(e) => Pms5003Error::Serial(e),
+        }
+    }
+}
+
+impl<E> From<FrameError> for Pms5003Error<E> {
+    fn from(e: FrameError) -> Self {
+        Pms5003Error::Frame(e)
+    }
+}
+
+impl<E> SensorError for Pms5003Error<E> {
+    fn kind(&self) -> ErrorKind {
+        match self {
+            Pms5003Error::Serial(_) | Pms5003Error::EndOfStream => ErrorKind::Bus,
+            Pms5003Error::Frame(_) => ErrorKind::Corrupted,
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum Pms5003Mode {
+    /// Sends a frame every second, the mode after power on.
+    #[default]
+    Active,
+    /// Only sends a frame when requested.
+    Passive,
+}
+
+/// Plantower PMS5003 particulate matter sensor on a 9600 baud UART.
+/// Only reports particulate matter, CO2, temperature and humidity are reported as missing.
+pub struct Pms5003Sensor<SERIAL, CLOCK> {
+    serial: SERIAL,
+    clock: CLOCK,
+    mode: Pms5003Mode,
+}
+
+impl<SERIAL, CLOCK> Pms5003Sensor<SERIAL, CLOCK>
+where
+    SERIAL: Read + Write,
+    CLOCK: Clock,
+{
+    pub fn new(serial: SERIAL, clock: CLOCK) -> Self {
+        Self {
+            serial,
+            clock,
+            mode: Pms5003Mode::default(),
+        }
+    }
+
+    pub fn mode(&self) -> Pms5003Mode {
+        self.mode
+    }
+
+    pub fn set_mode(&mut self, mode: Pms5003Mode) -> Result<(), Pms5003Error<SERIAL::Error>> {
+        self.write_command(CHANGE_MODE, (mode == Pms5003Mode::Active).into())?;
+        self.mode = mode;
+        Ok(())
+    }
+
+    /// Stops the fan and laser.
+    pub fn sleep(&mut self) -> Result<(), Pms5003Error<SERIAL::Error>> {
+        self.write_command(SLEEP, 0)
+    }
+
+    /// The fan needs at least 30 s after waking up before the readings are stable.
+    pub fn wake_up(&mut self) -> Result<(), Pms5003Error<SERIAL::Error>> {
+        self.write_command(SLEEP, 1)
+    }
+
+    pub fn release(self) -> SERIAL {
+        self.serial
+    }
+
+    fn write_command(&mut self, command: u8, data: u8) -> Result<(), Pms5003Error<SERIAL::Error>> {
+        let mut frame = [FRAME_START[0], FRAME_START[1], command, 0, data, 0, 0];
+        let checksum = frame[..5].iter().map(|&byte| u16::from(byte)).sum::<u16>();
+        frame[5..].copy_from_slice(&checksum.to_be_bytes());
+        self.serial
+            .write_all(&frame)
+            .map_err(Pms5003Error::Serial)?;
+        self.serial.flush().map_err(Pms5003Error::Serial)
+    }
+
+    /// Skips bytes until the start of a frame, e.g. when connecting in the middle of a frame.
+    fn synchronise(&mut self) -> Result<(), Pms5003Error<SERIAL::Error>> {
+        let mut previous = 0;
+        for _ in 0..2 * PMS5003_FRAME_LENGTH {
+            let mut byte = [0];
+            self.serial.read_exact(&mut byte)?;
+            if [previous, byte[0]] == FRAME_START {
+                return Ok(());
+            }
+            previous = byte[0];
+        }
+        Err(FrameError::Start.into())
+    }
+
+    fn read_frame(&mut self) -> Result<Pms5003Frame, Pms5003Error<SERIAL::Error>> {
+        let mut frame = [0; PMS5003_FRAME_LENGTH];
+        frame[..2].copy_from_slice(&FRAME_START);
+        // a response to a command may come before the data frame
+        for _ in 0..2 {
+            self.synchronise()?;
+            self.serial.read_exact(&mut frame[2..4])?;
+            let length = usize::from(u16::from_be_bytes([frame[2], frame[3]]));
+            if length < PMS5003_FRAME_LENGTH - 4 {
+                self.serial.read_exact(&mut frame[4..4 + length])?;
+                continue;
+            }
+            self.serial.read_exact(&mut frame[4..])?;
+            return Ok(Pms5003Frame::parse(&frame)?);
+        }
+        Err(FrameError::Length.into())
+    }
+}
+
+impl<SERIAL, CLOCK> Sensor for Pms5003Sensor<SERIAL, CLOCK>
+where
+    SERIAL: Read + Write,
+    CLOCK: Clock,
+{
+    type Error = Pms5003Error<SERIAL::Error>;
+
+    fn measure(&mut self) -> Result<Measurement, Self::Error> {
+        if self.mode == Pms5003Mode::Passive {
+            self.write_command(READ_PASSIVE, 0)?;
+        }
+        let frame = self.read_frame()?;
+        let [pm1_0, pm2_5, pm10] = frame.atmospheric.map(f32::from);
+        let mut measurement =
+            Measurement::new(self.clock.now(), SourceId::UNKNOWN, Data::default());
+        measurement.quality = QualityFlags::MISSING;
+        measurement.particulate_matter = Some(ParticulateMatter { pm1_0, pm2_5, pm10 });
+        Ok(measurement)
+    }
+}
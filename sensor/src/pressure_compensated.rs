@@ -0,0 +1,115 @@
+use crate::{PressureCompensation, Sensor};
+use airquamon_domain::{Measurement, Timestamp};
+use core::ops::RangeInclusive;
+use core::time::Duration;
+
+/// Ambient pressures in hPa the SCD4x can compensate for.
+const PRESSURE_RANGE: RangeInclusive<f32> = 700.0..=1200.0;
+
+/// Periodically measures the ambient pressure with a second sensor, e.g. a BMP280, and
+/// pushes it into the wrapped sensor so its readings stay accurate when the weather changes.
+///
+/// The pressure is measured before the first measurement and then whenever `interval` has
+/// passed since the last update. If measuring or pushing the pressure fails, or the pressure is
+/// outside of the 700 to 1200 hPa the SCD4x accepts, the previous value stays in use and the
+/// update is retried with the next measurement. Measurements are tagged with the last known
+/// pressure. Both sensors are expected to share a clock.
+pub struct PressureCompensated<S, P> {
+    sensor: S,
+    pressure_sensor: P,
+    interval: Duration,
+    /// Pressure in hPa and when it was measured.
+    last_update: Option<(f32, Timestamp)>,
+    last_measurement: Option<Timestamp>,
+}
+
+impl<S, P> PressureCompensated<S, P>
+where
+    S: Sensor + PressureCompensation,
+    P: Sensor,
+{
+    pub fn new(sensor: S, pressure_sensor: P, interval: Duration) -> Self {
+        Self {
+            sensor,
+            pressure_sensor,
+            interval,
+            last_update: None,
+            last_measurement: None,
+        }
+    }
+
+    /// The pressure in hPa last pushed into the wrapped sensor.
+    pub fn pressure(&self) -> Option<f32> {
+        self.last_update.map(|(pressure, _)| pressure)
+    }
+
+    pub fn inner(&self) -> &S {
+        &self.sensor
+    }
+
+    pub fn inner_mut(&mut self) -> &mut S {
+        &mut self.sensor
+    }
+
+    pub fn pressure_sensor(&self) -> &P {
+        &self.pressure_sensor
+    }
+
+    pub fn pressure_sensor_mut(&mut self) -> &mut P {
+        &mut self.pressure_sensor
+    }
+
+    pub fn into_inner(self) -> (S, P) {
+        (self.sensor, self.pressure_sensor)
+    }
+
+    fn update_is_due(&self) -> bool {
+        match (self.last_update, self.last_measurement) {
+            (Some((_, updated)), Some(measured)) => {
+                measured.duration_since(updated) >= self.interval
+            }
+            _ => true,
+        }
+    }
+
+    fn update_pressure(&mut self) {
+        let Ok(measurement) = self.pressure_sensor.measure() else {
+            return;
+        };
+        let Some(pressure) = measurement.validated().pressure else {
+            return;
+        };
+        // e.g. 0 hPa from a BMx280 with broken calibration data
+        if !PRESSURE_RANGE.contains(&pressure) {
+            return;
+        }
+        // the SCD4x takes whole hPa
+        if self
+            .sensor
+            .set_ambient_pressure(libm::roundf(pressure) as u16)
+            .is_ok()
+        {
+            self.last_update = Some((pressure, measurement.timestamp));
+        }
+    }
+}
+
+impl<S, P> Sensor for PressureCompensated<S, P>
+where
+    S: Sensor + PressureCompensation,
+    P: Sensor,
+{
+    type Error = <S as Sensor>::Error;
+
+    fn measure(&mut self) -> Result<Measurement, Self::Error> {
+        if self.update_is_due() {
+            self.update_pressure();
+        }
+        let mut measurement = self.sensor.measure()?;
+        self.last_measurement = Some(measurement.timestamp);
+        if measurement.pressure.is_none() {
+            measurement.pressure = self.pressure();
+        }
+        Ok(measurement)
+    }
+}
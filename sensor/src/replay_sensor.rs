@@ -0,0 +1,210 @@
+use crate::{ErrorKind, Sensor, SensorError};
+use airquamon_domain::{Data, Measurement, Quality, SourceId, Timestamp};
+use core::str::FromStr;
+use core::time::Duration;
+
+/// Column names of the optional header of a CSV recording.
+const CSV_HEADER: [&str; 4] = ["timestamp", "co2", "temperature", "humidity"];
+
+/// Gap between passes when looping a recording with a single measurement, the firmware's
+/// measurement interval.
+const DEFAULT_LOOP_GAP: Duration = Duration::from_secs(60);
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum ReplayError {
+    /// The whole recording has been played back and looping is disabled.
+    End,
+    /// The line of the CSV recording, counting from 1, isn't a valid measurement.
+    Parse { line: usize },
+}
+
+impl SensorError for ReplayError {
+    fn kind(&self) -> ErrorKind {
+        match self {
+            ReplayError::End => ErrorKind::End,
+            ReplayError::Parse { .. } => ErrorKind::Corrupted,
+        }
+    }
+}
+
+enum Recording<'a> {
+    Measurements(&'a [Measurement]),
+    Csv(&'a str),
+}
+
+/// Plays back a recorded series of measurements, e.g. a day in a meeting room.
+///
+/// Timestamps are replayed relative to the first measurement of the recording and multiplied
+/// by the time scale. When looping, the recording is repeated with timestamps continuing
+/// where the previous pass ended, one gap between its last two measurements later.
+pub struct ReplaySensor<'a> {
+    recording: Recording<'a>,
+    /// Index of the next measurement or byte offset of the next CSV line.
+    position: usize,
+    /// Number of the last CSV line read.
+    line: usize,
+    /// Whether the next CSV line can be the header, only the first one can.
+    header_allowed: bool,
+    looping: bool,
+    time_scale: f32,
+    first: Option<Timestamp>,
+    previous: Option<Timestamp>,
+    /// Time between the last two measurements, used as the gap when looping.
+    last_gap: Duration,
+    loop_offset: Duration,
+}
+
+impl<'a> ReplaySensor<'a> {
+    pub fn new(measurements: &'a [Measurement]) -> Self {
+        Self::with_recording(Recording::Measurements(measurements))
+    }
+
+    /// Replays a CSV recording with one `timestamp,co2,temperature,humidity` line per measurement,
+    /// timestamp in ms, CO2 in ppm, temperature in °C and relative humidity in %.
+    /// Empty values are replayed as missing. Empty lines, lines starting with `#` and a
+    /// `timestamp,co2,temperature,humidity` header as the first line are skipped, anything else
+    /// is a [`ReplayError::Parse`].
+    pub fn from_csv(csv: &'a str) -> Self {
+        Self::with_recording(Recording::Csv(csv))
+    }
+
+    fn with_recording(recording: Recording<'a>) -> Self {
+        Self {
+            recording,
+            position: 0,
+            line: 0,
+            header_allowed: true,
+            looping: false,
+            time_scale: 1.0,
+            first: None,
+            previous: None,
+            last_gap: Duration::ZERO,
+            loop_offset: Duration::ZERO,
+        }
+    }
+
+    pub fn with_looping(mut self, looping: bool) -> Self {
+        self.looping = looping;
+        self
+    }
+
+    /// E.g. 0.5 replays a recording in half the time it was recorded in.
+    pub fn with_time_scale(mut self, time_scale: f32) -> Self {
+        self.time_scale = time_scale;
+        self
+    }
+
+    fn rewind(&mut self) {
+        self.position = 0;
+        self.line = 0;
+        self.header_allowed = true;
+    }
+
+    fn next_record(&mut self) -> Option<Result<Measurement, ReplayError>> {
+        match self.recording {
+            Recording::Measurements(measurements) => {
+                let measurement = measurements.get(self.position)?;
+                self.position += 1;
+                Some(Ok(*measurement))
+            }
+            Recording::Csv(csv) => loop {
+                let rest = &csv[self.position..];
+                if rest.is_empty() {
+                    return None;
+                }
+                let (line, length) = match rest.find('\n') {
+                    Some(end) => (&rest[..end], end + 1),
+                    None => (rest, rest.len()),
+                };
+                self.position += length;
+                self.line += 1;
+
+                let line = line.trim();
+                if line.is_empty() || line.starts_with('#') {
+                    continue;
+                }
+                if core::mem::take(&mut self.header_allowed) && is_csv_header(line) {
+                    continue;
+                }
+                return Some(parse_csv_line(line).ok_or(ReplayError::Parse { line: self.line }));
+            },
+        }
+    }
+}
+
+impl<'a> Sensor for ReplaySensor<'a> {
+    type Error = ReplayError;
+
+    fn measure(&mut self) -> Result<Measurement, Self::Error> {
+        let record = match self.next_record() {
+            Some(record) => record?,
+            None => match (self.looping, self.first, self.previous) {
+                (true, Some(first), Some(previous)) => {
+                    let gap = if self.last_gap.is_zero() {
+                        DEFAULT_LOOP_GAP
+                    } else {
+                        self.last_gap
+                    };
+                    self.loop_offset += previous.duration_since(first) + gap;
+                    self.previous = None;
+                    self.rewind();
+                    self.next_record().ok_or(ReplayError::End)??
+                }
+                _ => return Err(ReplayError::End),
+            },
+        };
+
+        let first = *self.first.get_or_insert(record.timestamp);
+        if let Some(previous) = self.previous {
+            self.last_gap = record.timestamp.duration_since(previous);
+        }
+        self.previous = Some(record.timestamp);
+
+        let elapsed = record.timestamp.duration_since(first) + self.loop_offset;
+        let scaled = elapsed.as_millis() as f64 * f64::from(self.time_scale);
+        Ok(Measurement {
+            timestamp: Timestamp::from_millis(scaled as u64),
+            ..record
+        })
+    }
+}
+
+fn is_csv_header(line: &str) -> bool {
+    let mut columns = line.split(',').map(str::trim);
+    CSV_HEADER.iter().all(|name| {
+        columns
+            .next()
+            .is_some_and(|column| column.eq_ignore_ascii_case(name))
+    }) && columns.next().is_none()
+}
+
+fn parse_csv_line(line: &str) -> Option<Measurement> {
+    let mut fields = line.split(',').map(str::trim);
+    let timestamp = Timestamp::from_millis(fields.next()?.parse().ok()?);
+    let mut measurement = Measurement::new(timestamp, SourceId::UNKNOWN, Data::default());
+    parse_field(
+        fields.next()?,
+        &mut measurement.data.co2,
+        &mut measurement.quality.co2,
+    )?;
+    parse_field(
+        fields.next()?,
+        &mut measurement.data.temperature,
+        &mut measurement.quality.temperature,
+    )?;
+    parse_field(
+        fields.next()?,
+        &mut measurement.data.humidity,
+        &mut measurement.quality.humidity,
+    )?;
+    fields.next().is_none().then_some(measurement)
+}
+
+fn parse_field<T: FromStr>(field: &str, value: &mut T, quality: &mut Quality) -> Option<()> {
+    if field.is_empty() {
+        *quality = Quality::Missing;
+    } else {
+        *value = field.parse().ok()?;
+    }
+    Some(())
+}
@@ -0,0 +1,100 @@
+use crate::{PowerManagement, PowerState, Sensor};
+use airquamon_domain::Measurement;
+use embedded_hal::delay::DelayUs;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct RetryPolicy {
+    /// Attempts including the first one.
+    pub max_attempts: u8,
+    /// Delay before the first retry in ms, doubled for every further retry.
+    pub initial_backoff_ms: u32,
+    /// Upper bound for the delay between retries in ms.
+    pub max_backoff_ms: u32,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 3,
+            initial_backoff_ms: 500,
+            max_backoff_ms: 10_000,
+        }
+    }
+}
+
+/// Retries failed measurements of the wrapped sensor with exponential backoff,
+/// returning the last error once all attempts failed.
+pub struct Retry<S, DELAY> {
+    sensor: S,
+    delay: DELAY,
+    policy: RetryPolicy,
+}
+
+impl<S, DELAY> Retry<S, DELAY>
+where
+    S: Sensor,
+    DELAY: DelayUs,
+{
+    pub fn new(sensor: S, delay: DELAY, policy: RetryPolicy) -> Self {
+        Self {
+            sensor,
+            delay,
+            policy,
+        }
+    }
+
+    pub fn inner(&self) -> &S {
+        &self.sensor
+    }
+
+    pub fn inner_mut(&mut self) -> &mut S {
+        &mut self.sensor
+    }
+
+    pub fn into_inner(self) -> S {
+        self.sensor
+    }
+}
+
+impl<S, DELAY> Sensor for Retry<S, DELAY>
+where
+    S: Sensor,
+    DELAY: DelayUs,
+{
+    type Error = S::Error;
+
+    fn measure(&mut self) -> Result<Measurement, Self::Error> {
+        let mut backoff_ms = self.policy.initial_backoff_ms;
+        let mut attempt = 1;
+        loop {
+            match self.sensor.measure() {
+                Ok(measurement) => return Ok(measurement),
+                Err(e) if attempt >= self.policy.max_attempts => return Err(e),
+                Err(_) => {
+                    self.delay.delay_ms(backoff_ms);
+                    backoff_ms = backoff_ms.saturating_mul(2).min(self.policy.max_backoff_ms);
+                    attempt += 1;
+                }
+            }
+        }
+    }
+}
+
+impl<S, DELAY> PowerManagement for Retry<S, DELAY>
+where
+    S: PowerManagement,
+{
+    type Error = S::Error;
+
+    fn power_state(&self) -> PowerState {
+        self.sensor.power_state()
+    }
+
+    fn set_power_state(&mut self, state: PowerState) -> Result<(), Self::Error> {
+        self.sensor.set_power_state(state)
+    }
+
+    fn supply_current(&self, state: PowerState) -> u32 {
+        self.sensor.supply_current(state)
+    }
+}
@@ -0,0 +1,36 @@
+use core::f32::consts::PI;
+
+/// Small deterministic xorshift64* generator, good enough for simulated noise.
+#[derive(Clone, Debug)]
+pub(crate) struct Rng {
+    state: u64,
+}
+
+impl Rng {
+    pub(crate) fn new(seed: u64) -> Self {
+        let state = seed ^ 0x9E37_79B9_7F4A_7C15;
+        // xorshift never leaves the 0 state
+        Self {
+            state: if state == 0 { 1 } else { state },
+        }
+    }
+
+    pub(crate) fn next_u64(&mut self) -> u64 {
+        self.state ^= self.state >> 12;
+        self.state ^= self.state << 25;
+        self.state ^= self.state >> 27;
+        self.state.wrapping_mul(0x2545_F491_4F6C_DD1D)
+    }
+
+    /// Uniformly distributed in [0, 1).
+    pub(crate) fn next_f32(&mut self) -> f32 {
+        (self.next_u64() >> 40) as f32 / (1u64 << 24) as f32
+    }
+
+    /// Normally distributed with mean 0 and standard deviation 1 (Box-Muller).
+    pub(crate) fn gaussian(&mut self) -> f32 {
+        let u1 = 1.0 - self.next_f32();
+        let u2 = self.next_f32();
+        libm::sqrtf(-2.0 * libm::logf(u1)) * libm::cosf(2.0 * PI * u2)
+    }
+}
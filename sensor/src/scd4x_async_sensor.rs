@@ -0,0 +1,295 @@
+use crate::scd4x_commands::{
+    self, Command, ADDRESS, DATA_READY_POLL_INTERVAL_MS, GET_DATA_READY_STATUS, GET_SENSOR_VARIANT,
+    GET_SERIAL_NUMBER, MEASURE_SINGLE_SHOT, MEASURE_SINGLE_SHOT_RHT_ONLY, PERFORM_SELF_TEST,
+    POWER_DOWN, READ_MEASUREMENT, REINIT, START_LOW_POWER_PERIODIC_MEASUREMENT,
+    START_PERIODIC_MEASUREMENT, STOP_PERIODIC_MEASUREMENT, WAKE_UP,
+};
+use crate::sensirion;
+use crate::{
+    AsyncPowerManagement, AsyncSensor, AsyncStartUp, MeasurementMode, PowerState, Scd4xError,
+    SensorInfo, SensorKind,
+};
+use airquamon_domain::{Clock, Measurement, Quality, SourceId};
+use core::time::Duration;
+use embedded_hal_async::{delay::DelayUs, i2c::I2c};
+use scd4x::Error;
+
+/// Async counterpart of [`crate::Scd4xSensor`] with the same measurement modes, data ready
+/// timeout and power management, waiting on the delay instead of blocking.
+pub struct AsyncScd4xSensor<I2C, DELAY, CLOCK> {
+    i2c: I2C,
+    delay: DELAY,
+    clock: CLOCK,
+    source: SourceId,
+    mode: MeasurementMode,
+    /// Whether a periodic measurement has been started and not stopped since.
+    running: bool,
+    powered_down: bool,
+    /// The first single shot measurement after waking up has to be discarded.
+    discard_next: bool,
+    data_ready_timeout: Option<Duration>,
+}
+
+impl<I2C, DELAY, CLOCK> AsyncScd4xSensor<I2C, DELAY, CLOCK>
+where
+    I2C: I2c,
+    DELAY: DelayUs,
+    CLOCK: Clock,
+{
+    pub fn new(i2c: I2C, delay: DELAY, clock: CLOCK) -> Self {
+        Self {
+            i2c,
+            delay,
+            clock,
+            source: SourceId::UNKNOWN,
+            mode: MeasurementMode::default(),
+            running: false,
+            powered_down: false,
+            discard_next: false,
+            data_ready_timeout: None,
+        }
+    }
+
+    pub fn mode(&self) -> MeasurementMode {
+        self.mode
+    }
+
+    /// Switches the measurement mode, stopping a running periodic measurement.
+    /// The new mode takes effect on the next call to [`AsyncSensor::measure`].
+    pub async fn set_mode(&mut self, mode: MeasurementMode) -> Result<(), Error<I2C::Error>> {
+        if mode != self.mode {
+            self.stop().await?;
+            self.mode = mode;
+        }
+        Ok(())
+    }
+
+    /// Overrides how long [`AsyncSensor::measure`] waits for a measurement before giving up
+    /// with [`Scd4xError::Timeout`]. `None` waits twice the interval of the measurement mode.
+    pub fn set_data_ready_timeout(&mut self, timeout: Option<Duration>) {
+        self.data_ready_timeout = timeout;
+    }
+
+    /// Stops a running periodic measurement or wakes the sensor up, leaving it idle.
+    pub async fn stop(&mut self) -> Result<(), Error<I2C::Error>> {
+        self.wake_up().await;
+        if self.running {
+            self.write_command(STOP_PERIODIC_MEASUREMENT).await?;
+            self.running = false;
+        }
+        Ok(())
+    }
+
+    /// SCD41 only. Stops measuring and powers the sensor down until the next measurement,
+    /// which wakes it up again.
+    pub async fn power_down(&mut self) -> Result<(), Error<I2C::Error>> {
+        self.stop().await?;
+        self.write_command(POWER_DOWN).await?;
+        self.powered_down = true;
+        Ok(())
+    }
+
+    async fn write_command(
+        &mut self,
+        (command, delay_ms): Command,
+    ) -> Result<(), Error<I2C::Error>> {
+        self.i2c
+            .write(ADDRESS, &command.to_be_bytes())
+            .await
+            .map_err(Error::I2c)?;
+        self.delay.delay_ms(delay_ms).await;
+        Ok(())
+    }
+
+    async fn read_words<const N: usize>(
+        &mut self,
+        command: Command,
+    ) -> Result<[u16; N], Error<I2C::Error>> {
+        self.write_command(command).await?;
+        // 3 bytes per word, can't be expressed as an array length with const generics yet
+        let mut buf = [0; 9];
+        let buf = &mut buf[..N * 3];
+        self.i2c.read(ADDRESS, buf).await.map_err(Error::I2c)?;
+        let mut words = [0; N];
+        sensirion::decode_words(buf, &mut words)?;
+        Ok(words)
+    }
+
+    /// SCD40, SCD41 or SCD43, `None` if the sensor's firmware is too old to report it.
+    async fn variant(&mut self) -> Option<SensorKind> {
+        let [variant] = self.read_words(GET_SENSOR_VARIANT).await.ok()?;
+        scd4x_commands::variant(variant)
+    }
+
+    async fn force_wake_up(&mut self) {
+        // the sensor doesn't acknowledge the wake up command
+        let _ = self.write_command(WAKE_UP).await;
+    }
+
+    async fn wake_up(&mut self) {
+        if self.powered_down {
+            self.force_wake_up().await;
+            self.powered_down = false;
+            self.discard_next = true;
+        }
+    }
+
+    async fn start_periodic_measurement(&mut self) -> Result<(), Error<I2C::Error>> {
+        self.force_wake_up().await;
+        self.powered_down = false;
+        self.write_command(REINIT).await?;
+        match self.mode {
+            MeasurementMode::LowPowerPeriodic => {
+                self.write_command(START_LOW_POWER_PERIODIC_MEASUREMENT)
+                    .await?
+            }
+            _ => self.write_command(START_PERIODIC_MEASUREMENT).await?,
+        }
+        self.running = true;
+        Ok(())
+    }
+
+    async fn measure_single_shot(&mut self) -> Result<(), Error<I2C::Error>> {
+        match self.mode {
+            MeasurementMode::SingleShotRht => {
+                self.write_command(MEASURE_SINGLE_SHOT_RHT_ONLY).await
+            }
+            _ => self.write_command(MEASURE_SINGLE_SHOT).await,
+        }
+    }
+
+    async fn wait_for_data_ready(&mut self) -> Result<(), Scd4xError<I2C::Error>> {
+        let timeout = self
+            .data_ready_timeout
+            .unwrap_or(self.mode.data_ready_timeout());
+        let mut waited = Duration::ZERO;
+        loop {
+            let [status] = self.read_words(GET_DATA_READY_STATUS).await?;
+            if scd4x_commands::is_data_ready(status) {
+                return Ok(());
+            }
+            if waited >= timeout {
+                return Err(Scd4xError::Timeout);
+            }
+            self.delay.delay_ms(DATA_READY_POLL_INTERVAL_MS).await;
+            waited += Duration::from_millis(DATA_READY_POLL_INTERVAL_MS.into());
+        }
+    }
+}
+
+impl<I2C, DELAY, CLOCK> AsyncSensor for AsyncScd4xSensor<I2C, DELAY, CLOCK>
+where
+    I2C: I2c,
+    DELAY: DelayUs,
+    CLOCK: Clock,
+{
+    type Error = Scd4xError<I2C::Error>;
+
+    /// Wakes the sensor up if it was powered down.
+    async fn measure(&mut self) -> Result<Measurement, Self::Error> {
+        self.wake_up().await;
+        match self.mode {
+            MeasurementMode::Periodic | MeasurementMode::LowPowerPeriodic => {
+                if !self.running {
+                    self.start_periodic_measurement().await?;
+                }
+            }
+            MeasurementMode::SingleShot | MeasurementMode::SingleShotRht => {
+                if self.discard_next {
+                    self.measure_single_shot().await?;
+                    self.wait_for_data_ready().await?;
+                    self.read_words::<3>(READ_MEASUREMENT).await?;
+                }
+                self.measure_single_shot().await?;
+            }
+        }
+        self.discard_next = false;
+        self.wait_for_data_ready().await?;
+        let words = self.read_words(READ_MEASUREMENT).await?;
+        let mut measurement =
+            Measurement::new(self.clock.now(), self.source, scd4x_commands::data(words));
+        if self.mode == MeasurementMode::SingleShotRht {
+            measurement.quality.co2 = Quality::Missing;
+        }
+        Ok(measurement)
+    }
+}
+
+impl<I2C, DELAY, CLOCK> AsyncPowerManagement for AsyncScd4xSensor<I2C, DELAY, CLOCK>
+where
+    I2C: I2c,
+    DELAY: DelayUs,
+    CLOCK: Clock,
+{
+    type Error = Error<I2C::Error>;
+
+    /// Active while a periodic measurement is running, single shot measurements idle in between.
+    fn power_state(&self) -> PowerState {
+        if self.powered_down {
+            PowerState::PowerDown
+        } else if self.running {
+            PowerState::Active
+        } else {
+            PowerState::Idle
+        }
+    }
+
+    /// Active starts a periodic measurement in the periodic modes, otherwise it only wakes the
+    /// sensor up. Power-down is only supported by the SCD41.
+    async fn set_power_state(&mut self, state: PowerState) -> Result<(), Self::Error> {
+        match state {
+            PowerState::Active => {
+                self.wake_up().await;
+                match self.mode {
+                    MeasurementMode::Periodic | MeasurementMode::LowPowerPeriodic
+                        if !self.running =>
+                    {
+                        self.start_periodic_measurement().await
+                    }
+                    _ => Ok(()),
+                }
+            }
+            PowerState::Idle => self.stop().await,
+            PowerState::PowerDown => self.power_down().await,
+        }
+    }
+
+    fn supply_current(&self, state: PowerState) -> u32 {
+        scd4x_commands::supply_current(self.mode, state)
+    }
+}
+
+impl<I2C, DELAY, CLOCK> AsyncStartUp for AsyncScd4xSensor<I2C, DELAY, CLOCK>
+where
+    I2C: I2c,
+    DELAY: DelayUs,
+    CLOCK: Clock,
+{
+    type Error = Scd4xError<I2C::Error>;
+
+    /// Takes about 10 s for the self-test. Sensors too old to report their variant are reported
+    /// as [`SensorKind::Scd4x`].
+    async fn start_up(&mut self) -> Result<SensorInfo, Self::Error> {
+        self.force_wake_up().await;
+        // the sensor keeps measuring if only the MCU was reset
+        self.write_command(STOP_PERIODIC_MEASUREMENT).await?;
+        self.running = false;
+        self.powered_down = false;
+
+        let serial_number =
+            scd4x_commands::serial_number(self.read_words(GET_SERIAL_NUMBER).await?);
+        let kind = self.variant().await.unwrap_or(SensorKind::Scd4x);
+        let [self_test] = self.read_words(PERFORM_SELF_TEST).await?;
+        if self_test != 0 {
+            return Err(Scd4xError::SelfTestFailed);
+        }
+        let info = SensorInfo {
+            kind,
+            serial_number,
+            // the SCD4x has no command to read its firmware version
+            firmware_version: None,
+        };
+        self.source = info.source_id();
+        Ok(info)
+    }
+}
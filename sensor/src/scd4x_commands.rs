@@ -0,0 +1,82 @@
+//! Commands of the SCD4x shared by [`crate::Scd4xSensor`], for the commands its driver doesn't
+//! support, and [`crate::AsyncScd4xSensor`], which talks to the sensor directly.
+
+use crate::sensirion::CrcError;
+use crate::{MeasurementMode, PowerState, SensorKind};
+use airquamon_domain::Data;
+use scd4x::Error;
+
+pub(crate) const ADDRESS: u8 = 0x62;
+
+/// A command and its execution time in ms from the SCD4x datasheet.
+pub(crate) type Command = (u16, u32);
+
+pub(crate) const START_PERIODIC_MEASUREMENT: Command = (0x21b1, 0);
+pub(crate) const START_LOW_POWER_PERIODIC_MEASUREMENT: Command = (0x21ac, 0);
+pub(crate) const READ_MEASUREMENT: Command = (0xec05, 1);
+pub(crate) const STOP_PERIODIC_MEASUREMENT: Command = (0x3f86, 500);
+pub(crate) const GET_DATA_READY_STATUS: Command = (0xe4b8, 1);
+pub(crate) const GET_SERIAL_NUMBER: Command = (0x3682, 1);
+pub(crate) const PERFORM_SELF_TEST: Command = (0x3639, 10_000);
+pub(crate) const REINIT: Command = (0x3646, 20);
+pub(crate) const MEASURE_SINGLE_SHOT: Command = (0x219d, 5000);
+pub(crate) const MEASURE_SINGLE_SHOT_RHT_ONLY: Command = (0x2196, 50);
+pub(crate) const POWER_DOWN: Command = (0x36e0, 1);
+pub(crate) const WAKE_UP: Command = (0x36f6, 20);
+/// Not supported by the driver, newer sensor firmware only.
+pub(crate) const GET_SENSOR_VARIANT: Command = (0x202f, 1);
+
+/// How often the data ready status is polled while waiting for a measurement.
+pub(crate) const DATA_READY_POLL_INTERVAL_MS: u32 = 100;
+
+/// Approximate average supply currents in µA at 3.3 V, from the SCD41 datasheet.
+const PERIODIC_SUPPLY_CURRENT: u32 = 15_000;
+const LOW_POWER_PERIODIC_SUPPLY_CURRENT: u32 = 3_200;
+const IDLE_SUPPLY_CURRENT: u32 = 200;
+/// Not specified in the datasheet, leakage only.
+const POWER_DOWN_SUPPLY_CURRENT: u32 = 1;
+
+impl<E> From<CrcError> for Error<E> {
+    fn from(_: CrcError) -> Self {
+        Error::Crc
+    }
+}
+
+pub(crate) fn is_data_ready(status: u16) -> bool {
+    status & 0x07ff != 0
+}
+
+/// Converts the CO2, temperature and humidity words of a measurement.
+pub(crate) fn data([co2, temperature, humidity]: [u16; 3]) -> Data {
+    Data {
+        co2,
+        temperature: -45.0 + 175.0 * f32::from(temperature) / 65536.0,
+        humidity: 100.0 * f32::from(humidity) / 65536.0,
+    }
+}
+
+pub(crate) fn serial_number([high, middle, low]: [u16; 3]) -> u64 {
+    (u64::from(high) << 32) | (u64::from(middle) << 16) | u64::from(low)
+}
+
+/// SCD40, SCD41 or SCD43 from the response to [`GET_SENSOR_VARIANT`].
+pub(crate) fn variant(word: u16) -> Option<SensorKind> {
+    match word & 0xf000 {
+        0x0000 => Some(SensorKind::Scd40),
+        0x1000 => Some(SensorKind::Scd41),
+        0x5000 => Some(SensorKind::Scd43),
+        _ => None,
+    }
+}
+
+/// Expected average supply current in µA in `state` while measuring in `mode`.
+pub(crate) fn supply_current(mode: MeasurementMode, state: PowerState) -> u32 {
+    match state {
+        PowerState::Active => match mode {
+            MeasurementMode::LowPowerPeriodic => LOW_POWER_PERIODIC_SUPPLY_CURRENT,
+            _ => PERIODIC_SUPPLY_CURRENT,
+        },
+        PowerState::Idle => IDLE_SUPPLY_CURRENT,
+        PowerState::PowerDown => POWER_DOWN_SUPPLY_CURRENT,
+    }
+}
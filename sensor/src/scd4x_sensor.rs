@@ -1,54 +1,453 @@
-use crate::Sensor;
-use airquamon_domain::Data;
+use crate::scd4x_commands::{self, ADDRESS, DATA_READY_POLL_INTERVAL_MS, GET_SENSOR_VARIANT};
+use crate::sensirion;
+use crate::{
+    Calibrate, ErrorKind, PowerManagement, PowerState, PressureCompensation, Sensor, SensorError,
+    SensorInfo, SensorKind, StartUp,
+};
+use airquamon_domain::{Clock, Data, Measurement, Quality, SourceId};
+use core::time::Duration;
 use embedded_hal::{delay::DelayUs, i2c::I2c};
 use scd4x::{Error, Scd4x};
 
-pub struct Scd4xSensor<I2C, DELAY> {
-    scd4x: Scd4x<I2C, DELAY>,
+/// How the sensor takes measurements and which state it is left in between calls to
+/// [`Sensor::measure`].
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum MeasurementMode {
+    /// Measures every 5 s, keeps running between calls.
+    #[default]
+    Periodic,
+    /// Measures every 30 s, keeps running between calls.
+    LowPowerPeriodic,
+    /// SCD41 only. Measures on demand (5 s), idles between calls.
+    SingleShot,
+    /// SCD41 only. Measures temperature and humidity on demand (50 ms), idles between calls.
+    /// CO2 is reported as missing.
+    SingleShotRht,
+}
+
+impl MeasurementMode {
+    /// How long it usually takes until a new measurement is ready.
+    pub fn interval(self) -> Duration {
+        match self {
+            MeasurementMode::Periodic | MeasurementMode::SingleShot => Duration::from_secs(5),
+            MeasurementMode::LowPowerPeriodic => Duration::from_secs(30),
+            MeasurementMode::SingleShotRht => Duration::from_millis(50),
+        }
+    }
+
+    /// How long to wait for a measurement unless overridden, see
+    /// [`Scd4xSensor::set_data_ready_timeout`].
+    pub(crate) fn data_ready_timeout(self) -> Duration {
+        self.interval() * 2
+    }
+}
+
+#[derive(Debug)]
+pub enum Scd4xError<E> {
+    Sensor(Error<E>),
+    /// No measurement was ready within the data ready timeout.
+    Timeout,
+    SelfTestFailed,
+}
+
+impl<E> From<Error<E>> for Scd4xError<E> {
+    fn from(e: Error<E>) -> Self {
+        Scd4xError::Sensor(e)
+    }
+}
+
+impl<E> SensorError for Scd4xError<E> {
+    fn kind(&self) -> ErrorKind {
+        match self {
+            Scd4xError::Sensor(Error::I2c(_)) => ErrorKind::Bus,
+            Scd4xError::Sensor(Error::Crc) => ErrorKind::Corrupted,
+            Scd4xError::Timeout => ErrorKind::Timeout,
+            _ => ErrorKind::Other,
+        }
+    }
+}
+
+/// Settings compensating for where the sensor is installed.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Compensation {
+    /// Subtracted from the measured temperature in °C, accounts for self-heating of the sensor
+    /// and its enclosure. The factory default is 4 °C.
+    pub temperature_offset: f32,
+    /// Height above sea level in m, ignored once an ambient pressure is set.
+    pub altitude: u16,
+    /// Ambient pressure in hPa. Not stored on the sensor, has to be set again after a restart.
+    pub ambient_pressure: Option<u16>,
+}
+
+pub struct Scd4xSensor<I2C, DELAY, CLOCK> {
+    i2c: I2C,
     delay: DELAY,
+    clock: CLOCK,
+    source: SourceId,
+    mode: MeasurementMode,
+    /// Whether a periodic measurement has been started and not stopped since.
+    running: bool,
+    powered_down: bool,
+    /// The first single shot measurement after waking up has to be discarded.
+    discard_next: bool,
+    data_ready_timeout: Option<Duration>,
 }
 
-impl<I2C, DELAY> Scd4xSensor<I2C, DELAY>
+impl<I2C, DELAY, CLOCK> Scd4xSensor<I2C, DELAY, CLOCK>
 where
     I2C: I2c,
-    DELAY: DelayUs + Copy,
+    DELAY: DelayUs,
+    CLOCK: Clock,
 {
-    pub fn new(i2c: I2C, delay: DELAY) -> Self {
+    /// To put other sensors on the same bus `i2c` can be a device of `embedded-hal-bus`, e.g.
+    /// `RefCellDevice` as in `examples/shared_i2c.rs`, or `CriticalSectionDevice` if the bus is
+    /// also used from interrupts. `i2c` and `delay` can also be borrowed with `&mut` when the
+    /// sensor is only needed for a while.
+    pub fn new(i2c: I2C, delay: DELAY, clock: CLOCK) -> Self {
         Self {
-            scd4x: Scd4x::new(i2c, delay),
+            i2c,
             delay,
+            clock,
+            source: SourceId::UNKNOWN,
+            mode: MeasurementMode::default(),
+            running: false,
+            powered_down: false,
+            discard_next: false,
+            data_ready_timeout: None,
+        }
+    }
+
+    pub fn mode(&self) -> MeasurementMode {
+        self.mode
+    }
+
+    /// Switches the measurement mode, stopping a running periodic measurement.
+    /// The new mode takes effect on the next call to [`Sensor::measure`].
+    pub fn set_mode(&mut self, mode: MeasurementMode) -> Result<(), Error<I2C::Error>> {
+        if mode != self.mode {
+            self.stop()?;
+            self.mode = mode;
+        }
+        Ok(())
+    }
+
+    /// Overrides how long [`Sensor::measure`] waits for a measurement before giving up with
+    /// [`Scd4xError::Timeout`]. `None` waits twice the interval of the measurement mode.
+    pub fn set_data_ready_timeout(&mut self, timeout: Option<Duration>) {
+        self.data_ready_timeout = timeout;
+    }
+
+    /// Stops a running periodic measurement or wakes the sensor up, leaving it idle.
+    pub fn stop(&mut self) -> Result<(), Error<I2C::Error>> {
+        self.wake_up();
+        if self.running {
+            self.scd4x().stop_periodic_measurement()?;
+            self.running = false;
+        }
+        Ok(())
+    }
+
+    pub fn temperature_offset(&mut self) -> Result<f32, Error<I2C::Error>> {
+        self.stop()?;
+        self.scd4x().temperature_offset()
+    }
+
+    pub fn set_temperature_offset(&mut self, offset: f32) -> Result<(), Error<I2C::Error>> {
+        self.stop()?;
+        self.scd4x().set_temperature_offset(offset)
+    }
+
+    pub fn altitude(&mut self) -> Result<u16, Error<I2C::Error>> {
+        self.stop()?;
+        self.scd4x().altitude()
+    }
+
+    pub fn set_altitude(&mut self, altitude: u16) -> Result<(), Error<I2C::Error>> {
+        self.stop()?;
+        self.scd4x().set_altitude(altitude)
+    }
+
+    /// Can be set while a periodic measurement is running, unlike the other settings.
+    pub fn set_ambient_pressure(&mut self, pressure: u16) -> Result<(), Error<I2C::Error>> {
+        self.wake_up();
+        self.scd4x().set_ambient_pressure(pressure)
+    }
+
+    /// The temperature offset and altitude currently used by the sensor.
+    /// The ambient pressure can't be read back and is always `None`.
+    pub fn compensation(&mut self) -> Result<Compensation, Error<I2C::Error>> {
+        Ok(Compensation {
+            temperature_offset: self.temperature_offset()?,
+            altitude: self.altitude()?,
+            ambient_pressure: None,
+        })
+    }
+
+    /// Applies `compensation`. With `persist` the temperature offset and altitude are also
+    /// written to the EEPROM if they differ from the current ones, so they survive a power cycle.
+    /// The EEPROM is only rated for 2000 write cycles.
+    pub fn set_compensation(
+        &mut self,
+        compensation: &Compensation,
+        persist: bool,
+    ) -> Result<(), Error<I2C::Error>> {
+        let current = self.compensation()?;
+        // the offset is stored with a resolution of 175 / 2^16 °C
+        let offset_difference = current.temperature_offset - compensation.temperature_offset;
+        let changed = !(-0.01..=0.01).contains(&offset_difference)
+            || current.altitude != compensation.altitude;
+        if changed {
+            self.set_temperature_offset(compensation.temperature_offset)?;
+            self.set_altitude(compensation.altitude)?;
+            if persist {
+                self.persist_settings()?;
+            }
+        }
+        if let Some(pressure) = compensation.ambient_pressure {
+            self.set_ambient_pressure(pressure)?;
+        }
+        Ok(())
+    }
+
+    /// Writes the current settings to the EEPROM, see [`Self::set_compensation`].
+    pub fn persist_settings(&mut self) -> Result<(), Error<I2C::Error>> {
+        self.stop()?;
+        self.scd4x().persist_settings()
+    }
+
+    /// SCD41 only. Stops measuring and powers the sensor down until the next measurement or
+    /// change of settings, which wake it up again.
+    pub fn power_down(&mut self) -> Result<(), Error<I2C::Error>> {
+        self.stop()?;
+        self.scd4x().power_down()?;
+        self.powered_down = true;
+        Ok(())
+    }
+
+    /// The driver borrows the bus and delay for each command, so commands it doesn't support
+    /// can be sent directly.
+    fn scd4x(&mut self) -> Scd4x<&mut I2C, &mut DELAY> {
+        Scd4x::new(&mut self.i2c, &mut self.delay)
+    }
+
+    /// SCD40, SCD41 or SCD43, `None` if the sensor's firmware is too old to report it.
+    fn variant(&mut self) -> Option<SensorKind> {
+        let (command, delay_ms) = GET_SENSOR_VARIANT;
+        let mut buf = [0; 3];
+        self.i2c.write(ADDRESS, &command.to_be_bytes()).ok()?;
+        self.delay.delay_ms(delay_ms);
+        self.i2c.read(ADDRESS, &mut buf).ok()?;
+        let mut variant = [0];
+        sensirion::decode_words(&buf, &mut variant).ok()?;
+        scd4x_commands::variant(variant[0])
+    }
+
+    fn wake_up(&mut self) {
+        if self.powered_down {
+            // the sensor doesn't acknowledge the command, the driver ignores the error
+            self.scd4x().wake_up();
+            self.powered_down = false;
+            self.discard_next = true;
+        }
+    }
+
+    fn start_periodic_measurement(&mut self) -> Result<(), Error<I2C::Error>> {
+        self.scd4x().wake_up();
+        self.powered_down = false;
+        self.scd4x().reinit()?;
+        match self.mode {
+            MeasurementMode::LowPowerPeriodic => {
+                self.scd4x().start_low_power_periodic_measurements()?
+            }
+            _ => self.scd4x().start_periodic_measurement()?,
+        }
+        self.running = true;
+        Ok(())
+    }
+
+    fn measure_single_shot(&mut self) -> Result<(), Error<I2C::Error>> {
+        match self.mode {
+            MeasurementMode::SingleShotRht => self.scd4x().measure_single_shot_rht(),
+            _ => self.scd4x().measure_single_shot(),
+        }
+    }
+
+    fn wait_for_data_ready(&mut self) -> Result<(), Scd4xError<I2C::Error>> {
+        let timeout = self
+            .data_ready_timeout
+            .unwrap_or(self.mode.data_ready_timeout());
+        let mut waited = Duration::ZERO;
+        while !self.scd4x().data_ready_status()? {
+            if waited >= timeout {
+                return Err(Scd4xError::Timeout);
+            }
+            self.delay.delay_ms(DATA_READY_POLL_INTERVAL_MS);
+            waited += Duration::from_millis(DATA_READY_POLL_INTERVAL_MS.into());
+        }
+        Ok(())
+    }
+}
+
+impl<I2C, DELAY, CLOCK> Sensor for Scd4xSensor<I2C, DELAY, CLOCK>
+where
+    I2C: I2c,
+    DELAY: DelayUs,
+    CLOCK: Clock,
+{
+    type Error = Scd4xError<I2C::Error>;
+
+    /// Wakes the sensor up if it was powered down.
+    fn measure(&mut self) -> Result<Measurement, Self::Error> {
+        self.wake_up();
+        match self.mode {
+            MeasurementMode::Periodic | MeasurementMode::LowPowerPeriodic => {
+                if !self.running {
+                    self.start_periodic_measurement()?;
+                }
+            }
+            MeasurementMode::SingleShot | MeasurementMode::SingleShotRht => {
+                if self.discard_next {
+                    self.measure_single_shot()?;
+                    self.wait_for_data_ready()?;
+                    self.scd4x().measurement()?;
+                }
+                self.measure_single_shot()?;
+            }
         }
+        self.discard_next = false;
+        self.wait_for_data_ready()?;
+        let data = self.scd4x().measurement()?;
+        let mut measurement = Measurement::new(
+            self.clock.now(),
+            self.source,
+            Data {
+                co2: data.co2,
+                temperature: data.temperature,
+                humidity: data.humidity,
+            },
+        );
+        if self.mode == MeasurementMode::SingleShotRht {
+            measurement.quality.co2 = Quality::Missing;
+        }
+        Ok(measurement)
     }
 }
 
-impl<I2C, DELAY> Sensor for Scd4xSensor<I2C, DELAY>
+impl<I2C, DELAY, CLOCK> Calibrate for Scd4xSensor<I2C, DELAY, CLOCK>
 where
     I2C: I2c,
     DELAY: DelayUs,
+    CLOCK: Clock,
 {
     type Error = Error<I2C::Error>;
 
-    fn measure(&mut self) -> Result<Data, Self::Error> {
-        self.scd4x.wake_up();
-        self.scd4x.reinit()?;
-        self.scd4x.start_periodic_measurement()?;
-        self.delay.delay_ms(5000);
-        loop {
-            match self.scd4x.data_ready_status() {
-                Ok(true) => break,
-                Ok(false) => {
-                    self.delay.delay_ms(100);
-                    Ok(())
+    /// The sensor should have been measuring periodically in the reference environment
+    /// for at least 3 minutes beforehand.
+    fn forced_recalibration(&mut self, reference: u16) -> Result<i16, Self::Error> {
+        self.stop()?;
+        let correction = self.scd4x().forced_recalibration(reference)?;
+        Ok(correction as i16)
+    }
+
+    fn automatic_self_calibration(&mut self) -> Result<bool, Self::Error> {
+        self.stop()?;
+        self.scd4x().automatic_self_calibration()
+    }
+
+    fn set_automatic_self_calibration(&mut self, enabled: bool) -> Result<(), Self::Error> {
+        self.stop()?;
+        self.scd4x().set_automatic_self_calibration(enabled)
+    }
+}
+
+impl<I2C, DELAY, CLOCK> PressureCompensation for Scd4xSensor<I2C, DELAY, CLOCK>
+where
+    I2C: I2c,
+    DELAY: DelayUs,
+    CLOCK: Clock,
+{
+    type Error = Error<I2C::Error>;
+
+    fn set_ambient_pressure(&mut self, pressure: u16) -> Result<(), Self::Error> {
+        Scd4xSensor::set_ambient_pressure(self, pressure)
+    }
+}
+
+impl<I2C, DELAY, CLOCK> PowerManagement for Scd4xSensor<I2C, DELAY, CLOCK>
+where
+    I2C: I2c,
+    DELAY: DelayUs,
+    CLOCK: Clock,
+{
+    type Error = Error<I2C::Error>;
+
+    /// Active while a periodic measurement is running, single shot measurements idle in between.
+    fn power_state(&self) -> PowerState {
+        if self.powered_down {
+            PowerState::PowerDown
+        } else if self.running {
+            PowerState::Active
+        } else {
+            PowerState::Idle
+        }
+    }
+
+    /// Active starts a periodic measurement in the periodic modes, otherwise it only wakes the
+    /// sensor up. Power-down is only supported by the SCD41.
+    fn set_power_state(&mut self, state: PowerState) -> Result<(), Self::Error> {
+        match state {
+            PowerState::Active => {
+                self.wake_up();
+                match self.mode {
+                    MeasurementMode::Periodic | MeasurementMode::LowPowerPeriodic
+                        if !self.running =>
+                    {
+                        self.start_periodic_measurement()
+                    }
+                    _ => Ok(()),
                 }
-                Err(e) => Err(e),
-            }?;
-        }
-        let data = self.scd4x.measurement()?;
-        self.scd4x.stop_periodic_measurement()?;
-        Ok(Data {
-            co2: data.co2,
-            temperature: data.temperature,
-            humidity: data.humidity,
-        })
+            }
+            PowerState::Idle => self.stop(),
+            PowerState::PowerDown => self.power_down(),
+        }
+    }
+
+    /// Active depends on the measurement mode, the single shot modes draw about as much as a
+    /// periodic measurement while they measure.
+    fn supply_current(&self, state: PowerState) -> u32 {
+        scd4x_commands::supply_current(self.mode, state)
+    }
+}
+
+impl<I2C, DELAY, CLOCK> StartUp for Scd4xSensor<I2C, DELAY, CLOCK>
+where
+    I2C: I2c,
+    DELAY: DelayUs,
+    CLOCK: Clock,
+{
+    type Error = Scd4xError<I2C::Error>;
+
+    /// Takes about 10 s for the self-test. Sensors too old to report their variant are reported
+    /// as [`SensorKind::Scd4x`].
+    fn start_up(&mut self) -> Result<SensorInfo, Self::Error> {
+        self.scd4x().wake_up();
+        // the sensor keeps measuring if only the MCU was reset
+        self.scd4x().stop_periodic_measurement()?;
+        self.running = false;
+        self.powered_down = false;
+
+        let serial_number = self.scd4x().serial_number()?;
+        let kind = self.variant().unwrap_or(SensorKind::Scd4x);
+        if !self.scd4x().self_test_is_ok()? {
+            return Err(Scd4xError::SelfTestFailed);
+        }
+        let info = SensorInfo {
+            kind,
+            serial_number,
+            // the SCD4x has no command to read its firmware version
+            firmware_version: None,
+        };
+        self.source = info.source_id();
+        Ok(info)
     }
 }
@@ -0,0 +1,206 @@
+use crate::{rng::Rng, ErrorKind, Sensor, SensorError};
+use airquamon_domain::{Data, Measurement, SourceId, Timestamp};
+use core::{f32::consts::PI, time::Duration};
+
+/// How a value develops over the time elapsed since the start of a scenario.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Profile {
+    Constant(f32),
+    /// Changes linearly from `from` to `to` between `start` and `start + duration`.
+    Ramp {
+        from: f32,
+        to: f32,
+        start: Duration,
+        duration: Duration,
+    },
+    /// Jumps by `change` at `at`, 0 before.
+    Step {
+        change: f32,
+        at: Duration,
+    },
+    /// Oscillates around 0, e.g. people coming and going over the day.
+    Sine {
+        amplitude: f32,
+        period: Duration,
+        phase: Duration,
+    },
+}
+
+impl Profile {
+    pub fn value(&self, elapsed: Duration) -> f32 {
+        match *self {
+            Profile::Constant(value) => value,
+            Profile::Ramp {
+                from,
+                to,
+                start,
+                duration,
+            } => {
+                let progress = if elapsed <= start {
+                    0.0
+                } else if elapsed >= start + duration {
+                    1.0
+                } else {
+                    (elapsed - start).as_secs_f32() / duration.as_secs_f32()
+                };
+                from + (to - from) * progress
+            }
+            Profile::Step { change, at } => {
+                if elapsed >= at {
+                    change
+                } else {
+                    0.0
+                }
+            }
+            Profile::Sine {
+                amplitude,
+                period,
+                phase,
+            } => {
+                let cycles = (elapsed + phase).as_secs_f32() / period.as_secs_f32();
+                amplitude * libm::sinf(2.0 * PI * cycles)
+            }
+        }
+    }
+}
+
+/// The sum of its profiles plus Gaussian noise.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Signal<'a> {
+    pub profiles: &'a [Profile],
+    /// Standard deviation of the noise, 0 for none.
+    pub noise: f32,
+}
+
+impl<'a> Signal<'a> {
+    pub const fn new(profiles: &'a [Profile], noise: f32) -> Self {
+        Self { profiles, noise }
+    }
+
+    fn value(&self, elapsed: Duration, rng: &mut Rng) -> f32 {
+        let value: f32 = self.profiles.iter().map(|p| p.value(elapsed)).sum();
+        if self.noise > 0.0 {
+            value + rng.gaussian() * self.noise
+        } else {
+            value
+        }
+    }
+}
+
+/// Declarative description of synthetic measurements, e.g. a meeting room filling up:
+///
+/// ```
+/// # use core::time::Duration;
+/// # use sensor::{Profile, Scenario, Signal};
+/// const MEETING: Scenario = Scenario {
+///     co2: Signal::new(
+///         &[
+///             Profile::Constant(450.0),
+///             Profile::Ramp {
+///                 from: 0.0,
+///                 to: 900.0,
+///                 start: Duration::from_secs(10 * 60),
+///                 duration: Duration::from_secs(60 * 60),
+///             },
+///         ],
+///         15.0,
+///     ),
+///     temperature: Signal::new(&[Profile::Constant(21.5)], 0.1),
+///     humidity: Signal::new(&[Profile::Constant(45.0)], 0.5),
+///     interval: Duration::from_secs(60),
+///     duration: Some(Duration::from_secs(2 * 60 * 60)),
+///     dropout_rate: 0.01,
+///     seed: 42,
+/// };
+/// ```
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Scenario<'a> {
+    /// In ppm, clamped to 0..=65535.
+    pub co2: Signal<'a>,
+    /// In °C.
+    pub temperature: Signal<'a>,
+    /// In %, clamped to 0..=100.
+    pub humidity: Signal<'a>,
+    /// Time between measurements.
+    pub interval: Duration,
+    /// The scenario ends after this time, `None` runs forever.
+    pub duration: Option<Duration>,
+    /// Probability in 0..=1 that a measurement fails.
+    pub dropout_rate: f32,
+    /// The same seed always produces the same measurements.
+    pub seed: u64,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum ScenarioError {
+    /// The measurement was dropped, the next one follows one interval later.
+    Dropout,
+    /// The scenario's duration has passed.
+    End,
+}
+
+impl SensorError for ScenarioError {
+    fn kind(&self) -> ErrorKind {
+        match self {
+            ScenarioError::Dropout => ErrorKind::Unavailable,
+            ScenarioError::End => ErrorKind::End,
+        }
+    }
+}
+
+/// Generates the measurements described by a [`Scenario`].
+///
+/// Runs on simulated time: the n-th measurement is timestamped `n * interval` regardless of
+/// how fast it is called.
+pub struct ScenarioSensor<'a> {
+    scenario: Scenario<'a>,
+    rng: Rng,
+    elapsed: Duration,
+}
+
+impl<'a> ScenarioSensor<'a> {
+    pub fn new(scenario: Scenario<'a>) -> Self {
+        Self {
+            rng: Rng::new(scenario.seed),
+            scenario,
+            elapsed: Duration::ZERO,
+        }
+    }
+
+    /// Starts the scenario again from the beginning, repeating the same measurements.
+    pub fn reset(&mut self) {
+        self.rng = Rng::new(self.scenario.seed);
+        self.elapsed = Duration::ZERO;
+    }
+}
+
+impl<'a> Sensor for ScenarioSensor<'a> {
+    type Error = ScenarioError;
+
+    fn measure(&mut self) -> Result<Measurement, Self::Error> {
+        let elapsed = self.elapsed;
+        if self.scenario.duration.is_some_and(|d| elapsed > d) {
+            return Err(ScenarioError::End);
+        }
+        self.elapsed += self.scenario.interval;
+
+        // always draw the same numbers per measurement so a dropout doesn't shift the noise
+        let dropout = self.rng.next_f32() < self.scenario.dropout_rate;
+        let co2 = self.scenario.co2.value(elapsed, &mut self.rng);
+        let temperature = self.scenario.temperature.value(elapsed, &mut self.rng);
+        let humidity = self.scenario.humidity.value(elapsed, &mut self.rng);
+        if dropout {
+            return Err(ScenarioError::Dropout);
+        }
+
+        Ok(Measurement::new(
+            Timestamp::from_millis(elapsed.as_millis() as u64),
+            SourceId::UNKNOWN,
+            Data {
+                co2: co2.clamp(0.0, u16::MAX as f32) as u16,
+                temperature,
+                humidity: humidity.clamp(0.0, 100.0),
+            },
+        ))
+    }
+}
@@ -0,0 +1,39 @@
+//! Framing shared by the Sensirion I2C sensors: big-endian 16-bit words,
+//! each followed by a CRC-8 checksum.
+
+const CRC8_POLYNOMIAL: u8 = 0x31;
+const CRC8_INIT: u8 = 0xFF;
+
+pub(crate) struct CrcError;
+
+pub(crate) fn crc8(data: &[u8]) -> u8 {
+    let mut crc = CRC8_INIT;
+    for byte in data {
+        crc ^= byte;
+        for _ in 0..8 {
+            crc = if crc & 0x80 != 0 {
+                (crc << 1) ^ CRC8_POLYNOMIAL
+            } else {
+                crc << 1
+            };
+        }
+    }
+    crc
+}
+
+/// Decodes `words.len()` words from `buf`, which must be 3 bytes per word.
+pub(crate) fn decode_words(buf: &[u8], words: &mut [u16]) -> Result<(), CrcError> {
+    for (chunk, word) in buf.chunks_exact(3).zip(words.iter_mut()) {
+        if crc8(&chunk[..2]) != chunk[2] {
+            return Err(CrcError);
+        }
+        *word = u16::from_be_bytes([chunk[0], chunk[1]]);
+    }
+    Ok(())
+}
+
+/// Encodes `word` with its checksum, for commands that take arguments.
+pub(crate) fn encode_word(word: u16) -> [u8; 3] {
+    let [high, low] = word.to_be_bytes();
+    [high, low, crc8(&[high, low])]
+}
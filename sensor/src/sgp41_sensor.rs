@@ -0,0 +1,211 @@
+use crate::gas_index::{GasIndexAlgorithm, GasIndexKind};
+use crate::sensirion::{self, CrcError};
+use crate::{ErrorKind, Sensor, SensorError, SensorInfo, SensorKind, StartUp};
+use airquamon_domain::{Clock, Data, GasIndices, Measurement, QualityFlags, SourceId};
+use embedded_hal::{delay::DelayUs, i2c::I2c};
+
+const ADDRESS: u8 = 0x59;
+
+// Commands and their execution times in ms from the SGP41 datasheet
+const EXECUTE_CONDITIONING: (u16, u32) = (0x2612, 50);
+const MEASURE_RAW_SIGNALS: (u16, u32) = (0x2619, 50);
+const EXECUTE_SELF_TEST: (u16, u32) = (0x280e, 320);
+const TURN_HEATER_OFF: (u16, u32) = (0x3615, 1);
+const GET_SERIAL_NUMBER: (u16, u32) = (0x3682, 1);
+
+/// Conditioning the NOx pixel for 10 s after power on, longer would damage it.
+const CONDITIONING_SECONDS: u8 = 10;
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum Sgp41Error<E> {
+    I2c(E),
+    Crc,
+    SelfTestFailed,
+}
+
+impl<E> From<CrcError> for Sgp41Error<E> {
+    fn from(_: CrcError) -> Self {
+        Sgp41Error::Crc
+    }
+}
+
+impl<E> SensorError for Sgp41Error<E> {
+    fn kind(&self) -> ErrorKind {
+        match self {
+            Sgp41Error::I2c(_) => ErrorKind::Bus,
+            Sgp41Error::Crc => ErrorKind::Corrupted,
+            Sgp41Error::SelfTestFailed => ErrorKind::Other,
+        }
+    }
+}
+
+/// Sensirion SGP41 VOC and NOx sensor, reporting the indices of the gas index algorithm.
+///
+/// [`Sensor::measure`] has to be called every second for the algorithm. The first 10 calls
+/// condition the sensor and the indices take another 45 s to become available, until then
+/// they are reported as missing. CO2, temperature and humidity are always reported as missing.
+pub struct Sgp41Sensor<I2C, DELAY, CLOCK> {
+    i2c: I2C,
+    delay: DELAY,
+    clock: CLOCK,
+    source: SourceId,
+    voc: GasIndexAlgorithm,
+    nox: GasIndexAlgorithm,
+    conditioning_remaining: u8,
+    /// Relative humidity and temperature in ticks, as the sensor expects them.
+    compensation: [u16; 2],
+}
+
+impl<I2C, DELAY, CLOCK> Sgp41Sensor<I2C, DELAY, CLOCK>
+where
+    I2C: I2c,
+    DELAY: DelayUs,
+    CLOCK: Clock,
+{
+    pub fn new(i2c: I2C, delay: DELAY, clock: CLOCK) -> Self {
+        Self {
+            i2c,
+            delay,
+            clock,
+            source: SourceId::UNKNOWN,
+            voc: GasIndexAlgorithm::new(GasIndexKind::Voc),
+            nox: GasIndexAlgorithm::new(GasIndexKind::Nox),
+            conditioning_remaining: CONDITIONING_SECONDS,
+            // 50 %RH and 25 °C
+            compensation: [0x8000, 0x6666],
+        }
+    }
+
+    /// Compensates the raw signals for the humidity and temperature of `measurement`,
+    /// usually from a sensor next to the SGP41. Missing values leave the previous ones in place.
+    pub fn set_compensation(&mut self, measurement: &Measurement) {
+        if let Some(humidity) = measurement.humidity() {
+            self.compensation[0] = (humidity.clamp(0.0, 100.0) * 65535.0 / 100.0) as u16;
+        }
+        if let Some(temperature) = measurement.temperature() {
+            self.compensation[1] =
+                ((temperature.clamp(-45.0, 130.0) + 45.0) * 65535.0 / 175.0) as u16;
+        }
+    }
+
+    /// Measures the raw VOC and NOx signals in ticks, without the gas index algorithm.
+    pub fn measure_raw_signals(&mut self) -> Result<(u16, u16), Sgp41Error<I2C::Error>> {
+        let compensation = self.compensation;
+        let [voc, nox] = self.read_words(MEASURE_RAW_SIGNALS, &compensation)?;
+        Ok((voc, nox))
+    }
+
+    /// Switches the hot plate off until the next measurement, the sensor then idles.
+    /// The indices need to warm up again if it stays off for more than a few seconds.
+    pub fn turn_heater_off(&mut self) -> Result<(), Sgp41Error<I2C::Error>> {
+        self.write_command(TURN_HEATER_OFF, &[])
+    }
+
+    pub fn serial_number(&mut self) -> Result<u64, Sgp41Error<I2C::Error>> {
+        let words: [u16; 3] = self.read_words(GET_SERIAL_NUMBER, &[])?;
+        Ok(words
+            .iter()
+            .fold(0, |serial, &word| serial << 16 | u64::from(word)))
+    }
+
+    pub fn self_test(&mut self) -> Result<(), Sgp41Error<I2C::Error>> {
+        let [result] = self.read_words(EXECUTE_SELF_TEST, &[])?;
+        // bit 0 for the VOC pixel, bit 1 for the NOx pixel
+        if result & 0x0003 != 0 {
+            return Err(Sgp41Error::SelfTestFailed);
+        }
+        Ok(())
+    }
+
+    /// Starts conditioning and learning the baselines again, e.g. after the heater was off.
+    pub fn reset(&mut self) {
+        self.conditioning_remaining = CONDITIONING_SECONDS;
+        self.voc.reset();
+        self.nox.reset();
+    }
+
+    fn write_command(
+        &mut self,
+        (command, delay_ms): (u16, u32),
+        arguments: &[u16],
+    ) -> Result<(), Sgp41Error<I2C::Error>> {
+        let mut buf = [0; 8];
+        buf[..2].copy_from_slice(&command.to_be_bytes());
+        for (chunk, &argument) in buf[2..].chunks_exact_mut(3).zip(arguments) {
+            chunk.copy_from_slice(&sensirion::encode_word(argument));
+        }
+        let length = 2 + 3 * arguments.len();
+        self.i2c
+            .write(ADDRESS, &buf[..length])
+            .map_err(Sgp41Error::I2c)?;
+        self.delay.delay_ms(delay_ms);
+        Ok(())
+    }
+
+    fn read_words<const N: usize>(
+        &mut self,
+        command: (u16, u32),
+        arguments: &[u16],
+    ) -> Result<[u16; N], Sgp41Error<I2C::Error>> {
+        self.write_command(command, arguments)?;
+        // 3 bytes per word, the serial number is the longest with 3 words
+        let mut buf = [0; 9];
+        let buf = &mut buf[..N * 3];
+        self.i2c.read(ADDRESS, buf).map_err(Sgp41Error::I2c)?;
+        let mut words = [0; N];
+        sensirion::decode_words(buf, &mut words)?;
+        Ok(words)
+    }
+}
+
+impl<I2C, DELAY, CLOCK> Sensor for Sgp41Sensor<I2C, DELAY, CLOCK>
+where
+    I2C: I2c,
+    DELAY: DelayUs,
+    CLOCK: Clock,
+{
+    type Error = Sgp41Error<I2C::Error>;
+
+    fn measure(&mut self) -> Result<Measurement, Self::Error> {
+        let mut measurement = Measurement::new(self.clock.now(), self.source, Data::default());
+        measurement.quality = QualityFlags::MISSING;
+
+        if self.conditioning_remaining > 0 {
+            let compensation = self.compensation;
+            // the VOC signal measured meanwhile isn't used
+            let _: [u16; 1] = self.read_words(EXECUTE_CONDITIONING, &compensation)?;
+            self.conditioning_remaining -= 1;
+            return Ok(measurement);
+        }
+
+        let (voc, nox) = self.measure_raw_signals()?;
+        let indices = GasIndices {
+            voc: self.voc.process(voc),
+            nox: self.nox.process(nox),
+        };
+        // both are 0 during the initial blackout
+        measurement.gas_indices = (indices.voc != 0).then_some(indices);
+        Ok(measurement)
+    }
+}
+
+impl<I2C, DELAY, CLOCK> StartUp for Sgp41Sensor<I2C, DELAY, CLOCK>
+where
+    I2C: I2c,
+    DELAY: DelayUs,
+    CLOCK: Clock,
+{
+    type Error = Sgp41Error<I2C::Error>;
+
+    fn start_up(&mut self) -> Result<SensorInfo, Self::Error> {
+        let serial_number = self.serial_number()?;
+        self.self_test()?;
+        let info = SensorInfo {
+            kind: SensorKind::Sgp41,
+            serial_number,
+            firmware_version: None,
+        };
+        self.source = info.source_id();
+        Ok(info)
+    }
+}
@@ -0,0 +1,214 @@
+use crate::sensirion::{self, CrcError};
+use crate::{ErrorKind, Sensor, SensorError, SensorInfo, SensorKind, StartUp};
+use airquamon_domain::{Clock, Data, Measurement, Quality, SourceId};
+use embedded_hal::{delay::DelayUs, i2c::I2c};
+
+/// SHT40-AD1B, SHT41-AD1B and SHT45-AD1B. The BD1B and CD1B variants use 0x45 and 0x46.
+pub const SHT4X_DEFAULT_ADDRESS: u8 = 0x44;
+
+// Commands and their maximum execution times in µs from the SHT4x datasheet
+const SERIAL_NUMBER: (u8, u32) = (0x89, 1_000);
+const SOFT_RESET: (u8, u32) = (0x94, 1_000);
+
+/// Trades measurement noise against measurement time and power.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum Precision {
+    /// 8.3 ms, 0.04 °C and 0.08 %RH repeatability.
+    #[default]
+    High,
+    /// 4.5 ms, 0.07 °C and 0.15 %RH repeatability.
+    Medium,
+    /// 1.6 ms, 0.1 °C and 0.25 %RH repeatability.
+    Low,
+}
+
+impl Precision {
+    fn command(self) -> (u8, u32) {
+        match self {
+            Precision::High => (0xfd, 8_300),
+            Precision::Medium => (0xf6, 4_500),
+            Precision::Low => (0xe0, 1_600),
+        }
+    }
+}
+
+/// Power and duration of the built-in heater, which removes condensation and creep after
+/// long exposure to high humidity.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Heater {
+    Power200mW1s,
+    Power200mW100ms,
+    Power110mW1s,
+    Power110mW100ms,
+    Power20mW1s,
+    Power20mW100ms,
+}
+
+impl Heater {
+    fn command(self) -> (u8, u32) {
+        match self {
+            Heater::Power200mW1s => (0x39, 1_100_000),
+            Heater::Power200mW100ms => (0x32, 110_000),
+            Heater::Power110mW1s => (0x2f, 1_100_000),
+            Heater::Power110mW100ms => (0x24, 110_000),
+            Heater::Power20mW1s => (0x1e, 1_100_000),
+            Heater::Power20mW100ms => (0x15, 110_000),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum Sht4xError<E> {
+    I2c(E),
+    Crc,
+}
+
+impl<E> From<CrcError> for Sht4xError<E> {
+    fn from(_: CrcError) -> Self {
+        Sht4xError::Crc
+    }
+}
+
+impl<E> SensorError for Sht4xError<E> {
+    fn kind(&self) -> ErrorKind {
+        match self {
+            Sht4xError::I2c(_) => ErrorKind::Bus,
+            Sht4xError::Crc => ErrorKind::Corrupted,
+        }
+    }
+}
+
+/// Sensirion SHT4x temperature and humidity sensor. Doesn't measure CO2, which is reported as
+/// missing.
+pub struct Sht4xSensor<I2C, DELAY, CLOCK> {
+    i2c: I2C,
+    delay: DELAY,
+    clock: CLOCK,
+    address: u8,
+    source: SourceId,
+    precision: Precision,
+}
+
+impl<I2C, DELAY, CLOCK> Sht4xSensor<I2C, DELAY, CLOCK>
+where
+    I2C: I2c,
+    DELAY: DelayUs,
+    CLOCK: Clock,
+{
+    pub fn new(i2c: I2C, delay: DELAY, clock: CLOCK) -> Self {
+        Self {
+            i2c,
+            delay,
+            clock,
+            address: SHT4X_DEFAULT_ADDRESS,
+            source: SourceId::UNKNOWN,
+            precision: Precision::default(),
+        }
+    }
+
+    pub fn with_address(mut self, address: u8) -> Self {
+        self.address = address;
+        self
+    }
+
+    pub fn precision(&self) -> Precision {
+        self.precision
+    }
+
+    pub fn set_precision(&mut self, precision: Precision) {
+        self.precision = precision;
+    }
+
+    /// Runs the heater and returns the measurement taken at the end of it. The temperature is
+    /// raised by the heater and marked suspect. The heater shouldn't run for more than 10 % of
+    /// the time.
+    pub fn heat(&mut self, heater: Heater) -> Result<Measurement, Sht4xError<I2C::Error>> {
+        let mut measurement = self.read_measurement(heater.command())?;
+        measurement.quality.temperature = Quality::Suspect;
+        Ok(measurement)
+    }
+
+    pub fn soft_reset(&mut self) -> Result<(), Sht4xError<I2C::Error>> {
+        self.write_command(SOFT_RESET)
+    }
+
+    pub fn serial_number(&mut self) -> Result<u32, Sht4xError<I2C::Error>> {
+        let [high, low] = self.read_words(SERIAL_NUMBER)?;
+        Ok(u32::from(high) << 16 | u32::from(low))
+    }
+
+    fn write_command(
+        &mut self,
+        (command, delay_us): (u8, u32),
+    ) -> Result<(), Sht4xError<I2C::Error>> {
+        self.i2c
+            .write(self.address, &[command])
+            .map_err(Sht4xError::I2c)?;
+        self.delay.delay_us(delay_us);
+        Ok(())
+    }
+
+    fn read_words(&mut self, command: (u8, u32)) -> Result<[u16; 2], Sht4xError<I2C::Error>> {
+        self.write_command(command)?;
+        let mut buf = [0; 6];
+        self.i2c
+            .read(self.address, &mut buf)
+            .map_err(Sht4xError::I2c)?;
+        let mut words = [0; 2];
+        sensirion::decode_words(&buf, &mut words)?;
+        Ok(words)
+    }
+
+    fn read_measurement(
+        &mut self,
+        command: (u8, u32),
+    ) -> Result<Measurement, Sht4xError<I2C::Error>> {
+        let [temperature, humidity] = self.read_words(command)?;
+        let mut measurement = Measurement::new(
+            self.clock.now(),
+            self.source,
+            Data {
+                co2: 0,
+                temperature: -45.0 + 175.0 * f32::from(temperature) / 65535.0,
+                // the conversion exceeds 0 - 100 % near the ends of the range
+                humidity: (-6.0 + 125.0 * f32::from(humidity) / 65535.0).clamp(0.0, 100.0),
+            },
+        );
+        measurement.quality.co2 = Quality::Missing;
+        Ok(measurement)
+    }
+}
+
+impl<I2C, DELAY, CLOCK> Sensor for Sht4xSensor<I2C, DELAY, CLOCK>
+where
+    I2C: I2c,
+    DELAY: DelayUs,
+    CLOCK: Clock,
+{
+    type Error = Sht4xError<I2C::Error>;
+
+    fn measure(&mut self) -> Result<Measurement, Self::Error> {
+        self.read_measurement(self.precision.command())
+    }
+}
+
+impl<I2C, DELAY, CLOCK> StartUp for Sht4xSensor<I2C, DELAY, CLOCK>
+where
+    I2C: I2c,
+    DELAY: DelayUs,
+    CLOCK: Clock,
+{
+    type Error = Sht4xError<I2C::Error>;
+
+    /// The SHT4x has no self-test, a successful reset and matching checksums are all there is.
+    fn start_up(&mut self) -> Result<SensorInfo, Self::Error> {
+        self.soft_reset()?;
+        let info = SensorInfo {
+            kind: SensorKind::Sht4x,
+            serial_number: self.serial_number()?.into(),
+            firmware_version: None,
+        };
+        self.source = info.source_id();
+        Ok(info)
+    }
+}
@@ -0,0 +1,263 @@
+use crate::sensirion::{self, CrcError};
+use crate::{ErrorKind, Sensor, SensorError, SensorInfo, SensorKind, StartUp};
+use airquamon_domain::{Clock, Data, Measurement, ParticulateMatter, QualityFlags, SourceId};
+use core::time::Duration;
+use embedded_hal::{delay::DelayUs, i2c::I2c};
+
+const ADDRESS: u8 = 0x69;
+
+// Commands and their execution times in ms from the SPS30 datasheet
+const START_MEASUREMENT: (u16, u32) = (0x0010, 20);
+const STOP_MEASUREMENT: (u16, u32) = (0x0104, 20);
+const READ_DATA_READY: (u16, u32) = (0x0202, 0);
+const READ_MEASURED_VALUES: (u16, u32) = (0x0300, 0);
+const SLEEP: (u16, u32) = (0x1001, 5);
+const WAKE_UP: (u16, u32) = (0x1103, 5);
+const START_FAN_CLEANING: (u16, u32) = (0x5607, 5);
+const AUTO_CLEANING_INTERVAL: (u16, u32) = (0x8004, 5);
+const READ_FIRMWARE_VERSION: (u16, u32) = (0xd100, 0);
+const READ_SERIAL_NUMBER: (u16, u32) = (0xd033, 0);
+const RESET: (u16, u32) = (0xd304, 100);
+
+/// Measured values as big-endian unsigned integers, needs firmware 2.0 or later.
+const OUTPUT_FORMAT_U16: u16 = 0x0500;
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum Sps30Error<E> {
+    I2c(E),
+    Crc,
+    /// No measurement was ready within the data ready timeout.
+    Timeout,
+}
+
+impl<E> From<CrcError> for Sps30Error<E> {
+    fn from(_: CrcError) -> Self {
+        Sps30Error::Crc
+    }
+}
+
+impl<E> SensorError for Sps30Error<E> {
+    fn kind(&self) -> ErrorKind {
+        match self {
+            Sps30Error::I2c(_) => ErrorKind::Bus,
+            Sps30Error::Crc => ErrorKind::Corrupted,
+            Sps30Error::Timeout => ErrorKind::Timeout,
+        }
+    }
+}
+
+/// Sensirion SPS30 particulate matter sensor on I2C.
+/// Only reports particulate matter, CO2, temperature and humidity are reported as missing.
+pub struct Sps30Sensor<I2C, DELAY, CLOCK> {
+    i2c: I2C,
+    delay: DELAY,
+    clock: CLOCK,
+    source: SourceId,
+    /// Whether a measurement has been started and not stopped since.
+    running: bool,
+}
+
+impl<I2C, DELAY, CLOCK> Sps30Sensor<I2C, DELAY, CLOCK>
+where
+    I2C: I2c,
+    DELAY: DelayUs,
+    CLOCK: Clock,
+{
+    pub fn new(i2c: I2C, delay: DELAY, clock: CLOCK) -> Self {
+        Self {
+            i2c,
+            delay,
+            clock,
+            source: SourceId::UNKNOWN,
+            running: false,
+        }
+    }
+
+    /// Stops measuring and the fan, leaving the sensor idle.
+    pub fn stop(&mut self) -> Result<(), Sps30Error<I2C::Error>> {
+        if self.running {
+            self.write_command(STOP_MEASUREMENT, &[])?;
+            self.running = false;
+        }
+        Ok(())
+    }
+
+    /// Lowers the current from 8 mA idle to 38 µA.
+    pub fn sleep(&mut self) -> Result<(), Sps30Error<I2C::Error>> {
+        self.stop()?;
+        self.write_command(SLEEP, &[])
+    }
+
+    pub fn wake_up(&mut self) -> Result<(), Sps30Error<I2C::Error>> {
+        // the interface is off while sleeping, the first command only wakes it up and is NACKed
+        let _ = self.i2c.write(ADDRESS, &WAKE_UP.0.to_be_bytes());
+        self.write_command(WAKE_UP, &[])
+    }
+
+    /// Runs the fan at full speed for 10 s to blow out dust, measurements taken meanwhile
+    /// are not reliable. Starts measuring if it isn't yet.
+    pub fn start_fan_cleaning(&mut self) -> Result<(), Sps30Error<I2C::Error>> {
+        if !self.running {
+            self.start_measurement()?;
+        }
+        self.write_command(START_FAN_CLEANING, &[])
+    }
+
+    /// How often the fan is cleaned automatically while measuring, `None` if disabled.
+    pub fn auto_cleaning_interval(&mut self) -> Result<Option<Duration>, Sps30Error<I2C::Error>> {
+        let [high, low] = self.read_words(AUTO_CLEANING_INTERVAL)?;
+        let seconds = u32::from(high) << 16 | u32::from(low);
+        Ok((seconds != 0).then(|| Duration::from_secs(seconds.into())))
+    }
+
+    /// The factory default is weekly. Takes effect once the measurement is restarted.
+    pub fn set_auto_cleaning_interval(
+        &mut self,
+        interval: Option<Duration>,
+    ) -> Result<(), Sps30Error<I2C::Error>> {
+        let seconds = interval.map_or(0, |interval| {
+            u32::try_from(interval.as_secs()).unwrap_or(u32::MAX)
+        });
+        self.write_command(
+            AUTO_CLEANING_INTERVAL,
+            &[(seconds >> 16) as u16, seconds as u16],
+        )
+    }
+
+    pub fn firmware_version(&mut self) -> Result<(u8, u8), Sps30Error<I2C::Error>> {
+        let [version] = self.read_words(READ_FIRMWARE_VERSION)?;
+        let [major, minor] = version.to_be_bytes();
+        Ok((major, minor))
+    }
+
+    /// The serial number is 16 hexadecimal ASCII characters, `None` if it isn't.
+    pub fn serial_number(&mut self) -> Result<Option<u64>, Sps30Error<I2C::Error>> {
+        let words: [u16; 16] = self.read_words(READ_SERIAL_NUMBER)?;
+        let mut serial_number = 0u64;
+        for byte in words.iter().flat_map(|word| word.to_be_bytes()) {
+            if byte == 0 {
+                break;
+            }
+            let digit = match (byte as char).to_digit(16) {
+                Some(digit) => digit,
+                None => return Ok(None),
+            };
+            serial_number = match serial_number.checked_mul(16) {
+                Some(shifted) => shifted | u64::from(digit),
+                None => return Ok(None),
+            };
+        }
+        Ok(Some(serial_number))
+    }
+
+    pub fn reset(&mut self) -> Result<(), Sps30Error<I2C::Error>> {
+        self.write_command(RESET, &[])?;
+        self.running = false;
+        Ok(())
+    }
+
+    fn start_measurement(&mut self) -> Result<(), Sps30Error<I2C::Error>> {
+        self.write_command(START_MEASUREMENT, &[OUTPUT_FORMAT_U16])?;
+        self.running = true;
+        Ok(())
+    }
+
+    fn write_command(
+        &mut self,
+        (command, delay_ms): (u16, u32),
+        arguments: &[u16],
+    ) -> Result<(), Sps30Error<I2C::Error>> {
+        let mut buf = [0; 8];
+        buf[..2].copy_from_slice(&command.to_be_bytes());
+        for (chunk, &argument) in buf[2..].chunks_exact_mut(3).zip(arguments) {
+            chunk.copy_from_slice(&sensirion::encode_word(argument));
+        }
+        let length = 2 + 3 * arguments.len();
+        self.i2c
+            .write(ADDRESS, &buf[..length])
+            .map_err(Sps30Error::I2c)?;
+        self.delay.delay_ms(delay_ms);
+        Ok(())
+    }
+
+    fn read_words<const N: usize>(
+        &mut self,
+        command: (u16, u32),
+    ) -> Result<[u16; N], Sps30Error<I2C::Error>> {
+        self.write_command(command, &[])?;
+        // 3 bytes per word, the serial number is the longest with 16 words
+        let mut buf = [0; 48];
+        let buf = &mut buf[..N * 3];
+        self.i2c.read(ADDRESS, buf).map_err(Sps30Error::I2c)?;
+        let mut words = [0; N];
+        sensirion::decode_words(buf, &mut words)?;
+        Ok(words)
+    }
+
+    fn wait_for_data_ready(&mut self) -> Result<(), Sps30Error<I2C::Error>> {
+        const POLL_INTERVAL_MS: u32 = 100;
+        // a new measurement is ready every second
+        const TIMEOUT_MS: u32 = 3_000;
+        let mut waited = 0;
+        loop {
+            let [ready] = self.read_words(READ_DATA_READY)?;
+            if ready & 0x00ff != 0 {
+                return Ok(());
+            }
+            if waited >= TIMEOUT_MS {
+                return Err(Sps30Error::Timeout);
+            }
+            self.delay.delay_ms(POLL_INTERVAL_MS);
+            waited += POLL_INTERVAL_MS;
+        }
+    }
+}
+
+impl<I2C, DELAY, CLOCK> Sensor for Sps30Sensor<I2C, DELAY, CLOCK>
+where
+    I2C: I2c,
+    DELAY: DelayUs,
+    CLOCK: Clock,
+{
+    type Error = Sps30Error<I2C::Error>;
+
+    /// Starts measuring on the first call, the readings settle after about 8 s.
+    fn measure(&mut self) -> Result<Measurement, Self::Error> {
+        if !self.running {
+            self.start_measurement()?;
+        }
+        self.wait_for_data_ready()?;
+        // mass concentrations of PM1.0, PM2.5, PM4.0 and PM10 in µg/m³, followed by
+        // number concentrations and the typical particle size
+        let values: [u16; 10] = self.read_words(READ_MEASURED_VALUES)?;
+        let mut measurement = Measurement::new(self.clock.now(), self.source, Data::default());
+        measurement.quality = QualityFlags::MISSING;
+        measurement.particulate_matter = Some(ParticulateMatter {
+            pm1_0: values[0].into(),
+            pm2_5: values[1].into(),
+            pm10: values[3].into(),
+        });
+        Ok(measurement)
+    }
+}
+
+impl<I2C, DELAY, CLOCK> StartUp for Sps30Sensor<I2C, DELAY, CLOCK>
+where
+    I2C: I2c,
+    DELAY: DelayUs,
+    CLOCK: Clock,
+{
+    type Error = Sps30Error<I2C::Error>;
+
+    /// Resets the sensor, which stops a measurement left running by a previous boot.
+    fn start_up(&mut self) -> Result<SensorInfo, Self::Error> {
+        self.reset()?;
+        let info = SensorInfo {
+            kind: SensorKind::Sps30,
+            serial_number: self.serial_number()?.unwrap_or(SourceId::UNKNOWN.0),
+            firmware_version: Some(self.firmware_version()?),
+        };
+        self.source = info.source_id();
+        Ok(info)
+    }
+}
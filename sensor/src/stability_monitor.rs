@@ -0,0 +1,169 @@
+use crate::{PowerManagement, PowerState, Sensor};
+use airquamon_domain::{Data, Measurement, Quality};
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct StabilityPolicy {
+    /// Measurements after starting or waking up the sensor until its readings are stable.
+    pub warm_up_measurements: u16,
+    /// Whether readings taken while warming up are measured again straight away instead of
+    /// being flagged as [`Quality::WarmingUp`].
+    pub discard_warm_up: bool,
+    /// Consecutive identical values after which a field is flagged as [`Quality::Stale`],
+    /// 0 disables the check.
+    pub stale_measurements: u16,
+}
+
+impl Default for StabilityPolicy {
+    /// The SCD4x datasheet recommends discarding the first reading after waking up, a few more
+    /// are still noticeably off. Real readings never repeat exactly over 10 minutes.
+    fn default() -> Self {
+        Self {
+            warm_up_measurements: 3,
+            discard_warm_up: false,
+            stale_measurements: 10,
+        }
+    }
+}
+
+/// Flags readings of the wrapped sensor that can't be trusted yet or anymore, those taken
+/// while it warms up and values that stopped changing because the sensor froze.
+///
+/// Warming up starts when the monitor is created and again on [`StabilityMonitor::restart`],
+/// which should be called whenever the sensor was woken up or restarted. Powering the sensor
+/// down through [`PowerManagement::set_power_state`] restarts it by itself once the sensor is
+/// woken up again, either explicitly or by the next measurement. Only CO2, temperature and
+/// humidity are checked, missing values stay missing.
+pub struct StabilityMonitor<S> {
+    sensor: S,
+    policy: StabilityPolicy,
+    warm_up_remaining: u16,
+    last: Option<Data>,
+    /// How many times in a row CO2, temperature and humidity had the same value.
+    repeats: [u16; 3],
+    /// Whether the sensor was powered down and hasn't been woken up since.
+    powered_down: bool,
+}
+
+impl<S> StabilityMonitor<S>
+where
+    S: Sensor,
+{
+    pub fn new(sensor: S, policy: StabilityPolicy) -> Self {
+        Self {
+            sensor,
+            policy,
+            warm_up_remaining: policy.warm_up_measurements,
+            last: None,
+            repeats: [0; 3],
+            powered_down: false,
+        }
+    }
+
+    /// Starts warming up again, e.g. after the sensor woke up from sleep.
+    pub fn restart(&mut self) {
+        self.warm_up_remaining = self.policy.warm_up_measurements;
+        self.last = None;
+        self.repeats = [0; 3];
+    }
+
+    pub fn is_warming_up(&self) -> bool {
+        self.warm_up_remaining > 0
+    }
+
+    pub fn inner(&self) -> &S {
+        &self.sensor
+    }
+
+    pub fn inner_mut(&mut self) -> &mut S {
+        &mut self.sensor
+    }
+
+    pub fn into_inner(self) -> S {
+        self.sensor
+    }
+
+    fn track_repeats(&mut self, data: Data) {
+        if let Some(last) = self.last {
+            let same = [
+                data.co2 == last.co2,
+                data.temperature == last.temperature,
+                data.humidity == last.humidity,
+            ];
+            for (repeats, same) in self.repeats.iter_mut().zip(same) {
+                *repeats = if same { repeats.saturating_add(1) } else { 0 };
+            }
+        }
+        self.last = Some(data);
+    }
+}
+
+impl<S> Sensor for StabilityMonitor<S>
+where
+    S: Sensor,
+{
+    type Error = S::Error;
+
+    fn measure(&mut self) -> Result<Measurement, Self::Error> {
+        if self.powered_down {
+            // measuring wakes the sensor up
+            self.powered_down = false;
+            self.restart();
+        }
+        let mut measurement = self.sensor.measure()?;
+        while self.policy.discard_warm_up && self.warm_up_remaining > 0 {
+            self.warm_up_remaining -= 1;
+            measurement = self.sensor.measure()?;
+        }
+
+        let qualities = [
+            &mut measurement.quality.co2,
+            &mut measurement.quality.temperature,
+            &mut measurement.quality.humidity,
+        ];
+        if self.warm_up_remaining > 0 {
+            self.warm_up_remaining -= 1;
+            for quality in qualities {
+                if *quality != Quality::Missing {
+                    *quality = Quality::WarmingUp;
+                }
+            }
+            return Ok(measurement);
+        }
+
+        self.track_repeats(measurement.data);
+        // a value repeated n times has been the same for n + 1 measurements
+        let stale = self.policy.stale_measurements;
+        for (quality, repeats) in qualities.into_iter().zip(self.repeats) {
+            if stale > 0 && repeats.saturating_add(1) >= stale && *quality != Quality::Missing {
+                *quality = Quality::Stale;
+            }
+        }
+        Ok(measurement)
+    }
+}
+
+impl<S> PowerManagement for StabilityMonitor<S>
+where
+    S: Sensor + PowerManagement,
+{
+    type Error = <S as PowerManagement>::Error;
+
+    fn power_state(&self) -> PowerState {
+        self.sensor.power_state()
+    }
+
+    fn set_power_state(&mut self, state: PowerState) -> Result<(), Self::Error> {
+        self.sensor.set_power_state(state)?;
+        if state == PowerState::PowerDown {
+            self.powered_down = true;
+        } else if self.powered_down {
+            self.powered_down = false;
+            self.restart();
+        }
+        Ok(())
+    }
+
+    fn supply_current(&self, state: PowerState) -> u32 {
+        self.sensor.supply_current(state)
+    }
+}
@@ -0,0 +1,195 @@
+mod common;
+
+use airquamon_domain::{Quality, SourceId};
+use common::{clock, NoDelay};
+use embedded_hal::i2c::ErrorKind;
+use embedded_hal_mock::eh1::i2c::{Mock as I2cMock, Transaction};
+use sensor::{Bmx280Error, Bmx280Sensor, Sensor, SensorKind, StartUp};
+
+const ADDRESS: u8 = 0x76;
+
+fn assert_close(actual: f32, expected: f32, tolerance: f32) {
+    assert!(
+        (actual - expected).abs() <= tolerance,
+        "expected {expected} ± {tolerance}, got {actual}"
+    );
+}
+
+/// Temperature and pressure trimming of the compensation example in the BMP280 datasheet,
+/// dig_T1 to dig_P9 in little endian.
+fn datasheet_calibration() -> Vec<u8> {
+    let trimming: [i32; 12] = [
+        27504, 26435, -1000, 36477, -10685, 3024, 2855, 140, -7, 15500, -14600, 6000,
+    ];
+    trimming
+        .iter()
+        .flat_map(|value| (*value as u16).to_le_bytes())
+        .collect()
+}
+
+/// Humidity trimming of a BME280: dig_H1 = 75, dig_H2 = 362, dig_H3 = 0, dig_H4 = 313,
+/// dig_H5 = 50 and dig_H6 = 30, with H4 and H5 sharing the nibbles of 0xe5.
+const HUMIDITY_H1: u8 = 75;
+const HUMIDITY_CALIBRATION: [u8; 7] = [0x6a, 0x01, 0x00, 0x13, 0x29, 0x03, 0x1e];
+
+/// Raw pressure 415148 and temperature 519888 of the datasheet example and raw humidity 0x7000.
+const DATA: [u8; 8] = [0x65, 0x5a, 0xc0, 0x7e, 0xed, 0x00, 0x70, 0x00];
+
+fn identify(chip_id: u8) -> Vec<Transaction> {
+    let mut transactions = vec![
+        Transaction::write_read(ADDRESS, vec![0xd0], vec![chip_id]),
+        Transaction::write_read(ADDRESS, vec![0x88], datasheet_calibration()),
+    ];
+    if chip_id == 0x60 {
+        transactions.extend([
+            Transaction::write_read(ADDRESS, vec![0xa1], vec![HUMIDITY_H1]),
+            Transaction::write_read(ADDRESS, vec![0xe1], HUMIDITY_CALIBRATION.to_vec()),
+        ]);
+    }
+    transactions
+}
+
+/// Forced measurement with the sensor done straight away.
+fn measure() -> Vec<Transaction> {
+    vec![
+        Transaction::write(ADDRESS, vec![0xf4, 0x25]),
+        Transaction::write_read(ADDRESS, vec![0xf3], vec![0x00]),
+        Transaction::write_read(ADDRESS, vec![0xf7], DATA.to_vec()),
+    ]
+}
+
+#[test]
+fn compensates_the_datasheet_example() {
+    let mut i2c = I2cMock::new(&[identify(0x58), measure()].concat());
+    let mut bmp280 = Bmx280Sensor::new(i2c.clone(), NoDelay, clock);
+
+    let measurement = bmp280.measure().unwrap();
+
+    // 25.08 °C and 100653.27 Pa in the datasheet
+    assert_eq!(measurement.temperature(), Some(25.08));
+    assert_close(measurement.pressure.unwrap(), 1006.5327, 0.001);
+    assert_eq!(measurement.timestamp, clock());
+    assert_eq!(measurement.quality.co2, Quality::Missing);
+    assert_eq!(measurement.quality.humidity, Quality::Missing);
+    i2c.done();
+}
+
+#[test]
+fn bme280_also_measures_humidity() {
+    let mut i2c = I2cMock::new(
+        &[
+            identify(0x60),
+            vec![Transaction::write(ADDRESS, vec![0xf2, 0x01])],
+            measure(),
+        ]
+        .concat(),
+    );
+    let mut bme280 = Bmx280Sensor::new(i2c.clone(), NoDelay, clock);
+
+    let measurement = bme280.measure().unwrap();
+
+    // floating point compensation from the datasheet gives 47.607 %
+    assert_close(measurement.humidity().unwrap(), 47.607, 0.01);
+    assert_eq!(measurement.temperature(), Some(25.08));
+    i2c.done();
+}
+
+#[test]
+fn reads_the_calibration_only_once() {
+    let mut i2c = I2cMock::new(&[identify(0x58), measure(), measure()].concat());
+    let mut bmp280 = Bmx280Sensor::new(i2c.clone(), NoDelay, clock);
+
+    let first = bmp280.measure().unwrap();
+
+    assert_eq!(bmp280.measure().unwrap(), first);
+    i2c.done();
+}
+
+#[test]
+fn start_up_resets_and_identifies_the_chip() {
+    // (chip id, kind)
+    let table = [(0x58, SensorKind::Bmp280), (0x60, SensorKind::Bme280)];
+    for (chip_id, kind) in table {
+        let mut i2c = I2cMock::new(
+            &[
+                vec![Transaction::write(ADDRESS, vec![0xe0, 0xb6])],
+                identify(chip_id),
+            ]
+            .concat(),
+        );
+        let mut sensor = Bmx280Sensor::new(i2c.clone(), NoDelay, clock);
+
+        let info = sensor.start_up().unwrap();
+
+        assert_eq!(info.kind, kind);
+        assert_eq!(info.serial_number, SourceId::UNKNOWN.0);
+        assert_eq!(info.firmware_version, None);
+        i2c.done();
+    }
+}
+
+#[test]
+fn rejects_unknown_chips() {
+    let mut i2c = I2cMock::new(&[Transaction::write_read(ADDRESS, vec![0xd0], vec![0x55])]);
+    let mut sensor = Bmx280Sensor::new(i2c.clone(), NoDelay, clock);
+
+    assert_eq!(sensor.measure(), Err(Bmx280Error::UnknownChip(0x55)));
+    i2c.done();
+}
+
+#[test]
+fn waits_while_measuring() {
+    let mut i2c = I2cMock::new(
+        &[
+            identify(0x58),
+            vec![
+                Transaction::write(ADDRESS, vec![0xf4, 0x25]),
+                Transaction::write_read(ADDRESS, vec![0xf3], vec![0x08]),
+                Transaction::write_read(ADDRESS, vec![0xf3], vec![0x08]),
+                Transaction::write_read(ADDRESS, vec![0xf3], vec![0x00]),
+                Transaction::write_read(ADDRESS, vec![0xf7], DATA.to_vec()),
+            ],
+        ]
+        .concat(),
+    );
+    let mut bmp280 = Bmx280Sensor::new(i2c.clone(), NoDelay, clock);
+
+    assert_eq!(bmp280.measure().unwrap().temperature(), Some(25.08));
+    i2c.done();
+}
+
+#[test]
+fn gives_up_when_the_measurement_doesnt_finish() {
+    let mut i2c = I2cMock::new(
+        &[
+            identify(0x58),
+            vec![Transaction::write(ADDRESS, vec![0xf4, 0x25])],
+            vec![Transaction::write_read(ADDRESS, vec![0xf3], vec![0x08]); 10],
+        ]
+        .concat(),
+    );
+    let mut bmp280 = Bmx280Sensor::new(i2c.clone(), NoDelay, clock);
+
+    assert_eq!(bmp280.measure(), Err(Bmx280Error::Timeout));
+    i2c.done();
+}
+
+#[test]
+fn uses_the_configured_address() {
+    let mut i2c = I2cMock::new(&[Transaction::write(0x77, vec![0xe0, 0xb6])]);
+    let mut sensor = Bmx280Sensor::new(i2c.clone(), NoDelay, clock).with_address(0x77);
+
+    sensor.reset().unwrap();
+    i2c.done();
+}
+
+#[test]
+fn reports_i2c_errors() {
+    let mut i2c = I2cMock::new(&[
+        Transaction::write_read(ADDRESS, vec![0xd0], vec![0x58]).with_error(ErrorKind::Other)
+    ]);
+    let mut sensor = Bmx280Sensor::new(i2c.clone(), NoDelay, clock);
+
+    assert_eq!(sensor.measure(), Err(Bmx280Error::I2c(ErrorKind::Other)));
+    i2c.done();
+}
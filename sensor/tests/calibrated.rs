@@ -0,0 +1,186 @@
+mod common;
+
+use airquamon_domain::{Data, Measurement, Quality, QualityFlags, SourceId};
+use common::{clock, crc8};
+use sensor::{
+    Calibrated, CalibrationProfile, Correction, MockSensor, ProfileError, Sensor,
+    CALIBRATION_PROFILE_LENGTH,
+};
+
+/// Always returns the same measurement.
+struct Fixed(Measurement);
+
+impl Sensor for Fixed {
+    type Error = ();
+
+    fn measure(&mut self) -> Result<Measurement, Self::Error> {
+        Ok(self.0)
+    }
+}
+
+#[test]
+fn identity_profile_leaves_measurements_unchanged() {
+    let mut sensor = Calibrated::new(
+        MockSensor::new(clock, 800, 21.5, 40.0),
+        CalibrationProfile::IDENTITY,
+    );
+
+    let measurement = sensor.measure().unwrap();
+
+    assert_eq!(
+        measurement.data,
+        Data {
+            co2: 800,
+            temperature: 21.5,
+            humidity: 40.0,
+        }
+    );
+}
+
+#[test]
+fn applies_gain_and_offset_to_data() {
+    let profile = CalibrationProfile {
+        co2: Correction::offset(-30.0),
+        temperature: Correction {
+            gain: 0.9,
+            offset: -1.5,
+        },
+        humidity: Correction {
+            gain: 1.1,
+            offset: 2.0,
+        },
+    };
+    let mut sensor = Calibrated::new(MockSensor::new(clock, 800, 25.0, 40.0), profile);
+
+    let measurement = sensor.measure().unwrap();
+
+    assert_eq!(measurement.data.co2, 770);
+    assert!((measurement.data.temperature - 21.0).abs() < 1e-4);
+    assert!((measurement.data.humidity - 46.0).abs() < 1e-4);
+    assert_eq!(measurement.quality, QualityFlags::VALID);
+    assert_eq!(measurement.timestamp, clock());
+}
+
+#[test]
+fn rounds_and_clamps_co2() {
+    let profile = CalibrationProfile {
+        co2: Correction {
+            gain: 1.0,
+            offset: -500.4,
+        },
+        ..CalibrationProfile::IDENTITY
+    };
+    let mut sensor = Calibrated::new(MockSensor::new(clock, 800, 25.0, 40.0), profile);
+    assert_eq!(sensor.measure().unwrap().data.co2, 300);
+
+    sensor.set_profile(CalibrationProfile {
+        co2: Correction::offset(-1000.0),
+        ..CalibrationProfile::IDENTITY
+    });
+    assert_eq!(sensor.measure().unwrap().data.co2, 0);
+}
+
+#[test]
+fn leaves_missing_values_alone() {
+    let mut measurement = Measurement::new(
+        clock(),
+        SourceId::UNKNOWN,
+        Data {
+            co2: 0,
+            temperature: 20.0,
+            humidity: 0.0,
+        },
+    );
+    measurement.quality.co2 = Quality::Missing;
+    measurement.quality.humidity = Quality::Missing;
+    let profile = CalibrationProfile {
+        co2: Correction::offset(50.0),
+        temperature: Correction::offset(-2.0),
+        humidity: Correction::offset(5.0),
+    };
+    let mut sensor = Calibrated::new(Fixed(measurement), profile);
+
+    let measurement = sensor.measure().unwrap();
+
+    assert_eq!(
+        measurement.data,
+        Data {
+            co2: 0,
+            temperature: 18.0,
+            humidity: 0.0,
+        }
+    );
+    assert_eq!(measurement.co2(), None);
+    assert_eq!(measurement.humidity(), None);
+}
+
+#[test]
+fn two_point_calibration_maps_measured_onto_reference_values() {
+    // the sensor reads 2 °C high in a cold room and 4 °C high in a warm one
+    let correction = Correction::two_point((17.0, 29.0), (15.0, 25.0)).unwrap();
+
+    assert!((correction.apply(17.0) - 15.0).abs() < 1e-4);
+    assert!((correction.apply(29.0) - 25.0).abs() < 1e-4);
+    assert!((correction.apply(23.0) - 20.0).abs() < 1e-4);
+
+    let profile = CalibrationProfile {
+        temperature: correction,
+        ..CalibrationProfile::IDENTITY
+    };
+    let mut sensor = Calibrated::new(MockSensor::new(clock, 800, 23.0, 40.0), profile);
+    assert!((sensor.measure().unwrap().data.temperature - 20.0).abs() < 1e-4);
+}
+
+#[test]
+fn two_point_calibration_needs_distinct_measured_values() {
+    assert_eq!(Correction::two_point((20.0, 20.0), (19.0, 21.0)), None);
+}
+
+#[test]
+fn stored_profile_round_trips_and_flows_through_to_data() {
+    let profile = CalibrationProfile {
+        co2: Correction::offset(25.0),
+        temperature: Correction {
+            gain: 0.98,
+            offset: -3.2,
+        },
+        humidity: Correction::offset(4.5),
+    };
+
+    let bytes = profile.to_bytes();
+    let loaded = CalibrationProfile::from_bytes(&bytes).unwrap();
+    assert_eq!(loaded, profile);
+
+    let mut sensor = Calibrated::new(MockSensor::new(clock, 600, 30.0, 35.0), loaded);
+    let measurement = sensor.measure().unwrap();
+    assert_eq!(measurement.data.co2, 625);
+    assert!((measurement.data.temperature - 26.2).abs() < 1e-4);
+    assert!((measurement.data.humidity - 39.5).abs() < 1e-4);
+}
+
+#[test]
+fn rejects_corrupted_profiles() {
+    let mut bytes = CalibrationProfile::IDENTITY.to_bytes();
+    assert_eq!(
+        CalibrationProfile::from_bytes(&bytes[..CALIBRATION_PROFILE_LENGTH - 1]),
+        Err(ProfileError::Length)
+    );
+
+    bytes[3] ^= 0x01;
+    assert_eq!(
+        CalibrationProfile::from_bytes(&bytes),
+        Err(ProfileError::Checksum)
+    );
+}
+
+#[test]
+fn rejects_unknown_profile_versions() {
+    let mut bytes = CalibrationProfile::IDENTITY.to_bytes();
+    bytes[0] = 2;
+    bytes[CALIBRATION_PROFILE_LENGTH - 1] = crc8(&bytes[..CALIBRATION_PROFILE_LENGTH - 1]);
+
+    assert_eq!(
+        CalibrationProfile::from_bytes(&bytes),
+        Err(ProfileError::Version(2))
+    );
+}
@@ -0,0 +1,55 @@
+//! Fixtures shared by the sensor tests. Not every test uses all of them.
+#![allow(dead_code)]
+
+use airquamon_domain::Timestamp;
+use embedded_hal_mock::eh1::i2c::Transaction;
+
+/// Returns immediately, blocking and async. Neither `Copy` nor `Clone`, like the delays of
+/// some HALs.
+pub struct NoDelay;
+
+impl embedded_hal::delay::DelayUs for NoDelay {
+    fn delay_us(&mut self, _us: u32) {}
+}
+
+impl embedded_hal_async::delay::DelayUs for NoDelay {
+    async fn delay_us(&mut self, _us: u32) {}
+
+    async fn delay_ms(&mut self, _ms: u32) {}
+}
+
+pub fn clock() -> Timestamp {
+    Timestamp::from_millis(1_000)
+}
+
+/// The expected transactions of the parts in order.
+pub fn transcript<const N: usize>(parts: [&[Transaction]; N]) -> Vec<Transaction> {
+    parts.concat()
+}
+
+/// CRC-8 with polynomial 0x31 and initial value 0xff, as used by the Sensirion sensors.
+pub fn crc8(data: &[u8]) -> u8 {
+    let mut crc = 0xff;
+    for byte in data {
+        crc ^= byte;
+        for _ in 0..8 {
+            crc = if crc & 0x80 != 0 {
+                (crc << 1) ^ 0x31
+            } else {
+                crc << 1
+            };
+        }
+    }
+    crc
+}
+
+/// Words as sent by a Sensirion sensor, each followed by its checksum.
+pub fn words(words: &[u16]) -> Vec<u8> {
+    words
+        .iter()
+        .flat_map(|word| {
+            let [high, low] = word.to_be_bytes();
+            [high, low, crc8(&[high, low])]
+        })
+        .collect()
+}
@@ -0,0 +1,227 @@
+use airquamon_domain::{Data, Field, Measurement, Quality, SourceId, Timestamp};
+use sensor::{
+    CompositeError, CompositeSensor, ErrorKind, FaultError, FaultInjector, FaultRates, MockSensor,
+    Route, ScenarioError, Sensor, SensorError,
+};
+use std::collections::VecDeque;
+
+#[derive(Debug)]
+struct Failure(ErrorKind);
+
+impl SensorError for Failure {
+    fn kind(&self) -> ErrorKind {
+        self.0
+    }
+}
+
+/// Returns the scripted results in order.
+struct Scripted(VecDeque<Result<Measurement, ErrorKind>>);
+
+impl Scripted {
+    fn new(results: &[Result<Measurement, ErrorKind>]) -> Self {
+        Self(results.iter().copied().collect())
+    }
+}
+
+impl Sensor for Scripted {
+    type Error = Failure;
+
+    fn measure(&mut self) -> Result<Measurement, Self::Error> {
+        self.0
+            .pop_front()
+            .expect("measured too often")
+            .map_err(Failure)
+    }
+}
+
+/// CO2, temperature and humidity of an SCD4x.
+fn scd4x(millis: u64) -> Measurement {
+    Measurement::new(
+        Timestamp::from_millis(millis),
+        SourceId(41),
+        Data {
+            co2: 800,
+            temperature: 24.0,
+            humidity: 38.0,
+        },
+    )
+}
+
+/// Temperature and humidity of a more accurate SHT4x.
+fn sht4x(millis: u64) -> Measurement {
+    let mut measurement = Measurement::new(
+        Timestamp::from_millis(millis),
+        SourceId(45),
+        Data {
+            co2: 0,
+            temperature: 22.5,
+            humidity: 41.0,
+        },
+    );
+    measurement.quality.co2 = Quality::Missing;
+    measurement
+}
+
+const ROUTES: [Route; 3] = [
+    Route {
+        field: Field::Co2,
+        sources: &[0],
+    },
+    Route {
+        field: Field::Temperature,
+        sources: &[1, 0],
+    },
+    Route {
+        field: Field::Humidity,
+        sources: &[1, 0],
+    },
+];
+
+#[test]
+fn takes_each_field_from_its_preferred_source() {
+    let (mut scd, mut sht) = (
+        Scripted::new(&[Ok(scd4x(1_000))]),
+        Scripted::new(&[Ok(sht4x(1_010))]),
+    );
+    let mut sensor = CompositeSensor::new([&mut scd, &mut sht], &ROUTES);
+
+    let measurement = sensor.measure().unwrap();
+
+    assert_eq!(measurement.co2(), Some(800));
+    assert_eq!(measurement.temperature(), Some(22.5));
+    assert_eq!(measurement.humidity(), Some(41.0));
+    assert_eq!(measurement.timestamp, Timestamp::from_millis(1_000));
+    assert_eq!(measurement.source, SourceId(41));
+    // fields without a route are missing
+    assert_eq!(measurement.pressure, None);
+    let report = sensor.report();
+    assert_eq!(report.source(Field::Co2), Some(0));
+    assert_eq!(report.source(Field::Temperature), Some(1));
+    assert_eq!(report.source(Field::Pressure), None);
+    assert!(!report.is_partial());
+    assert_eq!(report.failures().count(), 0);
+}
+
+#[test]
+fn falls_back_while_the_sources_fail_in_turn() {
+    let (mut scd, mut sht) = (
+        Scripted::new(&[Ok(scd4x(1_000)), Err(ErrorKind::Timeout), Ok(scd4x(3_000))]),
+        Scripted::new(&[Err(ErrorKind::Bus), Ok(sht4x(2_010)), Ok(sht4x(3_010))]),
+    );
+    let mut sensor = CompositeSensor::new([&mut scd, &mut sht], &ROUTES);
+
+    // (co2, temperature, co2 source, temperature source, failures)
+    let table = [
+        (
+            Some(800),
+            Some(24.0),
+            Some(0),
+            Some(0),
+            vec![(1, ErrorKind::Bus)],
+        ),
+        (
+            None,
+            Some(22.5),
+            None,
+            Some(1),
+            vec![(0, ErrorKind::Timeout)],
+        ),
+        (Some(800), Some(22.5), Some(0), Some(1), vec![]),
+    ];
+    for (co2, temperature, co2_source, temperature_source, failures) in table {
+        let measurement = sensor.measure().unwrap();
+        assert_eq!(measurement.co2(), co2);
+        assert_eq!(measurement.temperature(), temperature);
+
+        let report = sensor.report();
+        assert_eq!(report.source(Field::Co2), co2_source);
+        assert_eq!(report.source(Field::Temperature), temperature_source);
+        assert_eq!(report.failures().collect::<Vec<_>>(), failures);
+        assert_eq!(report.is_partial(), !failures.is_empty());
+        for (index, kind) in failures {
+            assert!(report.failed(index));
+            assert_eq!(report.error(index), Some(kind));
+        }
+    }
+}
+
+#[test]
+fn missing_field_of_a_failed_source_is_marked_missing() {
+    let (mut scd, mut sht) = (
+        Scripted::new(&[Err(ErrorKind::Corrupted)]),
+        Scripted::new(&[Ok(sht4x(1_010))]),
+    );
+    let mut sensor = CompositeSensor::new([&mut scd, &mut sht], &ROUTES);
+
+    let measurement = sensor.measure().unwrap();
+
+    assert_eq!(measurement.quality.co2, Quality::Missing);
+    // timestamp and source of the first source that could be measured
+    assert_eq!(measurement.timestamp, Timestamp::from_millis(1_010));
+    assert_eq!(measurement.source, SourceId(45));
+    assert!(sensor.report().failed(0));
+    assert!(!sensor.report().failed(1));
+}
+
+#[test]
+fn falls_back_when_the_preferred_source_reports_a_field_missing() {
+    let mut without_humidity = sht4x(1_010);
+    without_humidity.quality.humidity = Quality::Missing;
+    let (mut scd, mut sht) = (
+        Scripted::new(&[Ok(scd4x(1_000))]),
+        Scripted::new(&[Ok(without_humidity)]),
+    );
+    let mut sensor = CompositeSensor::new([&mut scd, &mut sht], &ROUTES);
+
+    let measurement = sensor.measure().unwrap();
+
+    assert_eq!(measurement.humidity(), Some(38.0));
+    assert_eq!(sensor.report().source(Field::Humidity), Some(0));
+    assert!(!sensor.report().is_partial());
+}
+
+#[test]
+fn fails_when_every_source_fails() {
+    let (mut scd, mut sht) = (
+        Scripted::new(&[Err(ErrorKind::Timeout)]),
+        Scripted::new(&[Err(ErrorKind::Bus)]),
+    );
+    let mut sensor = CompositeSensor::new([&mut scd, &mut sht], &ROUTES);
+
+    assert_eq!(sensor.measure(), Err(CompositeError::AllFailed));
+    let report = sensor.report();
+    assert_eq!(
+        report.failures().collect::<Vec<_>>(),
+        [(0, ErrorKind::Timeout), (1, ErrorKind::Bus)]
+    );
+    assert_eq!(report.source(Field::Co2), None);
+    assert_eq!(report.error(2), None);
+}
+
+#[test]
+fn keeps_the_kind_of_errors_of_wrapped_sensors() {
+    let clock = || Timestamp::from_millis(1_000);
+    let always =
+        |rates: FaultRates| FaultInjector::new(MockSensor::new(clock, 800, 22.5, 41.0), rates, 1);
+    let mut injected = always(FaultRates {
+        error: 1.0,
+        ..FaultRates::default()
+    });
+    let mut timing_out = always(FaultRates {
+        timeout: 1.0,
+        ..FaultRates::default()
+    });
+    let routes = [Route {
+        field: Field::Co2,
+        sources: &[0, 1],
+    }];
+    let mut sensor = CompositeSensor::new([&mut injected, &mut timing_out], &routes);
+
+    assert_eq!(sensor.measure(), Err(CompositeError::AllFailed));
+    assert_eq!(sensor.report().error(0), Some(ErrorKind::Other));
+    assert_eq!(sensor.report().error(1), Some(ErrorKind::Timeout));
+    assert_eq!(
+        FaultError::Sensor(ScenarioError::End).kind(),
+        ErrorKind::End
+    );
+}
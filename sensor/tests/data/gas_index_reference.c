@@ -0,0 +1,372 @@
+/*
+ * Generates gas_index_reference.txt, which tests/gas_index.rs replays against
+ * GasIndexAlgorithm.
+ *
+ * The algorithm below is a transcription of sensirion_gas_index_algorithm.c
+ * v3.2.0 from https://github.com/Sensirion/gas-index-algorithm, kept apart
+ * from the Rust port so the two can be compared. Functions and fields follow
+ * the upstream names to make that comparison easy. The sampling interval is
+ * fixed at 1 s.
+ *
+ * Build and regenerate with:
+ *
+ *     cc -O2 -o gas_index_reference gas_index_reference.c -lm
+ *     ./gas_index_reference > gas_index_reference.txt
+ *
+ * Each output line is: time in s, VOC raw signal, NOx raw signal, VOC index,
+ * NOx index. The raw signals are a steady baseline with a little noise, a VOC
+ * event (the signal drops) between 3600 s and 3900 s and a NOx event (the
+ * signal rises) between 4000 s and 4300 s.
+ */
+
+#include <math.h>
+#include <stdbool.h>
+#include <stdint.h>
+#include <stdio.h>
+
+#define ALGORITHM_TYPE_VOC 0
+#define ALGORITHM_TYPE_NOX 1
+
+#define SAMPLING_INTERVAL 1.f
+#define INITIAL_BLACKOUT 45.f
+#define INDEX_GAIN 230.f
+#define SRAW_STD_INITIAL 50.f
+#define SRAW_STD_BONUS_VOC 220.f
+#define SRAW_STD_NOX 2000.f
+#define TAU_MEAN_HOURS 12.f
+#define TAU_VARIANCE_HOURS 12.f
+#define TAU_INITIAL_MEAN_VOC 20.f
+#define TAU_INITIAL_MEAN_NOX 1200.f
+#define TAU_INITIAL_VARIANCE 2500.f
+#define GATING_THRESHOLD_INITIAL 510.f
+#define GATING_THRESHOLD_TRANSITION 0.09f
+#define GATING_VOC_MAX_RATIO 0.3f
+#define SIGMOID_L 500.f
+#define SIGMOID_K_VOC -0.0065f
+#define SIGMOID_X0_VOC 213.f
+#define SIGMOID_K_NOX -0.0101f
+#define SIGMOID_X0_NOX 614.f
+#define VOC_INDEX_OFFSET_DEFAULT 100.f
+#define NOX_INDEX_OFFSET_DEFAULT 1.f
+#define LP_TAU_FAST 20.f
+#define LP_TAU_SLOW 500.f
+#define LP_ALPHA -0.2f
+#define PERSISTENCE_UPTIME_GAMMA (3.f * 3600.f)
+#define MEAN_VARIANCE_ESTIMATOR__GAMMA_SCALING 64.f
+#define MEAN_VARIANCE_ESTIMATOR__ADDITIONAL_GAMMA_MEAN_SCALING 8.f
+#define MEAN_VARIANCE_ESTIMATOR__FIX16_MAX 32767.f
+
+typedef struct {
+    int algorithm_type;
+    float index_offset;
+    int32_t sraw_minimum;
+    float gating_max_duration_minutes;
+    float init_duration_mean;
+    float init_duration_variance;
+    float gating_threshold;
+    float uptime;
+    float sraw;
+    float gas_index;
+
+    bool mve_initialized;
+    float mve_mean;
+    float mve_sraw_offset;
+    float mve_std;
+    float mve_gamma_mean;
+    float mve_gamma_variance;
+    float mve_gamma_initial_mean;
+    float mve_gamma_initial_variance;
+    float mve_gamma_mean_current;
+    float mve_gamma_variance_current;
+    float mve_uptime_gamma;
+    float mve_uptime_gating;
+    float mve_gating_duration_minutes;
+    float mve_sigmoid_k;
+    float mve_sigmoid_x0;
+
+    float mox_model_sraw_std;
+    float mox_model_sraw_mean;
+
+    float sigmoid_scaled_k;
+    float sigmoid_scaled_x0;
+    float sigmoid_scaled_offset_default;
+
+    float lp_a1;
+    float lp_a2;
+    bool lp_initialized;
+    float lp_x1;
+    float lp_x2;
+    float lp_x3;
+} GasIndexAlgorithmParams;
+
+static void mean_variance_estimator__set_parameters(GasIndexAlgorithmParams* p) {
+    p->mve_initialized = false;
+    p->mve_mean = 0.f;
+    p->mve_sraw_offset = 0.f;
+    p->mve_std = SRAW_STD_INITIAL;
+    p->mve_gamma_mean =
+        ((MEAN_VARIANCE_ESTIMATOR__ADDITIONAL_GAMMA_MEAN_SCALING *
+          MEAN_VARIANCE_ESTIMATOR__GAMMA_SCALING) *
+         (SAMPLING_INTERVAL / 3600.f)) /
+        (TAU_MEAN_HOURS + (SAMPLING_INTERVAL / 3600.f));
+    p->mve_gamma_variance =
+        (MEAN_VARIANCE_ESTIMATOR__GAMMA_SCALING * (SAMPLING_INTERVAL / 3600.f)) /
+        (TAU_VARIANCE_HOURS + (SAMPLING_INTERVAL / 3600.f));
+    float tau_initial_mean = p->algorithm_type == ALGORITHM_TYPE_NOX
+                                 ? TAU_INITIAL_MEAN_NOX
+                                 : TAU_INITIAL_MEAN_VOC;
+    p->mve_gamma_initial_mean =
+        ((MEAN_VARIANCE_ESTIMATOR__ADDITIONAL_GAMMA_MEAN_SCALING *
+          MEAN_VARIANCE_ESTIMATOR__GAMMA_SCALING) *
+         SAMPLING_INTERVAL) /
+        (tau_initial_mean + SAMPLING_INTERVAL);
+    p->mve_gamma_initial_variance =
+        (MEAN_VARIANCE_ESTIMATOR__GAMMA_SCALING * SAMPLING_INTERVAL) /
+        (TAU_INITIAL_VARIANCE + SAMPLING_INTERVAL);
+    p->mve_gamma_mean_current = 0.f;
+    p->mve_gamma_variance_current = 0.f;
+    p->mve_uptime_gamma = 0.f;
+    p->mve_uptime_gating = 0.f;
+    p->mve_gating_duration_minutes = 0.f;
+}
+
+static void mean_variance_estimator__sigmoid__set_parameters(
+    GasIndexAlgorithmParams* p, float x0, float k) {
+    p->mve_sigmoid_k = k;
+    p->mve_sigmoid_x0 = x0;
+}
+
+static float mean_variance_estimator__sigmoid__process(GasIndexAlgorithmParams* p,
+                                                       float sample) {
+    float x = p->mve_sigmoid_k * (sample - p->mve_sigmoid_x0);
+    if (x < -50.f) {
+        return 1.f;
+    } else if (x > 50.f) {
+        return 0.f;
+    }
+    return 1.f / (1.f + expf(x));
+}
+
+static void mean_variance_estimator__calculate_gamma(GasIndexAlgorithmParams* p) {
+    float uptime_limit = MEAN_VARIANCE_ESTIMATOR__FIX16_MAX - SAMPLING_INTERVAL;
+    if (p->mve_uptime_gamma < uptime_limit) {
+        p->mve_uptime_gamma += SAMPLING_INTERVAL;
+    }
+    if (p->mve_uptime_gating < uptime_limit) {
+        p->mve_uptime_gating += SAMPLING_INTERVAL;
+    }
+
+    mean_variance_estimator__sigmoid__set_parameters(p, p->init_duration_mean, 0.01f);
+    float sigmoid_gamma_mean =
+        mean_variance_estimator__sigmoid__process(p, p->mve_uptime_gamma);
+    float gamma_mean = p->mve_gamma_mean +
+                       ((p->mve_gamma_initial_mean - p->mve_gamma_mean) * sigmoid_gamma_mean);
+    float gating_threshold_mean =
+        p->gating_threshold +
+        ((GATING_THRESHOLD_INITIAL - p->gating_threshold) *
+         mean_variance_estimator__sigmoid__process(p, p->mve_uptime_gating));
+    mean_variance_estimator__sigmoid__set_parameters(p, gating_threshold_mean,
+                                                     GATING_THRESHOLD_TRANSITION);
+    float sigmoid_gating_mean = mean_variance_estimator__sigmoid__process(p, p->gas_index);
+    p->mve_gamma_mean_current = sigmoid_gating_mean * gamma_mean;
+
+    mean_variance_estimator__sigmoid__set_parameters(p, p->init_duration_variance, 0.01f);
+    float sigmoid_gamma_variance =
+        mean_variance_estimator__sigmoid__process(p, p->mve_uptime_gamma);
+    float gamma_variance =
+        p->mve_gamma_variance +
+        ((p->mve_gamma_initial_variance - p->mve_gamma_variance) *
+         (sigmoid_gamma_variance - sigmoid_gamma_mean));
+    float gating_threshold_variance =
+        p->gating_threshold +
+        ((GATING_THRESHOLD_INITIAL - p->gating_threshold) *
+         mean_variance_estimator__sigmoid__process(p, p->mve_uptime_gating));
+    mean_variance_estimator__sigmoid__set_parameters(p, gating_threshold_variance,
+                                                     GATING_THRESHOLD_TRANSITION);
+    float sigmoid_gating_variance =
+        mean_variance_estimator__sigmoid__process(p, p->gas_index);
+    p->mve_gamma_variance_current = sigmoid_gating_variance * gamma_variance;
+
+    p->mve_gating_duration_minutes =
+        p->mve_gating_duration_minutes +
+        ((SAMPLING_INTERVAL / 60.f) *
+         (((1.f - sigmoid_gating_mean) * (1.f + GATING_VOC_MAX_RATIO)) -
+          GATING_VOC_MAX_RATIO));
+    if (p->mve_gating_duration_minutes < 0.f) {
+        p->mve_gating_duration_minutes = 0.f;
+    }
+    if (p->mve_gating_duration_minutes > p->gating_max_duration_minutes) {
+        p->mve_uptime_gating = 0.f;
+    }
+}
+
+static void mean_variance_estimator__process(GasIndexAlgorithmParams* p, float sraw) {
+    if (!p->mve_initialized) {
+        p->mve_initialized = true;
+        p->mve_sraw_offset = sraw;
+        p->mve_mean = 0.f;
+        return;
+    }
+    if (p->mve_mean >= 100.f || p->mve_mean <= -100.f) {
+        p->mve_sraw_offset = p->mve_sraw_offset + p->mve_mean;
+        p->mve_mean = 0.f;
+    }
+    sraw = sraw - p->mve_sraw_offset;
+    mean_variance_estimator__calculate_gamma(p);
+    float delta_sgp = (sraw - p->mve_mean) / MEAN_VARIANCE_ESTIMATOR__GAMMA_SCALING;
+    float c;
+    if (delta_sgp < 0.f) {
+        c = p->mve_std - delta_sgp;
+    } else {
+        c = p->mve_std + delta_sgp;
+    }
+    float additional_scaling = 1.f;
+    if (c > 1440.f) {
+        additional_scaling = (c / 1440.f) * (c / 1440.f);
+    }
+    p->mve_std =
+        sqrtf(additional_scaling *
+              (MEAN_VARIANCE_ESTIMATOR__GAMMA_SCALING - p->mve_gamma_variance_current)) *
+        sqrtf((p->mve_std *
+               (p->mve_std / (MEAN_VARIANCE_ESTIMATOR__GAMMA_SCALING * additional_scaling))) +
+              (((p->mve_gamma_variance_current * delta_sgp) / additional_scaling) * delta_sgp));
+    p->mve_mean = p->mve_mean + ((p->mve_gamma_mean_current * delta_sgp) /
+                                 MEAN_VARIANCE_ESTIMATOR__ADDITIONAL_GAMMA_MEAN_SCALING);
+}
+
+static float mox_model__process(GasIndexAlgorithmParams* p, float sraw) {
+    if (p->algorithm_type == ALGORITHM_TYPE_NOX) {
+        return ((sraw - p->mox_model_sraw_mean) / SRAW_STD_NOX) * INDEX_GAIN;
+    }
+    return ((sraw - p->mox_model_sraw_mean) / (-(p->mox_model_sraw_std + SRAW_STD_BONUS_VOC))) *
+           INDEX_GAIN;
+}
+
+static float sigmoid_scaled__process(GasIndexAlgorithmParams* p, float sample) {
+    float x = p->sigmoid_scaled_k * (sample - p->sigmoid_scaled_x0);
+    if (x < -50.f) {
+        return SIGMOID_L;
+    } else if (x > 50.f) {
+        return 0.f;
+    }
+    if (sample >= 0.f) {
+        float shift;
+        if (p->sigmoid_scaled_offset_default == 1.f) {
+            shift = (500.f / 499.f) * (1.f - p->index_offset);
+        } else {
+            shift = (SIGMOID_L - (5.f * p->index_offset)) / 4.f;
+        }
+        return ((SIGMOID_L + shift) / (1.f + expf(x))) - shift;
+    }
+    return (p->index_offset / p->sigmoid_scaled_offset_default) * (SIGMOID_L / (1.f + expf(x)));
+}
+
+static float adaptive_lowpass__process(GasIndexAlgorithmParams* p, float sample) {
+    if (!p->lp_initialized) {
+        p->lp_x1 = sample;
+        p->lp_x2 = sample;
+        p->lp_x3 = sample;
+        p->lp_initialized = true;
+    }
+    p->lp_x1 = ((1.f - p->lp_a1) * p->lp_x1) + (p->lp_a1 * sample);
+    p->lp_x2 = ((1.f - p->lp_a2) * p->lp_x2) + (p->lp_a2 * sample);
+    float abs_delta = p->lp_x1 - p->lp_x2;
+    if (abs_delta < 0.f) {
+        abs_delta = -abs_delta;
+    }
+    float f1 = expf(LP_ALPHA * abs_delta);
+    float tau_a = ((LP_TAU_SLOW - LP_TAU_FAST) * f1) + LP_TAU_FAST;
+    float a3 = SAMPLING_INTERVAL / (SAMPLING_INTERVAL + tau_a);
+    p->lp_x3 = ((1.f - a3) * p->lp_x3) + (a3 * sample);
+    return p->lp_x3;
+}
+
+static void GasIndexAlgorithm_reset(GasIndexAlgorithmParams* p) {
+    p->uptime = 0.f;
+    p->sraw = 0.f;
+    p->gas_index = 0.f;
+    mean_variance_estimator__set_parameters(p);
+    p->mox_model_sraw_std = p->mve_std;
+    p->mox_model_sraw_mean = p->mve_mean + p->mve_sraw_offset;
+    if (p->algorithm_type == ALGORITHM_TYPE_NOX) {
+        p->sigmoid_scaled_k = SIGMOID_K_NOX;
+        p->sigmoid_scaled_x0 = SIGMOID_X0_NOX;
+        p->sigmoid_scaled_offset_default = NOX_INDEX_OFFSET_DEFAULT;
+    } else {
+        p->sigmoid_scaled_k = SIGMOID_K_VOC;
+        p->sigmoid_scaled_x0 = SIGMOID_X0_VOC;
+        p->sigmoid_scaled_offset_default = VOC_INDEX_OFFSET_DEFAULT;
+    }
+    p->lp_a1 = SAMPLING_INTERVAL / (LP_TAU_FAST + SAMPLING_INTERVAL);
+    p->lp_a2 = SAMPLING_INTERVAL / (LP_TAU_SLOW + SAMPLING_INTERVAL);
+    p->lp_initialized = false;
+}
+
+static void GasIndexAlgorithm_init(GasIndexAlgorithmParams* p, int algorithm_type) {
+    p->algorithm_type = algorithm_type;
+    if (algorithm_type == ALGORITHM_TYPE_NOX) {
+        p->index_offset = NOX_INDEX_OFFSET_DEFAULT;
+        p->sraw_minimum = 10000;
+        p->gating_max_duration_minutes = 60.f * 12.f;
+        p->init_duration_mean = 3600.f * 4.75f;
+        p->init_duration_variance = 3600.f * 5.70f;
+        p->gating_threshold = 30.f;
+    } else {
+        p->index_offset = VOC_INDEX_OFFSET_DEFAULT;
+        p->sraw_minimum = 20000;
+        p->gating_max_duration_minutes = 60.f * 3.f;
+        p->init_duration_mean = 3600.f * 0.75f;
+        p->init_duration_variance = 3600.f * 1.45f;
+        p->gating_threshold = 340.f;
+    }
+    GasIndexAlgorithm_reset(p);
+}
+
+static int32_t GasIndexAlgorithm_process(GasIndexAlgorithmParams* p, int32_t sraw) {
+    if (p->uptime <= INITIAL_BLACKOUT) {
+        p->uptime += SAMPLING_INTERVAL;
+    } else {
+        if (sraw > 0 && sraw < 65000) {
+            if (sraw < p->sraw_minimum + 1) {
+                sraw = p->sraw_minimum + 1;
+            } else if (sraw > p->sraw_minimum + 32767) {
+                sraw = p->sraw_minimum + 32767;
+            }
+            p->sraw = (float)(sraw - p->sraw_minimum);
+        }
+        if (p->algorithm_type == ALGORITHM_TYPE_VOC || p->mve_initialized) {
+            p->gas_index = mox_model__process(p, p->sraw);
+            p->gas_index = sigmoid_scaled__process(p, p->gas_index);
+        } else {
+            p->gas_index = p->index_offset;
+        }
+        p->gas_index = adaptive_lowpass__process(p, p->gas_index);
+        if (p->gas_index < 0.5f) {
+            p->gas_index = 0.5f;
+        }
+        if (p->sraw > 0.f) {
+            mean_variance_estimator__process(p, p->sraw);
+            p->mox_model_sraw_std = p->mve_std;
+            p->mox_model_sraw_mean = p->mve_mean + p->mve_sraw_offset;
+        }
+    }
+    return (int32_t)(p->gas_index + 0.5f);
+}
+
+int main(void) {
+    GasIndexAlgorithmParams voc;
+    GasIndexAlgorithmParams nox;
+    GasIndexAlgorithm_init(&voc, ALGORITHM_TYPE_VOC);
+    GasIndexAlgorithm_init(&nox, ALGORITHM_TYPE_NOX);
+
+    for (int32_t t = 0; t < 4500; t++) {
+        int32_t voc_event = t > 3600 && t < 3900 ? -2000 : 0;
+        int32_t nox_event = t > 4000 && t < 4300 ? 1500 : 0;
+        int32_t voc_raw = 30000 + voc_event + (t % 7) * 3;
+        int32_t nox_raw = 15000 + nox_event + (t % 5) * 2;
+        int32_t voc_index = GasIndexAlgorithm_process(&voc, voc_raw);
+        int32_t nox_index = GasIndexAlgorithm_process(&nox, nox_raw);
+        printf("%d %d %d %d %d\n", t, voc_raw, nox_raw, voc_index, nox_index);
+    }
+    return 0;
+}
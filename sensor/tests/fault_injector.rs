@@ -0,0 +1,166 @@
+use airquamon_domain::filter::{Ema, Filter, Median, SpikeRejection};
+use airquamon_domain::{Measurement, Quality};
+use core::time::Duration;
+use sensor::{
+    FaultError, FaultInjector, FaultRates, Profile, Scenario, ScenarioError, ScenarioSensor,
+    Sensor, Signal,
+};
+
+const CO2: [Profile; 1] = [Profile::Constant(800.0)];
+const TEMPERATURE: [Profile; 1] = [Profile::Constant(21.5)];
+const HUMIDITY: [Profile; 1] = [Profile::Constant(45.0)];
+
+/// Reads 800 ppm, 21.5 °C and 45 % every minute.
+fn steady() -> Scenario<'static> {
+    Scenario {
+        co2: Signal::new(&CO2, 0.0),
+        temperature: Signal::new(&TEMPERATURE, 0.0),
+        humidity: Signal::new(&HUMIDITY, 0.0),
+        interval: Duration::from_secs(60),
+        duration: None,
+        dropout_rate: 0.0,
+        seed: 1,
+    }
+}
+
+fn injector(rates: FaultRates, seed: u64) -> FaultInjector<ScenarioSensor<'static>> {
+    FaultInjector::new(ScenarioSensor::new(steady()), rates, seed)
+}
+
+fn run(
+    sensor: &mut impl Sensor<Error = FaultError<ScenarioError>>,
+    n: usize,
+) -> Vec<Result<Measurement, FaultError<ScenarioError>>> {
+    (0..n).map(|_| sensor.measure()).collect()
+}
+
+const FIELD_RATES: FaultRates = FaultRates {
+    error: 0.1,
+    timeout: 0.1,
+    stuck: 0.05,
+    stuck_measurements: 12,
+    nan_temperature: 0.2,
+    out_of_range_co2: 0.2,
+};
+
+#[test]
+fn same_seed_injects_the_same_faults() {
+    let first = run(&mut injector(FIELD_RATES, 42), 200);
+
+    // NaN != NaN, so compare the debug output
+    assert_eq!(
+        format!("{first:?}"),
+        format!("{:?}", run(&mut injector(FIELD_RATES, 42), 200))
+    );
+    assert_ne!(
+        format!("{first:?}"),
+        format!("{:?}", run(&mut injector(FIELD_RATES, 43), 200))
+    );
+}
+
+#[test]
+fn injects_errors_and_timeouts() {
+    let mut sensor = injector(
+        FaultRates {
+            error: 1.0,
+            ..FaultRates::default()
+        },
+        1,
+    );
+    assert_eq!(sensor.measure(), Err(FaultError::Injected));
+
+    sensor.set_rates(FaultRates {
+        timeout: 1.0,
+        ..FaultRates::default()
+    });
+    assert_eq!(sensor.measure(), Err(FaultError::Timeout));
+
+    sensor.set_rates(FaultRates::default());
+    assert_eq!(sensor.measure().unwrap().data.co2, 800);
+}
+
+#[test]
+fn stuck_sensor_repeats_its_last_values() {
+    let mut sensor = FaultInjector::new(
+        ScenarioSensor::new(Scenario {
+            co2: Signal::new(&CO2, 20.0),
+            ..steady()
+        }),
+        FaultRates {
+            stuck: 1.0,
+            stuck_measurements: 3,
+            ..FaultRates::default()
+        },
+        1,
+    );
+
+    let readings: Vec<_> = run(&mut sensor, 4)
+        .into_iter()
+        .map(|measurement| measurement.unwrap())
+        .collect();
+
+    assert!(readings[1..].iter().all(|m| m.data == readings[0].data));
+    // timestamps still advance
+    assert!(readings.windows(2).all(|m| m[0].timestamp < m[1].timestamp));
+}
+
+#[test]
+fn validation_marks_injected_values_unusable() {
+    let mut sensor = injector(
+        FaultRates {
+            nan_temperature: 1.0,
+            out_of_range_co2: 1.0,
+            ..FaultRates::default()
+        },
+        7,
+    );
+
+    for measurement in run(&mut sensor, 20) {
+        let measurement = measurement.unwrap().validated();
+        assert!([0, u16::MAX].contains(&measurement.data.co2));
+        assert_eq!(measurement.quality.co2, Quality::Suspect);
+        assert_eq!(measurement.co2(), None);
+        assert_eq!(measurement.quality.temperature, Quality::Missing);
+        assert_eq!(measurement.temperature(), None);
+        assert_eq!(measurement.humidity(), Some(45.0));
+    }
+}
+
+#[test]
+fn faults_dont_reach_the_smoothed_values() {
+    let mut sensor = injector(
+        FaultRates {
+            nan_temperature: 0.3,
+            out_of_range_co2: 0.3,
+            ..FIELD_RATES
+        },
+        11,
+    );
+    let mut smoothing = SpikeRejection::new()
+        .then(Median::<3>::new())
+        .then(Ema::new(0.5));
+
+    let mut errors = 0;
+    let mut unusable_co2 = 0;
+    let mut unusable_temperature = 0;
+    for result in run(&mut sensor, 500) {
+        let Ok(measurement) = result else {
+            errors += 1;
+            continue;
+        };
+        let measurement = smoothing.filter(measurement.validated());
+        match measurement.co2() {
+            Some(co2) => assert_eq!(co2, 800),
+            None => unusable_co2 += 1,
+        }
+        match measurement.temperature() {
+            Some(temperature) => assert_eq!(temperature, 21.5),
+            None => unusable_temperature += 1,
+        }
+        assert_eq!(measurement.humidity(), Some(45.0));
+    }
+
+    assert!(errors > 0);
+    assert!(unusable_co2 > 0);
+    assert!(unusable_temperature > 0);
+}
@@ -0,0 +1,87 @@
+use sensor::{GasIndexAlgorithm, GasIndexKind};
+
+/// Output of `data/gas_index_reference.c`, a transcription of Sensirion's reference
+/// implementation (gas-index-algorithm v3.2.0), once a second: time in s, VOC and NOx raw
+/// signals, VOC and NOx indices.
+const REFERENCE: &str = include_str!("data/gas_index_reference.txt");
+
+fn reference() -> impl Iterator<Item = [u16; 5]> {
+    REFERENCE.lines().map(|line| {
+        let mut values = line.split(' ').map(|value| value.parse().unwrap());
+        [(); 5].map(|_| values.next().unwrap())
+    })
+}
+
+/// VOC and NOx indices at `times` for the raw signals of [`REFERENCE`].
+fn indices_at(times: &[u16]) -> Vec<(u16, u16, u16)> {
+    let mut voc = GasIndexAlgorithm::new(GasIndexKind::Voc);
+    let mut nox = GasIndexAlgorithm::new(GasIndexKind::Nox);
+    let mut indices = Vec::new();
+    for [t, voc_raw, nox_raw, _, _] in reference() {
+        let (voc_index, nox_index) = (voc.process(voc_raw), nox.process(nox_raw));
+        if times.contains(&t) {
+            indices.push((t, voc_index, nox_index));
+        }
+    }
+    indices
+}
+
+#[test]
+fn reports_0_during_the_initial_blackout() {
+    let mut voc = GasIndexAlgorithm::new(GasIndexKind::Voc);
+    let mut nox = GasIndexAlgorithm::new(GasIndexKind::Nox);
+
+    for _ in 0..46 {
+        assert_eq!(voc.process(30_000), 0);
+        assert_eq!(nox.process(15_000), 0);
+    }
+    assert_ne!(voc.process(30_000), 0);
+    assert_ne!(nox.process(15_000), 0);
+}
+
+#[test]
+fn settles_at_100_for_voc_and_1_for_nox() {
+    assert_eq!(
+        indices_at(&[600, 1800, 3600]),
+        [(600, 100, 1), (1800, 100, 1), (3600, 100, 1)]
+    );
+}
+
+#[test]
+fn matches_the_reference_implementation() {
+    let mut voc = GasIndexAlgorithm::new(GasIndexKind::Voc);
+    let mut nox = GasIndexAlgorithm::new(GasIndexKind::Nox);
+
+    let mut seconds = 0;
+    for [t, voc_raw, nox_raw, voc_index, nox_index] in reference() {
+        assert_eq!(voc.process(voc_raw), voc_index, "VOC at {t} s");
+        assert_eq!(nox.process(nox_raw), nox_index, "NOx at {t} s");
+        seconds += 1;
+    }
+    // warming up, the VOC and NOx events and the recovery
+    assert_eq!(seconds, 4500);
+}
+
+#[test]
+fn follows_the_voc_and_nox_events() {
+    // the VOC event between 3600 s and 3900 s, the NOx event between 4000 s and 4300 s
+    let indices = indices_at(&[3600, 3700, 3950, 4200, 4400]);
+
+    assert!(indices[1].1 > 400, "{indices:?}");
+    assert!(indices[2].1 < indices[1].1, "{indices:?}");
+    assert_eq!(indices[0].2, 1);
+    assert!(indices[3].2 > 1, "{indices:?}");
+    assert!(indices[4].2 > 1, "{indices:?}");
+}
+
+#[test]
+fn reset_starts_over() {
+    let mut voc = GasIndexAlgorithm::new(GasIndexKind::Voc);
+    for _ in 0..600 {
+        voc.process(30_000);
+    }
+
+    voc.reset();
+
+    assert_eq!(voc.process(30_000), 0);
+}
@@ -0,0 +1,31 @@
+mod common;
+
+use airquamon_domain::{QualityFlags, SourceId};
+use common::clock;
+use embassy_futures::block_on;
+use sensor::{AsyncSensor, MockSensor, Sensor, SensorKind, StartUp};
+
+#[test]
+fn measures_the_same_blocking_and_async() {
+    let mut sensor = MockSensor::new(clock, 800, 22.5, 41.0);
+
+    let blocking = Sensor::measure(&mut sensor).unwrap();
+    let measurement = block_on(AsyncSensor::measure(&mut sensor)).unwrap();
+
+    assert_eq!(measurement, blocking);
+    assert_eq!(measurement.timestamp, clock());
+    assert_eq!(measurement.co2(), Some(800));
+    assert_eq!(measurement.temperature(), Some(22.5));
+    assert_eq!(measurement.humidity(), Some(41.0));
+    assert_eq!(measurement.quality, QualityFlags::VALID);
+}
+
+#[test]
+fn starts_up_as_a_mock() {
+    let mut sensor = MockSensor::new(clock, 800, 22.5, 41.0);
+
+    let info = sensor.start_up().unwrap();
+
+    assert_eq!(info.kind, SensorKind::Mock);
+    assert_eq!(info.source_id(), SourceId::UNKNOWN);
+}
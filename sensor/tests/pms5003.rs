@@ -0,0 +1,192 @@
+mod common;
+
+use airquamon_domain::{Field, ParticulateMatter};
+use common::clock;
+use embedded_io::blocking::{Read, Write};
+use embedded_io::Io;
+use sensor::{
+    FrameError, Pms5003Error, Pms5003Frame, Pms5003Mode, Pms5003Sensor, Sensor,
+    PMS5003_FRAME_LENGTH,
+};
+
+/// Data frame with the given words after the length, followed by its checksum.
+fn frame(words: [u16; 13]) -> [u8; PMS5003_FRAME_LENGTH] {
+    let mut frame = [0; PMS5003_FRAME_LENGTH];
+    frame[..4].copy_from_slice(&[0x42, 0x4d, 0x00, 0x1c]);
+    for (i, word) in words.iter().enumerate() {
+        frame[4 + 2 * i..6 + 2 * i].copy_from_slice(&word.to_be_bytes());
+    }
+    let checksum: u16 = frame[..30].iter().map(|&byte| u16::from(byte)).sum();
+    frame[30..].copy_from_slice(&checksum.to_be_bytes());
+    frame
+}
+
+const WORDS: [u16; 13] = [5, 12, 20, 4, 11, 18, 900, 300, 60, 8, 2, 1, 0];
+
+/// Serial port replaying received bytes and recording what is sent.
+struct Serial<'a> {
+    received: &'a [u8],
+    sent: Vec<u8>,
+}
+
+impl Io for Serial<'_> {
+    type Error = core::convert::Infallible;
+}
+
+impl Read for Serial<'_> {
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize, Self::Error> {
+        self.received.read(buf)
+    }
+}
+
+impl Write for Serial<'_> {
+    fn write(&mut self, buf: &[u8]) -> Result<usize, Self::Error> {
+        self.sent.extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> Result<(), Self::Error> {
+        Ok(())
+    }
+}
+
+#[test]
+fn parses_data_frame() {
+    let frame = Pms5003Frame::parse(&frame(WORDS)).unwrap();
+
+    assert_eq!(frame.standard, [5, 12, 20]);
+    assert_eq!(frame.atmospheric, [4, 11, 18]);
+    assert_eq!(frame.particles, [900, 300, 60, 8, 2, 1]);
+}
+
+#[test]
+fn rejects_frame_with_wrong_checksum() {
+    let mut bytes = frame(WORDS);
+    bytes[31] ^= 0x01;
+
+    assert_eq!(Pms5003Frame::parse(&bytes), Err(FrameError::Checksum));
+}
+
+#[test]
+fn rejects_frame_with_wrong_start() {
+    let mut bytes = frame(WORDS);
+    bytes[1] = 0x4e;
+
+    assert_eq!(Pms5003Frame::parse(&bytes), Err(FrameError::Start));
+}
+
+#[test]
+fn rejects_frame_with_wrong_length() {
+    let mut bytes = frame(WORDS);
+    bytes[3] = 0x14;
+
+    assert_eq!(Pms5003Frame::parse(&bytes), Err(FrameError::Length));
+}
+
+#[test]
+fn measures_atmospheric_concentrations() {
+    let received = frame(WORDS);
+    let mut pms5003 = Pms5003Sensor::new(
+        Serial {
+            received: &received,
+            sent: Vec::new(),
+        },
+        clock,
+    );
+
+    let measurement = pms5003.measure().unwrap();
+
+    assert_eq!(measurement.timestamp, clock());
+    assert_eq!(
+        measurement.particulate_matter,
+        Some(ParticulateMatter {
+            pm1_0: 4.0,
+            pm2_5: 11.0,
+            pm10: 18.0
+        })
+    );
+    assert_eq!(measurement.value(Field::Pm2_5), Some(11.0));
+    assert_eq!(measurement.co2(), None);
+    assert_eq!(measurement.temperature(), None);
+    assert_eq!(measurement.humidity(), None);
+}
+
+#[test]
+fn synchronises_on_the_start_of_a_frame() {
+    // connected in the middle of a frame, followed by a response to a command
+    let mut received = vec![0x1c, 0x00, 0x42, 0x12];
+    received.extend_from_slice(&[0x42, 0x4d, 0x00, 0x04, 0xe1, 0x00, 0x01, 0x74]);
+    received.extend_from_slice(&frame(WORDS));
+    let mut pms5003 = Pms5003Sensor::new(
+        Serial {
+            received: &received,
+            sent: Vec::new(),
+        },
+        clock,
+    );
+
+    let measurement = pms5003.measure().unwrap();
+
+    assert_eq!(measurement.value(Field::Pm10), Some(18.0));
+}
+
+#[test]
+fn requests_frame_in_passive_mode() {
+    let received = frame(WORDS);
+    let mut pms5003 = Pms5003Sensor::new(
+        Serial {
+            received: &received,
+            sent: Vec::new(),
+        },
+        clock,
+    );
+
+    pms5003.set_mode(Pms5003Mode::Passive).unwrap();
+    pms5003.measure().unwrap();
+
+    assert_eq!(
+        pms5003.release().sent,
+        [
+            [0x42, 0x4d, 0xe1, 0x00, 0x00, 0x01, 0x70],
+            [0x42, 0x4d, 0xe2, 0x00, 0x00, 0x01, 0x71]
+        ]
+        .concat()
+    );
+}
+
+#[test]
+fn sends_sleep_and_wake_up_commands() {
+    let mut pms5003 = Pms5003Sensor::new(
+        Serial {
+            received: &[],
+            sent: Vec::new(),
+        },
+        clock,
+    );
+
+    pms5003.sleep().unwrap();
+    pms5003.wake_up().unwrap();
+
+    assert_eq!(
+        pms5003.release().sent,
+        [
+            [0x42, 0x4d, 0xe4, 0x00, 0x00, 0x01, 0x73],
+            [0x42, 0x4d, 0xe4, 0x00, 0x01, 0x01, 0x74]
+        ]
+        .concat()
+    );
+}
+
+#[test]
+fn reports_incomplete_frame() {
+    let received = frame(WORDS);
+    let mut pms5003 = Pms5003Sensor::new(
+        Serial {
+            received: &received[..20],
+            sent: Vec::new(),
+        },
+        clock,
+    );
+
+    assert_eq!(pms5003.measure(), Err(Pms5003Error::EndOfStream));
+}
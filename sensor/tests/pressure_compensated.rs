@@ -0,0 +1,199 @@
+use airquamon_domain::{Data, Measurement, SourceId, Timestamp};
+use core::time::Duration;
+use sensor::{PressureCompensated, PressureCompensation, Sensor};
+use std::cell::Cell;
+use std::rc::Rc;
+
+const INTERVAL: Duration = Duration::from_secs(60);
+
+/// The clock both sensors share, in ms.
+type Time = Rc<Cell<u64>>;
+
+fn measurement(time: &Time) -> Measurement {
+    Measurement::new(
+        Timestamp::from_millis(time.get()),
+        SourceId::UNKNOWN,
+        Data {
+            co2: 800,
+            temperature: 21.0,
+            humidity: 40.0,
+        },
+    )
+}
+
+/// Records the pressures pushed into it, rejecting them while `fail` is set.
+struct Co2Sensor {
+    time: Time,
+    pressures: Vec<u16>,
+    fail: bool,
+}
+
+impl Sensor for Co2Sensor {
+    type Error = ();
+
+    fn measure(&mut self) -> Result<Measurement, Self::Error> {
+        Ok(measurement(&self.time))
+    }
+}
+
+impl PressureCompensation for Co2Sensor {
+    type Error = ();
+
+    fn set_ambient_pressure(&mut self, pressure: u16) -> Result<(), Self::Error> {
+        if self.fail {
+            return Err(());
+        }
+        self.pressures.push(pressure);
+        Ok(())
+    }
+}
+
+/// Measures `pressure` in hPa, fails if it is `None`.
+struct Barometer {
+    time: Time,
+    pressure: Option<f32>,
+    measurements: usize,
+}
+
+impl Sensor for Barometer {
+    type Error = ();
+
+    fn measure(&mut self) -> Result<Measurement, Self::Error> {
+        self.measurements += 1;
+        let pressure = self.pressure.ok_or(())?;
+        let mut measurement = measurement(&self.time);
+        measurement.pressure = Some(pressure);
+        Ok(measurement)
+    }
+}
+
+fn compensated(time: &Time, pressure: f32) -> PressureCompensated<Co2Sensor, Barometer> {
+    let sensor = Co2Sensor {
+        time: time.clone(),
+        pressures: Vec::new(),
+        fail: false,
+    };
+    let barometer = Barometer {
+        time: time.clone(),
+        pressure: Some(pressure),
+        measurements: 0,
+    };
+    PressureCompensated::new(sensor, barometer, INTERVAL)
+}
+
+#[test]
+fn updates_before_the_first_measurement_and_then_every_interval() {
+    // without a clock of its own the wrapper goes by the time of the previous measurement,
+    // so the update follows the first measurement at least an interval after the last one
+    // (time in s, pressure measured, pressures pushed so far)
+    let steps: [(u64, f32, &[u16]); 6] = [
+        (0, 1013.25, &[1013]),
+        (30, 1000.0, &[1013]),
+        (60, 1000.0, &[1013]),
+        (61, 1000.6, &[1013, 1001]),
+        (121, 990.0, &[1013, 1001]),
+        (130, 990.0, &[1013, 1001, 990]),
+    ];
+    let time = Time::default();
+    let mut sensor = compensated(&time, 1013.25);
+
+    for (seconds, pressure, pushed) in steps {
+        time.set(seconds * 1_000);
+        sensor.pressure_sensor_mut().pressure = Some(pressure);
+
+        sensor.measure().unwrap();
+
+        assert_eq!(sensor.inner().pressures, pushed, "at {seconds} s");
+        assert_eq!(
+            sensor.pressure_sensor().measurements,
+            pushed.len(),
+            "at {seconds} s"
+        );
+    }
+}
+
+#[test]
+fn keeps_the_previous_pressure_and_retries_when_the_pressure_sensor_fails() {
+    let time = Time::default();
+    let mut sensor = compensated(&time, 1013.0);
+    sensor.measure().unwrap();
+
+    time.set(60_000);
+    sensor.pressure_sensor_mut().pressure = None;
+    let measurement = sensor.measure().unwrap();
+
+    assert_eq!(sensor.pressure(), Some(1013.0));
+    assert_eq!(measurement.pressure, Some(1013.0));
+
+    time.set(65_000);
+    sensor.pressure_sensor_mut().pressure = Some(1005.0);
+    sensor.measure().unwrap();
+
+    assert_eq!(sensor.inner().pressures, [1013, 1005]);
+    assert_eq!(sensor.pressure(), Some(1005.0));
+}
+
+#[test]
+fn keeps_the_previous_pressure_and_retries_when_setting_it_fails() {
+    let time = Time::default();
+    let mut sensor = compensated(&time, 1013.0);
+    sensor.measure().unwrap();
+
+    time.set(60_000);
+    sensor.pressure_sensor_mut().pressure = Some(1005.0);
+    sensor.inner_mut().fail = true;
+    let measurement = sensor.measure().unwrap();
+
+    assert_eq!(sensor.pressure(), Some(1013.0));
+    assert_eq!(measurement.pressure, Some(1013.0));
+
+    time.set(65_000);
+    sensor.inner_mut().fail = false;
+    sensor.measure().unwrap();
+
+    assert_eq!(sensor.inner().pressures, [1013, 1005]);
+    assert_eq!(sensor.pressure(), Some(1005.0));
+}
+
+#[test]
+fn rejects_pressures_the_scd4x_cannot_compensate_for() {
+    // (pressure, accepted)
+    let cases = [
+        (0.0, false),
+        (699.9, false),
+        (700.0, true),
+        (1200.0, true),
+        (1200.1, false),
+        (f32::NAN, false),
+    ];
+    for (pressure, accepted) in cases {
+        let time = Time::default();
+        let mut sensor = compensated(&time, pressure);
+
+        let measurement = sensor.measure().unwrap();
+
+        let expected = accepted.then_some(pressure);
+        assert_eq!(sensor.pressure(), expected, "{pressure} hPa");
+        assert_eq!(measurement.pressure, expected, "{pressure} hPa");
+        assert_eq!(
+            sensor.inner().pressures.len(),
+            usize::from(accepted),
+            "{pressure} hPa"
+        );
+    }
+}
+
+#[test]
+fn tags_measurements_with_the_last_known_pressure() {
+    let time = Time::default();
+    let mut sensor = compensated(&time, 1013.25);
+
+    let first = sensor.measure().unwrap();
+    time.set(30_000);
+    let second = sensor.measure().unwrap();
+
+    // unrounded, unlike the pressure pushed into the sensor
+    assert_eq!(first.pressure, Some(1013.25));
+    assert_eq!(second.pressure, Some(1013.25));
+    assert_eq!(second.timestamp, Timestamp::from_millis(30_000));
+}
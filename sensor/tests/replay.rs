@@ -0,0 +1,137 @@
+use airquamon_domain::{Data, Measurement, Quality, SourceId, Timestamp};
+use sensor::{ReplayError, ReplaySensor, Sensor};
+
+const RECORDING: &str = "\
+timestamp,co2,temperature,humidity
+# meeting room, one reading a minute
+60000,450,21.5,40.0
+120000,520,21.8,41.5
+
+180000,610,,42.0
+";
+
+fn measurement(millis: u64, co2: u16) -> Measurement {
+    Measurement::new(
+        Timestamp::from_millis(millis),
+        SourceId::UNKNOWN,
+        Data {
+            co2,
+            temperature: 21.0,
+            humidity: 40.0,
+        },
+    )
+}
+
+/// Timestamps in ms of the next `n` measurements.
+fn timestamps(sensor: &mut ReplaySensor, n: usize) -> Vec<u64> {
+    (0..n)
+        .map(|_| sensor.measure().unwrap().timestamp.as_millis())
+        .collect()
+}
+
+#[test]
+fn replays_csv_relative_to_the_first_measurement() {
+    let mut sensor = ReplaySensor::from_csv(RECORDING);
+
+    let first = sensor.measure().unwrap();
+    assert_eq!(first.timestamp, Timestamp::from_millis(0));
+    assert_eq!(
+        first.data,
+        Data {
+            co2: 450,
+            temperature: 21.5,
+            humidity: 40.0,
+        }
+    );
+    assert_eq!(sensor.measure().unwrap().timestamp.as_millis(), 60_000);
+
+    let last = sensor.measure().unwrap();
+    assert_eq!(last.timestamp.as_millis(), 120_000);
+    assert_eq!(last.co2(), Some(610));
+    assert_eq!(last.quality.temperature, Quality::Missing);
+    assert_eq!(last.temperature(), None);
+
+    assert_eq!(sensor.measure(), Err(ReplayError::End));
+    assert_eq!(sensor.measure(), Err(ReplayError::End));
+}
+
+#[test]
+fn header_is_optional() {
+    let mut sensor = ReplaySensor::from_csv("0,450,21.5,40.0\n");
+
+    assert_eq!(sensor.measure().unwrap().data.co2, 450);
+    assert_eq!(sensor.measure(), Err(ReplayError::End));
+}
+
+#[test]
+fn rejects_malformed_lines() {
+    // (recording, line of the error)
+    let table = [
+        ("0,450,21.5,40.0\ntimestamp,co2,temperature,humidity\n", 2),
+        ("time,co2,temperature,humidity\n0,450,21.5,40.0\n", 1),
+        ("0,450,21.5,40.0\nnan,450,21.5,40.0\n", 2),
+        ("0,450,21.5,40.0\n60000,high,21.5,40.0\n", 2),
+        ("0,450,21.5\n", 1),
+        ("0,450,21.5,40.0,1013\n", 1),
+        ("# comment\n\n0,-1,21.5,40.0\n", 3),
+    ];
+    for (recording, line) in table {
+        let mut sensor = ReplaySensor::from_csv(recording);
+        let result = (0..3).find_map(|_| sensor.measure().err());
+        assert_eq!(result, Some(ReplayError::Parse { line }), "{recording:?}");
+    }
+}
+
+#[test]
+fn scales_time() {
+    let mut sensor = ReplaySensor::from_csv(RECORDING).with_time_scale(0.5);
+
+    assert_eq!(timestamps(&mut sensor, 3), [0, 30_000, 60_000]);
+}
+
+#[test]
+fn loops_with_timestamps_continuing() {
+    let mut sensor = ReplaySensor::from_csv(RECORDING).with_looping(true);
+
+    assert_eq!(
+        timestamps(&mut sensor, 7),
+        [0, 60_000, 120_000, 180_000, 240_000, 300_000, 360_000]
+    );
+}
+
+#[test]
+fn loops_a_single_measurement_with_advancing_timestamps() {
+    let recording = [measurement(5_000, 450)];
+    let mut sensor = ReplaySensor::new(&recording).with_looping(true);
+
+    assert_eq!(timestamps(&mut sensor, 3), [0, 60_000, 120_000]);
+}
+
+#[test]
+fn replays_measurements_from_a_slice() {
+    let recording = [
+        measurement(10_000, 450),
+        measurement(20_000, 500),
+        measurement(40_000, 550),
+    ];
+    let mut sensor = ReplaySensor::new(&recording)
+        .with_looping(true)
+        .with_time_scale(2.0);
+
+    let co2: Vec<u16> = (0..4).map(|_| sensor.measure().unwrap().data.co2).collect();
+    assert_eq!(co2, [450, 500, 550, 450]);
+
+    let mut sensor = ReplaySensor::new(&recording)
+        .with_looping(true)
+        .with_time_scale(2.0);
+    assert_eq!(timestamps(&mut sensor, 4), [0, 20_000, 60_000, 100_000]);
+}
+
+#[test]
+fn empty_recording_ends_straight_away() {
+    for recording in ["", "timestamp,co2,temperature,humidity\n# nothing\n"] {
+        let mut sensor = ReplaySensor::from_csv(recording).with_looping(true);
+
+        assert_eq!(sensor.measure(), Err(ReplayError::End));
+    }
+}
@@ -0,0 +1,155 @@
+use airquamon_domain::{Data, Measurement, SourceId, Timestamp};
+use embedded_hal::delay::DelayUs;
+use sensor::{Retry, RetryPolicy, Sensor};
+use std::cell::RefCell;
+use std::collections::VecDeque;
+use std::rc::Rc;
+
+/// Records the delays between attempts in ms.
+#[derive(Clone, Default)]
+struct RecordingDelay(Rc<RefCell<Vec<u32>>>);
+
+impl DelayUs for RecordingDelay {
+    fn delay_us(&mut self, _us: u32) {}
+
+    fn delay_ms(&mut self, ms: u32) {
+        self.0.borrow_mut().push(ms);
+    }
+}
+
+/// Returns the scripted results in order, an error is the number of the attempt.
+struct Scripted {
+    results: VecDeque<Result<u16, u8>>,
+    attempts: u8,
+}
+
+impl Scripted {
+    fn new(results: &[Result<u16, ()>]) -> Self {
+        Self {
+            results: results
+                .iter()
+                .enumerate()
+                .map(|(attempt, result)| result.map_err(|_| attempt as u8 + 1))
+                .collect(),
+            attempts: 0,
+        }
+    }
+}
+
+impl Sensor for Scripted {
+    type Error = u8;
+
+    fn measure(&mut self) -> Result<Measurement, Self::Error> {
+        self.attempts += 1;
+        let co2 = self.results.pop_front().expect("measured too often")?;
+        Ok(Measurement::new(
+            Timestamp::from_millis(1_000),
+            SourceId::UNKNOWN,
+            Data {
+                co2,
+                temperature: 20.0,
+                humidity: 40.0,
+            },
+        ))
+    }
+}
+
+fn policy(max_attempts: u8, initial_backoff_ms: u32, max_backoff_ms: u32) -> RetryPolicy {
+    RetryPolicy {
+        max_attempts,
+        initial_backoff_ms,
+        max_backoff_ms,
+    }
+}
+
+#[test]
+fn doesnt_wait_when_the_first_attempt_succeeds() {
+    let delay = RecordingDelay::default();
+    let mut sensor = Retry::new(Scripted::new(&[Ok(800)]), delay.clone(), policy(3, 500, 0));
+
+    assert_eq!(sensor.measure().unwrap().data.co2, 800);
+    assert_eq!(sensor.inner().attempts, 1);
+    assert!(delay.0.borrow().is_empty());
+}
+
+#[test]
+fn doubles_the_backoff_until_an_attempt_succeeds() {
+    let delay = RecordingDelay::default();
+    let mut sensor = Retry::new(
+        Scripted::new(&[Err(()), Err(()), Err(()), Ok(800)]),
+        delay.clone(),
+        policy(4, 100, 1_000),
+    );
+
+    assert_eq!(sensor.measure().unwrap().data.co2, 800);
+    assert_eq!(sensor.inner().attempts, 4);
+    assert_eq!(*delay.0.borrow(), [100, 200, 400]);
+}
+
+#[test]
+fn caps_the_backoff_and_returns_the_last_error() {
+    let delay = RecordingDelay::default();
+    let mut sensor = Retry::new(
+        Scripted::new(&[Err(()); 6]),
+        delay.clone(),
+        policy(6, 300, 1_000),
+    );
+
+    assert_eq!(sensor.measure(), Err(6));
+    assert_eq!(*delay.0.borrow(), [300, 600, 1_000, 1_000, 1_000]);
+}
+
+#[test]
+fn starts_over_for_every_measurement() {
+    let delay = RecordingDelay::default();
+    let mut sensor = Retry::new(
+        Scripted::new(&[Err(()), Ok(800), Err(()), Err(()), Ok(900)]),
+        delay.clone(),
+        policy(3, 500, 10_000),
+    );
+
+    assert_eq!(sensor.measure().unwrap().data.co2, 800);
+    assert_eq!(sensor.measure().unwrap().data.co2, 900);
+    assert_eq!(*delay.0.borrow(), [500, 500, 1_000]);
+}
+
+#[test]
+fn single_attempt_doesnt_retry() {
+    for max_attempts in [0, 1] {
+        let delay = RecordingDelay::default();
+        let mut sensor = Retry::new(
+            Scripted::new(&[Err(())]),
+            delay.clone(),
+            policy(max_attempts, 500, 10_000),
+        );
+
+        assert_eq!(sensor.measure(), Err(1));
+        assert!(delay.0.borrow().is_empty());
+    }
+}
+
+#[test]
+fn backoff_saturates_instead_of_overflowing() {
+    let delay = RecordingDelay::default();
+    let mut sensor = Retry::new(
+        Scripted::new(&[Err(()), Err(()), Err(())]),
+        delay.clone(),
+        policy(3, u32::MAX / 2 + 1, u32::MAX),
+    );
+
+    assert_eq!(sensor.measure(), Err(3));
+    assert_eq!(*delay.0.borrow(), [u32::MAX / 2 + 1, u32::MAX]);
+}
+
+#[test]
+fn default_policy_makes_three_attempts() {
+    let delay = RecordingDelay::default();
+    let mut sensor = Retry::new(
+        Scripted::new(&[Err(()), Err(()), Err(())]),
+        delay.clone(),
+        RetryPolicy::default(),
+    );
+
+    assert_eq!(sensor.measure(), Err(3));
+    assert_eq!(*delay.0.borrow(), [500, 1_000]);
+}
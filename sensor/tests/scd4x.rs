@@ -0,0 +1,587 @@
+mod common;
+
+use airquamon_domain::{Quality, SourceId};
+use common::{clock, transcript, words, NoDelay};
+use core::{cell::RefCell, time::Duration};
+use critical_section::Mutex;
+use embedded_hal::delay::DelayUs;
+use embedded_hal::i2c::{ErrorKind, NoAcknowledgeSource};
+use embedded_hal_bus::i2c::CriticalSectionDevice;
+use embedded_hal_mock::eh1::i2c::{Mock as I2cMock, Transaction};
+use scd4x::Error;
+use sensor::{
+    Calibrate, Compensation, MeasurementMode, PowerManagement, PowerState, Scd4xError, Scd4xSensor,
+    Sensor, SensorKind, Sht4xSensor, StartUp, SHT4X_DEFAULT_ADDRESS,
+};
+
+const ADDRESS: u8 = 0x62;
+
+const NACK: ErrorKind = ErrorKind::NoAcknowledge(NoAcknowledgeSource::Address);
+
+fn command(command: u16) -> Transaction {
+    Transaction::write(ADDRESS, command.to_be_bytes().to_vec())
+}
+
+fn wake_up() -> Transaction {
+    // the sensor doesn't acknowledge waking up
+    command(0x36f6).with_error(NACK)
+}
+
+fn data_ready(ready: bool) -> [Transaction; 2] {
+    let response = if ready {
+        vec![0x80, 0x06, 0x04]
+    } else {
+        vec![0x80, 0x00, 0xa2]
+    };
+    [command(0xe4b8), Transaction::read(ADDRESS, response)]
+}
+
+/// 500 ppm, 25 °C and 37 %RH.
+fn read_measurement() -> [Transaction; 2] {
+    [
+        command(0xec05),
+        Transaction::read(
+            ADDRESS,
+            vec![0x01, 0xf4, 0x33, 0x66, 0x67, 0xa2, 0x5e, 0xb9, 0x3c],
+        ),
+    ]
+}
+
+fn start_periodic_measurement() -> [Transaction; 3] {
+    [wake_up(), command(0x3646), command(0x21b1)]
+}
+
+fn command_with_word(command: u16, word: u16) -> Transaction {
+    Transaction::write(
+        ADDRESS,
+        [&command.to_be_bytes()[..], &words(&[word])].concat(),
+    )
+}
+
+fn read_word(command: u16, word: u16) -> [Transaction; 2] {
+    [
+        self::command(command),
+        Transaction::read(ADDRESS, words(&[word])),
+    ]
+}
+
+#[test]
+fn measures_periodically_end_to_end() {
+    let expectations = transcript([
+        &start_periodic_measurement(),
+        &data_ready(false),
+        &data_ready(true),
+        &read_measurement(),
+        &[command(0x3f86)],
+    ]);
+    let mut i2c = I2cMock::new(&expectations);
+    let mut scd4x = Scd4xSensor::new(i2c.clone(), NoDelay, clock);
+
+    let measurement = scd4x.measure().unwrap();
+    scd4x.stop().unwrap();
+
+    assert_eq!(measurement.timestamp, clock());
+    assert_eq!(measurement.source, SourceId::UNKNOWN);
+    assert_eq!(measurement.data.co2, 500);
+    assert!((measurement.data.temperature - 25.0).abs() < 0.01);
+    assert!((measurement.data.humidity - 37.0).abs() < 0.01);
+    assert_eq!(measurement.quality.co2, Quality::Valid);
+    i2c.done();
+}
+
+#[test]
+fn keeps_the_periodic_measurement_running_between_measurements() {
+    let expectations = transcript([
+        &start_periodic_measurement(),
+        &data_ready(true),
+        &read_measurement(),
+        &data_ready(true),
+        &read_measurement(),
+    ]);
+    let mut i2c = I2cMock::new(&expectations);
+    let mut scd4x = Scd4xSensor::new(i2c.clone(), NoDelay, clock);
+
+    scd4x.measure().unwrap();
+    scd4x.measure().unwrap();
+
+    assert_eq!(scd4x.power_state(), PowerState::Active);
+    i2c.done();
+}
+
+#[test]
+fn measures_single_shot_and_idles() {
+    let expectations = transcript([&[command(0x219d)], &data_ready(true), &read_measurement()]);
+    let mut i2c = I2cMock::new(&expectations);
+    let mut scd4x = Scd4xSensor::new(i2c.clone(), NoDelay, clock);
+    scd4x.set_mode(MeasurementMode::SingleShot).unwrap();
+
+    let measurement = scd4x.measure().unwrap();
+
+    assert_eq!(measurement.co2(), Some(500));
+    assert_eq!(scd4x.power_state(), PowerState::Idle);
+    i2c.done();
+}
+
+#[test]
+fn discards_the_first_single_shot_after_powering_down() {
+    let expectations = transcript([
+        &[command(0x36e0)],
+        &[wake_up(), command(0x219d)],
+        &data_ready(true),
+        &read_measurement(),
+        &[command(0x219d)],
+        &data_ready(true),
+        &read_measurement(),
+    ]);
+    let mut i2c = I2cMock::new(&expectations);
+    let mut scd4x = Scd4xSensor::new(i2c.clone(), NoDelay, clock);
+    scd4x.set_mode(MeasurementMode::SingleShot).unwrap();
+
+    scd4x.set_power_state(PowerState::PowerDown).unwrap();
+    assert_eq!(scd4x.power_state(), PowerState::PowerDown);
+    let measurement = scd4x.measure().unwrap();
+
+    assert_eq!(measurement.co2(), Some(500));
+    assert_eq!(scd4x.power_state(), PowerState::Idle);
+    i2c.done();
+}
+
+#[test]
+fn measures_temperature_and_humidity_only() {
+    let expectations = transcript([&[command(0x2196)], &data_ready(true), &read_measurement()]);
+    let mut i2c = I2cMock::new(&expectations);
+    let mut scd4x = Scd4xSensor::new(i2c.clone(), NoDelay, clock);
+    scd4x.set_mode(MeasurementMode::SingleShotRht).unwrap();
+
+    let measurement = scd4x.measure().unwrap();
+
+    assert_eq!(measurement.co2(), None);
+    assert!(measurement.temperature().is_some());
+    i2c.done();
+}
+
+#[test]
+fn rejects_measurement_with_wrong_checksum() {
+    let expectations = transcript([
+        &start_periodic_measurement(),
+        &data_ready(true),
+        &[
+            command(0xec05),
+            Transaction::read(
+                ADDRESS,
+                vec![0x01, 0xf4, 0x34, 0x66, 0x67, 0xa2, 0x5e, 0xb9, 0x3c],
+            ),
+        ],
+    ]);
+    let mut i2c = I2cMock::new(&expectations);
+    let mut scd4x = Scd4xSensor::new(i2c.clone(), NoDelay, clock);
+
+    let result = scd4x.measure();
+
+    assert!(matches!(result, Err(Scd4xError::Sensor(Error::Crc))));
+    i2c.done();
+}
+
+#[test]
+fn rejects_data_ready_status_with_wrong_checksum() {
+    let expectations = transcript([
+        &start_periodic_measurement(),
+        &[
+            command(0xe4b8),
+            Transaction::read(ADDRESS, vec![0x80, 0x06, 0x05]),
+        ],
+    ]);
+    let mut i2c = I2cMock::new(&expectations);
+    let mut scd4x = Scd4xSensor::new(i2c.clone(), NoDelay, clock);
+
+    let result = scd4x.measure();
+
+    assert!(matches!(result, Err(Scd4xError::Sensor(Error::Crc))));
+    i2c.done();
+}
+
+#[test]
+fn reports_nack_when_starting() {
+    let mut i2c = I2cMock::new(&[wake_up(), command(0x3646).with_error(NACK)]);
+    let mut scd4x = Scd4xSensor::new(i2c.clone(), NoDelay, clock);
+
+    let result = scd4x.measure();
+
+    assert!(matches!(result, Err(Scd4xError::Sensor(Error::I2c(NACK)))));
+    i2c.done();
+}
+
+#[test]
+fn reports_nack_when_reading() {
+    let expectations = transcript([
+        &start_periodic_measurement(),
+        &data_ready(true),
+        &[command(0xec05).with_error(NACK)],
+    ]);
+    let mut i2c = I2cMock::new(&expectations);
+    let mut scd4x = Scd4xSensor::new(i2c.clone(), NoDelay, clock);
+
+    let result = scd4x.measure();
+
+    assert!(matches!(result, Err(Scd4xError::Sensor(Error::I2c(NACK)))));
+    i2c.done();
+}
+
+#[test]
+fn times_out_when_no_measurement_is_ready() {
+    // polled every 100 ms, at 0, 100 and 200 ms
+    let expectations = transcript([
+        &start_periodic_measurement(),
+        &data_ready(false),
+        &data_ready(false),
+        &data_ready(false),
+    ]);
+    let mut i2c = I2cMock::new(&expectations);
+    let mut scd4x = Scd4xSensor::new(i2c.clone(), NoDelay, clock);
+    scd4x.set_data_ready_timeout(Some(Duration::from_millis(200)));
+
+    let result = scd4x.measure();
+
+    assert!(matches!(result, Err(Scd4xError::Timeout)));
+    i2c.done();
+}
+
+/// Stops a measurement left running and reads the serial number 0x123456789abc.
+fn identify() -> [Transaction; 4] {
+    [
+        wake_up(),
+        command(0x3f86),
+        command(0x3682),
+        Transaction::read(
+            ADDRESS,
+            vec![0x12, 0x34, 0x37, 0x56, 0x78, 0x7d, 0x9a, 0xbc, 0xe0],
+        ),
+    ]
+}
+
+fn self_test(ok: bool) -> [Transaction; 2] {
+    let response = if ok {
+        vec![0x00, 0x00, 0x81]
+    } else {
+        vec![0x00, 0x01, 0xb0]
+    };
+    [command(0x3639), Transaction::read(ADDRESS, response)]
+}
+
+#[test]
+fn start_up_identifies_the_sensor_and_runs_self_test() {
+    // (variant word and checksum, kind)
+    let table = [
+        ([0x00, 0x00, 0x81], SensorKind::Scd40),
+        ([0x14, 0x40, 0x51], SensorKind::Scd41),
+        ([0x50, 0x00, 0x66], SensorKind::Scd43),
+    ];
+    for (variant, kind) in table {
+        let expectations = transcript([
+            &identify(),
+            &[
+                command(0x202f),
+                Transaction::read(ADDRESS, variant.to_vec()),
+            ],
+            &self_test(true),
+        ]);
+        let mut i2c = I2cMock::new(&expectations);
+        let mut scd4x = Scd4xSensor::new(i2c.clone(), NoDelay, clock);
+
+        let info = scd4x.start_up().unwrap();
+
+        assert_eq!(info.kind, kind);
+        assert_eq!(info.serial_number, 0x1234_5678_9abc);
+        assert_eq!(info.firmware_version, None);
+        i2c.done();
+    }
+}
+
+#[test]
+fn start_up_tags_measurements_with_the_serial_number() {
+    let expectations = transcript([
+        &identify(),
+        &[
+            command(0x202f),
+            Transaction::read(ADDRESS, vec![0x10, 0x00, 0xef]),
+        ],
+        &self_test(true),
+        &start_periodic_measurement(),
+        &data_ready(true),
+        &read_measurement(),
+    ]);
+    let mut i2c = I2cMock::new(&expectations);
+    let mut scd4x = Scd4xSensor::new(i2c.clone(), NoDelay, clock);
+
+    let info = scd4x.start_up().unwrap();
+    let measurement = scd4x.measure().unwrap();
+
+    assert_eq!(measurement.source, info.source_id());
+    assert_eq!(measurement.source, SourceId(0x1234_5678_9abc));
+    i2c.done();
+}
+
+#[test]
+fn start_up_falls_back_when_the_variant_is_unknown() {
+    let expectations = transcript([
+        &identify(),
+        // older firmware doesn't know the command
+        &[command(0x202f).with_error(NACK)],
+        &self_test(true),
+    ]);
+    let mut i2c = I2cMock::new(&expectations);
+    let mut scd4x = Scd4xSensor::new(i2c.clone(), NoDelay, clock);
+
+    let info = scd4x.start_up().unwrap();
+
+    assert_eq!(info.kind, SensorKind::Scd4x);
+    i2c.done();
+}
+
+#[test]
+fn start_up_fails_when_self_test_fails() {
+    let expectations = transcript([
+        &identify(),
+        &[
+            command(0x202f),
+            Transaction::read(ADDRESS, vec![0x10, 0x00, 0xef]),
+        ],
+        &self_test(false),
+    ]);
+    let mut i2c = I2cMock::new(&expectations);
+    let mut scd4x = Scd4xSensor::new(i2c.clone(), NoDelay, clock);
+
+    let result = scd4x.start_up();
+
+    assert!(matches!(result, Err(Scd4xError::SelfTestFailed)));
+    i2c.done();
+}
+
+#[test]
+fn borrows_the_bus_and_delay() {
+    let expectations = transcript([
+        &start_periodic_measurement(),
+        &data_ready(true),
+        &read_measurement(),
+    ]);
+    let mut i2c = I2cMock::new(&expectations);
+    let mut delay = NoDelay;
+
+    let mut scd4x = Scd4xSensor::new(&mut i2c, &mut delay, clock);
+    assert_eq!(scd4x.measure().unwrap().co2(), Some(500));
+
+    // usable again once the sensor is dropped
+    delay.delay_ms(1);
+    i2c.done();
+}
+
+#[test]
+fn shares_the_bus_with_other_sensors() {
+    let expectations = transcript([
+        &start_periodic_measurement(),
+        &data_ready(true),
+        &read_measurement(),
+        &[
+            Transaction::write(SHT4X_DEFAULT_ADDRESS, vec![0xfd]),
+            Transaction::read(
+                SHT4X_DEFAULT_ADDRESS,
+                vec![0x66, 0x66, 0x93, 0x80, 0x00, 0xa2],
+            ),
+        ],
+    ]);
+    let mut i2c = I2cMock::new(&expectations);
+    let bus = Mutex::new(RefCell::new(i2c.clone()));
+    let mut scd4x = Scd4xSensor::new(CriticalSectionDevice::new(&bus), NoDelay, clock);
+    let mut sht4x = Sht4xSensor::new(CriticalSectionDevice::new(&bus), NoDelay, clock);
+
+    assert_eq!(scd4x.measure().unwrap().co2(), Some(500));
+    assert!(sht4x.measure().unwrap().temperature().is_some());
+    i2c.done();
+}
+
+/// About 4.0 °C, stored with a resolution of 175 / 2^16 °C.
+const OFFSET_4_C: u16 = 1498;
+
+/// Reads the temperature offset and altitude currently used by the sensor.
+fn current_compensation(offset: u16, altitude: u16) -> Vec<Transaction> {
+    transcript([&read_word(0x2318, offset), &read_word(0x2322, altitude)])
+}
+
+fn compensation(temperature_offset: f32, altitude: u16) -> Compensation {
+    Compensation {
+        temperature_offset,
+        altitude,
+        ambient_pressure: None,
+    }
+}
+
+#[test]
+fn set_compensation_leaves_unchanged_settings_alone() {
+    // (temperature offset, altitude), within 0.01 °C of the current offset
+    let table = [(4.0, 300), (4.009, 300), (3.991, 300)];
+    for (temperature_offset, altitude) in table {
+        let mut i2c = I2cMock::new(&current_compensation(OFFSET_4_C, 300));
+        let mut scd4x = Scd4xSensor::new(i2c.clone(), NoDelay, clock);
+
+        scd4x
+            .set_compensation(&compensation(temperature_offset, altitude), true)
+            .unwrap();
+
+        i2c.done();
+    }
+}
+
+#[test]
+fn set_compensation_writes_changed_settings() {
+    // (temperature offset, altitude, persist, offset written)
+    let table = [
+        (4.02, 300, false, 1505),
+        (5.0, 300, true, 1872),
+        (4.0, 310, false, 1497),
+        (4.0, 0, true, 1497),
+    ];
+    for (temperature_offset, altitude, persist, offset) in table {
+        let persisted = if persist {
+            vec![command(0x3615)]
+        } else {
+            vec![]
+        };
+        let expectations = transcript([
+            &current_compensation(OFFSET_4_C, 300),
+            &[
+                command_with_word(0x241d, offset),
+                command_with_word(0x2427, altitude),
+            ],
+            &persisted,
+        ]);
+        let mut i2c = I2cMock::new(&expectations);
+        let mut scd4x = Scd4xSensor::new(i2c.clone(), NoDelay, clock);
+
+        scd4x
+            .set_compensation(&compensation(temperature_offset, altitude), persist)
+            .unwrap();
+
+        i2c.done();
+    }
+}
+
+#[test]
+fn set_compensation_always_sets_the_ambient_pressure() {
+    // (temperature offset, writes)
+    let table = [
+        (4.0, vec![]),
+        (
+            5.0,
+            vec![
+                command_with_word(0x241d, 1872),
+                command_with_word(0x2427, 300),
+                command(0x3615),
+            ],
+        ),
+    ];
+    for (temperature_offset, writes) in table {
+        let expectations = transcript([
+            &current_compensation(OFFSET_4_C, 300),
+            &writes,
+            &[command_with_word(0xe000, 1013)],
+        ]);
+        let mut i2c = I2cMock::new(&expectations);
+        let mut scd4x = Scd4xSensor::new(i2c.clone(), NoDelay, clock);
+
+        scd4x
+            .set_compensation(
+                &Compensation {
+                    ambient_pressure: Some(1013),
+                    ..compensation(temperature_offset, 300)
+                },
+                true,
+            )
+            .unwrap();
+
+        i2c.done();
+    }
+}
+
+#[test]
+fn persist_settings_stops_the_periodic_measurement_first() {
+    let expectations = transcript([
+        &start_periodic_measurement(),
+        &data_ready(true),
+        &read_measurement(),
+        &[command(0x3f86), command(0x3615)],
+    ]);
+    let mut i2c = I2cMock::new(&expectations);
+    let mut scd4x = Scd4xSensor::new(i2c.clone(), NoDelay, clock);
+    scd4x.measure().unwrap();
+
+    scd4x.persist_settings().unwrap();
+
+    assert_eq!(scd4x.power_state(), PowerState::Idle);
+    i2c.done();
+}
+
+fn read_response(word: u16) -> [Transaction; 1] {
+    [Transaction::read(ADDRESS, words(&[word]))]
+}
+
+#[test]
+fn forced_recalibration_returns_the_correction() {
+    // (response, correction in ppm)
+    let table = [(0x8000, 0), (0x8064, 100), (0x7fce, -50)];
+    for (response, correction) in table {
+        let expectations =
+            transcript([&[command_with_word(0x362f, 420)], &read_response(response)]);
+        let mut i2c = I2cMock::new(&expectations);
+        let mut scd4x = Scd4xSensor::new(i2c.clone(), NoDelay, clock);
+
+        assert_eq!(scd4x.forced_recalibration(420).unwrap(), correction);
+        i2c.done();
+    }
+}
+
+#[test]
+fn forced_recalibration_stops_the_periodic_measurement_first() {
+    let expectations = transcript([
+        &start_periodic_measurement(),
+        &data_ready(true),
+        &read_measurement(),
+        &[command(0x3f86), command_with_word(0x362f, 420)],
+        &read_response(0x8000),
+    ]);
+    let mut i2c = I2cMock::new(&expectations);
+    let mut scd4x = Scd4xSensor::new(i2c.clone(), NoDelay, clock);
+    scd4x.measure().unwrap();
+
+    assert_eq!(scd4x.forced_recalibration(420).unwrap(), 0);
+    i2c.done();
+}
+
+#[test]
+fn forced_recalibration_fails_when_the_sensor_does() {
+    // 0xffff when the sensor wasn't measuring long enough beforehand
+    let expectations = transcript([&[command_with_word(0x362f, 420)], &read_response(0xffff)]);
+    let mut i2c = I2cMock::new(&expectations);
+    let mut scd4x = Scd4xSensor::new(i2c.clone(), NoDelay, clock);
+
+    let result = scd4x.forced_recalibration(420);
+
+    assert!(matches!(result, Err(Error::Internal)));
+    i2c.done();
+}
+
+#[test]
+fn reads_and_sets_automatic_self_calibration() {
+    // (word, enabled)
+    let table = [(0, false), (1, true)];
+    for (word, enabled) in table {
+        let expectations =
+            transcript([&read_word(0x2313, word), &[command_with_word(0x2416, word)]]);
+        let mut i2c = I2cMock::new(&expectations);
+        let mut scd4x = Scd4xSensor::new(i2c.clone(), NoDelay, clock);
+
+        assert_eq!(scd4x.automatic_self_calibration().unwrap(), enabled);
+        scd4x.set_automatic_self_calibration(enabled).unwrap();
+
+        i2c.done();
+    }
+}
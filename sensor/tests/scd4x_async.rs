@@ -0,0 +1,267 @@
+mod common;
+
+use airquamon_domain::{Quality, SourceId};
+use common::{clock, transcript, NoDelay};
+use core::time::Duration;
+use embassy_futures::block_on;
+use embedded_hal::i2c::{ErrorKind, NoAcknowledgeSource};
+use embedded_hal_mock::eh1::i2c::{Mock as I2cMock, Transaction};
+use scd4x::Error;
+use sensor::{
+    AsyncPowerManagement, AsyncScd4xSensor, AsyncSensor, AsyncStartUp, MeasurementMode, PowerState,
+    Scd4xError, SensorKind,
+};
+
+const ADDRESS: u8 = 0x62;
+
+const NACK: ErrorKind = ErrorKind::NoAcknowledge(NoAcknowledgeSource::Address);
+
+fn command(command: u16) -> Transaction {
+    Transaction::write(ADDRESS, command.to_be_bytes().to_vec())
+}
+
+fn wake_up() -> Transaction {
+    // the sensor doesn't acknowledge waking up
+    command(0x36f6).with_error(NACK)
+}
+
+fn data_ready(ready: bool) -> [Transaction; 2] {
+    let response = if ready {
+        vec![0x80, 0x06, 0x04]
+    } else {
+        vec![0x80, 0x00, 0xa2]
+    };
+    [command(0xe4b8), Transaction::read(ADDRESS, response)]
+}
+
+/// 500 ppm, 25 °C and 37 %RH.
+fn read_measurement() -> [Transaction; 2] {
+    [
+        command(0xec05),
+        Transaction::read(
+            ADDRESS,
+            vec![0x01, 0xf4, 0x33, 0x66, 0x67, 0xa2, 0x5e, 0xb9, 0x3c],
+        ),
+    ]
+}
+
+fn start_periodic_measurement() -> [Transaction; 3] {
+    [wake_up(), command(0x3646), command(0x21b1)]
+}
+
+#[test]
+fn measures_periodically_end_to_end() {
+    let expectations = transcript([
+        &start_periodic_measurement(),
+        &data_ready(false),
+        &data_ready(true),
+        &read_measurement(),
+        &data_ready(true),
+        &read_measurement(),
+        &[command(0x3f86)],
+    ]);
+    let mut i2c = I2cMock::new(&expectations);
+    let mut scd4x = AsyncScd4xSensor::new(i2c.clone(), NoDelay, clock);
+
+    let measurement = block_on(scd4x.measure()).unwrap();
+    // keeps running between measurements
+    block_on(scd4x.measure()).unwrap();
+    assert_eq!(scd4x.power_state(), PowerState::Active);
+    block_on(scd4x.stop()).unwrap();
+
+    assert_eq!(measurement.timestamp, clock());
+    assert_eq!(measurement.source, SourceId::UNKNOWN);
+    assert_eq!(measurement.data.co2, 500);
+    assert!((measurement.data.temperature - 25.0).abs() < 0.01);
+    assert!((measurement.data.humidity - 37.0).abs() < 0.01);
+    assert_eq!(measurement.quality.co2, Quality::Valid);
+    assert_eq!(scd4x.power_state(), PowerState::Idle);
+    i2c.done();
+}
+
+#[test]
+fn measures_single_shot_and_idles() {
+    let expectations = transcript([&[command(0x219d)], &data_ready(true), &read_measurement()]);
+    let mut i2c = I2cMock::new(&expectations);
+    let mut scd4x = AsyncScd4xSensor::new(i2c.clone(), NoDelay, clock);
+    block_on(scd4x.set_mode(MeasurementMode::SingleShot)).unwrap();
+
+    let measurement = block_on(scd4x.measure()).unwrap();
+
+    assert_eq!(measurement.co2(), Some(500));
+    assert_eq!(scd4x.power_state(), PowerState::Idle);
+    i2c.done();
+}
+
+#[test]
+fn measures_temperature_and_humidity_only() {
+    let expectations = transcript([&[command(0x2196)], &data_ready(true), &read_measurement()]);
+    let mut i2c = I2cMock::new(&expectations);
+    let mut scd4x = AsyncScd4xSensor::new(i2c.clone(), NoDelay, clock);
+    block_on(scd4x.set_mode(MeasurementMode::SingleShotRht)).unwrap();
+
+    let measurement = block_on(scd4x.measure()).unwrap();
+
+    assert_eq!(measurement.co2(), None);
+    assert!(measurement.temperature().is_some());
+    i2c.done();
+}
+
+#[test]
+fn discards_the_first_single_shot_after_powering_down() {
+    let expectations = transcript([
+        &[command(0x36e0)],
+        &[wake_up(), command(0x219d)],
+        &data_ready(true),
+        &read_measurement(),
+        &[command(0x219d)],
+        &data_ready(true),
+        &read_measurement(),
+    ]);
+    let mut i2c = I2cMock::new(&expectations);
+    let mut scd4x = AsyncScd4xSensor::new(i2c.clone(), NoDelay, clock);
+    block_on(scd4x.set_mode(MeasurementMode::SingleShot)).unwrap();
+
+    block_on(scd4x.set_power_state(PowerState::PowerDown)).unwrap();
+    assert_eq!(scd4x.power_state(), PowerState::PowerDown);
+    let measurement = block_on(scd4x.measure()).unwrap();
+
+    assert_eq!(measurement.co2(), Some(500));
+    assert_eq!(scd4x.power_state(), PowerState::Idle);
+    i2c.done();
+}
+
+#[test]
+fn rejects_measurement_with_wrong_checksum() {
+    let expectations = transcript([
+        &start_periodic_measurement(),
+        &data_ready(true),
+        &[
+            command(0xec05),
+            Transaction::read(
+                ADDRESS,
+                vec![0x01, 0xf4, 0x34, 0x66, 0x67, 0xa2, 0x5e, 0xb9, 0x3c],
+            ),
+        ],
+    ]);
+    let mut i2c = I2cMock::new(&expectations);
+    let mut scd4x = AsyncScd4xSensor::new(i2c.clone(), NoDelay, clock);
+
+    let result = block_on(scd4x.measure());
+
+    assert!(matches!(result, Err(Scd4xError::Sensor(Error::Crc))));
+    i2c.done();
+}
+
+#[test]
+fn reports_nack_when_starting() {
+    let mut i2c = I2cMock::new(&[wake_up(), command(0x3646).with_error(NACK)]);
+    let mut scd4x = AsyncScd4xSensor::new(i2c.clone(), NoDelay, clock);
+
+    let result = block_on(scd4x.measure());
+
+    assert!(matches!(result, Err(Scd4xError::Sensor(Error::I2c(NACK)))));
+    i2c.done();
+}
+
+#[test]
+fn times_out_when_no_measurement_is_ready() {
+    // polled every 100 ms, at 0, 100 and 200 ms
+    let expectations = transcript([
+        &start_periodic_measurement(),
+        &data_ready(false),
+        &data_ready(false),
+        &data_ready(false),
+    ]);
+    let mut i2c = I2cMock::new(&expectations);
+    let mut scd4x = AsyncScd4xSensor::new(i2c.clone(), NoDelay, clock);
+    scd4x.set_data_ready_timeout(Some(Duration::from_millis(200)));
+
+    let result = block_on(scd4x.measure());
+
+    assert!(matches!(result, Err(Scd4xError::Timeout)));
+    i2c.done();
+}
+
+/// Stops a measurement left running and reads the serial number 0x123456789abc.
+fn identify() -> [Transaction; 4] {
+    [
+        wake_up(),
+        command(0x3f86),
+        command(0x3682),
+        Transaction::read(
+            ADDRESS,
+            vec![0x12, 0x34, 0x37, 0x56, 0x78, 0x7d, 0x9a, 0xbc, 0xe0],
+        ),
+    ]
+}
+
+fn self_test(ok: bool) -> [Transaction; 2] {
+    let response = if ok {
+        vec![0x00, 0x00, 0x81]
+    } else {
+        vec![0x00, 0x01, 0xb0]
+    };
+    [command(0x3639), Transaction::read(ADDRESS, response)]
+}
+
+#[test]
+fn start_up_tags_measurements_with_the_serial_number() {
+    let expectations = transcript([
+        &identify(),
+        &[
+            command(0x202f),
+            Transaction::read(ADDRESS, vec![0x10, 0x00, 0xef]),
+        ],
+        &self_test(true),
+        &start_periodic_measurement(),
+        &data_ready(true),
+        &read_measurement(),
+    ]);
+    let mut i2c = I2cMock::new(&expectations);
+    let mut scd4x = AsyncScd4xSensor::new(i2c.clone(), NoDelay, clock);
+
+    let info = block_on(scd4x.start_up()).unwrap();
+    let measurement = block_on(scd4x.measure()).unwrap();
+
+    assert_eq!(info.kind, SensorKind::Scd41);
+    assert_eq!(info.serial_number, 0x1234_5678_9abc);
+    assert_eq!(measurement.source, SourceId(0x1234_5678_9abc));
+    i2c.done();
+}
+
+#[test]
+fn start_up_falls_back_when_the_variant_is_unknown() {
+    let expectations = transcript([
+        &identify(),
+        // older firmware doesn't know the command
+        &[command(0x202f).with_error(NACK)],
+        &self_test(true),
+    ]);
+    let mut i2c = I2cMock::new(&expectations);
+    let mut scd4x = AsyncScd4xSensor::new(i2c.clone(), NoDelay, clock);
+
+    let info = block_on(scd4x.start_up()).unwrap();
+
+    assert_eq!(info.kind, SensorKind::Scd4x);
+    i2c.done();
+}
+
+#[test]
+fn start_up_fails_when_self_test_fails() {
+    let expectations = transcript([
+        &identify(),
+        &[
+            command(0x202f),
+            Transaction::read(ADDRESS, vec![0x10, 0x00, 0xef]),
+        ],
+        &self_test(false),
+    ]);
+    let mut i2c = I2cMock::new(&expectations);
+    let mut scd4x = AsyncScd4xSensor::new(i2c.clone(), NoDelay, clock);
+
+    let result = block_on(scd4x.start_up());
+
+    assert!(matches!(result, Err(Scd4xError::SelfTestFailed)));
+    i2c.done();
+}
@@ -0,0 +1,187 @@
+use airquamon_domain::Measurement;
+use core::time::Duration;
+use sensor::{Profile, Scenario, ScenarioError, ScenarioSensor, Sensor, Signal};
+
+const MINUTE: Duration = Duration::from_secs(60);
+
+fn assert_close(actual: f32, expected: f32, tolerance: f32) {
+    assert!(
+        (actual - expected).abs() <= tolerance,
+        "expected {expected} ± {tolerance}, got {actual}"
+    );
+}
+
+const RAMP: [Profile; 2] = [
+    Profile::Constant(450.0),
+    Profile::Ramp {
+        from: 0.0,
+        to: 600.0,
+        start: Duration::from_secs(10 * 60),
+        duration: Duration::from_secs(60 * 60),
+    },
+];
+
+fn scenario(noise: f32, dropout_rate: f32, seed: u64) -> Scenario<'static> {
+    Scenario {
+        co2: Signal::new(&RAMP, noise),
+        temperature: Signal::new(&[Profile::Constant(21.5)], noise / 100.0),
+        humidity: Signal::new(&[Profile::Constant(45.0)], noise / 10.0),
+        interval: MINUTE,
+        duration: None,
+        dropout_rate,
+        seed,
+    }
+}
+
+fn run(sensor: &mut ScenarioSensor, n: usize) -> Vec<Result<Measurement, ScenarioError>> {
+    (0..n).map(|_| sensor.measure()).collect()
+}
+
+#[test]
+fn same_seed_produces_the_same_measurements() {
+    let first = run(&mut ScenarioSensor::new(scenario(15.0, 0.1, 42)), 200);
+    let second = run(&mut ScenarioSensor::new(scenario(15.0, 0.1, 42)), 200);
+    let other_seed = run(&mut ScenarioSensor::new(scenario(15.0, 0.1, 43)), 200);
+
+    assert_eq!(first, second);
+    assert_ne!(first, other_seed);
+}
+
+#[test]
+fn reset_repeats_the_measurements() {
+    let mut sensor = ScenarioSensor::new(scenario(15.0, 0.1, 7));
+    let first = run(&mut sensor, 50);
+
+    sensor.reset();
+
+    assert_eq!(run(&mut sensor, 50), first);
+}
+
+#[test]
+fn profiles_have_the_described_shape() {
+    let ramp = RAMP[1];
+    let step = Profile::Step {
+        change: 300.0,
+        at: 5 * MINUTE,
+    };
+    let sine = Profile::Sine {
+        amplitude: 100.0,
+        period: 60 * MINUTE,
+        phase: Duration::ZERO,
+    };
+    let shifted = Profile::Sine {
+        amplitude: 100.0,
+        period: 60 * MINUTE,
+        phase: 15 * MINUTE,
+    };
+
+    // (profile, elapsed minutes, value)
+    let table = [
+        (Profile::Constant(450.0), 0, 450.0),
+        (Profile::Constant(450.0), 1000, 450.0),
+        (ramp, 0, 0.0),
+        (ramp, 10, 0.0),
+        (ramp, 40, 300.0),
+        (ramp, 70, 600.0),
+        (ramp, 200, 600.0),
+        (step, 4, 0.0),
+        (step, 5, 300.0),
+        (step, 100, 300.0),
+        (sine, 0, 0.0),
+        (sine, 15, 100.0),
+        (sine, 30, 0.0),
+        (sine, 45, -100.0),
+        (sine, 60, 0.0),
+        (shifted, 0, 100.0),
+    ];
+    for (profile, minutes, expected) in table {
+        assert_close(profile.value(minutes * MINUTE), expected, 1e-3);
+    }
+}
+
+#[test]
+fn noiseless_scenario_follows_its_profiles_in_simulated_time() {
+    let mut sensor = ScenarioSensor::new(scenario(0.0, 0.0, 1));
+
+    for (minute, measurement) in run(&mut sensor, 80).into_iter().enumerate() {
+        let measurement = measurement.unwrap();
+        let expected_co2 = 450.0 + 10.0 * (minute as f32 - 10.0).clamp(0.0, 60.0);
+        assert_eq!(measurement.timestamp.as_millis(), minute as u64 * 60_000);
+        assert_eq!(measurement.data.co2, expected_co2 as u16);
+        assert_eq!(measurement.data.temperature, 21.5);
+        assert_eq!(measurement.data.humidity, 45.0);
+    }
+}
+
+#[test]
+fn noise_has_the_configured_standard_deviation() {
+    let constant = [Profile::Constant(20.0)];
+    let mut sensor = ScenarioSensor::new(Scenario {
+        temperature: Signal::new(&constant, 2.0),
+        ..scenario(0.0, 0.0, 3)
+    });
+
+    let temperatures: Vec<f32> = run(&mut sensor, 5_000)
+        .into_iter()
+        .map(|measurement| measurement.unwrap().data.temperature)
+        .collect();
+    let mean = temperatures.iter().sum::<f32>() / temperatures.len() as f32;
+    let variance = temperatures
+        .iter()
+        .map(|t| (t - mean) * (t - mean))
+        .sum::<f32>()
+        / temperatures.len() as f32;
+
+    assert_close(mean, 20.0, 0.1);
+    assert_close(variance.sqrt(), 2.0, 0.1);
+}
+
+#[test]
+fn drops_measurements_at_the_configured_rate() {
+    let results = run(&mut ScenarioSensor::new(scenario(15.0, 0.25, 5)), 4_000);
+
+    let dropouts = results
+        .iter()
+        .filter(|result| **result == Err(ScenarioError::Dropout))
+        .count();
+    assert_close(dropouts as f32 / results.len() as f32, 0.25, 0.03);
+}
+
+#[test]
+fn dropouts_dont_shift_the_other_measurements() {
+    let reliable = run(&mut ScenarioSensor::new(scenario(15.0, 0.0, 9)), 100);
+    let flaky = run(&mut ScenarioSensor::new(scenario(15.0, 0.5, 9)), 100);
+
+    assert!(flaky.iter().any(Result::is_err));
+    for (reliable, flaky) in reliable.iter().zip(&flaky) {
+        if flaky.is_ok() {
+            assert_eq!(flaky, reliable);
+        }
+    }
+}
+
+#[test]
+fn ends_after_its_duration() {
+    let mut sensor = ScenarioSensor::new(Scenario {
+        duration: Some(3 * MINUTE),
+        ..scenario(0.0, 0.0, 1)
+    });
+
+    assert_eq!(run(&mut sensor, 4).iter().filter(|r| r.is_ok()).count(), 4);
+    assert_eq!(sensor.measure(), Err(ScenarioError::End));
+    assert_eq!(sensor.measure(), Err(ScenarioError::End));
+}
+
+#[test]
+fn clamps_co2_and_humidity() {
+    let mut sensor = ScenarioSensor::new(Scenario {
+        co2: Signal::new(&[Profile::Constant(-100.0)], 0.0),
+        humidity: Signal::new(&[Profile::Constant(150.0)], 0.0),
+        ..scenario(0.0, 0.0, 1)
+    });
+
+    let measurement = sensor.measure().unwrap();
+
+    assert_eq!(measurement.data.co2, 0);
+    assert_eq!(measurement.data.humidity, 100.0);
+}
@@ -0,0 +1,154 @@
+mod common;
+
+use airquamon_domain::{Data, GasIndices, Measurement, SourceId};
+use common::{clock, NoDelay};
+use embedded_hal::i2c::ErrorKind;
+use embedded_hal_mock::eh1::i2c::{Mock as I2cMock, Transaction};
+use sensor::{Sensor, SensorKind, Sgp41Error, Sgp41Sensor, StartUp};
+
+const ADDRESS: u8 = 0x59;
+
+/// 50 %RH and 25 °C, the default compensation.
+const DEFAULT_COMPENSATION: [u8; 6] = [0x80, 0x00, 0xa2, 0x66, 0x66, 0x93];
+
+fn command(command: [u8; 2], arguments: [u8; 6]) -> Vec<u8> {
+    [&command[..], &arguments[..]].concat()
+}
+
+fn conditioning() -> [Transaction; 2] {
+    [
+        Transaction::write(ADDRESS, command([0x26, 0x12], DEFAULT_COMPENSATION)),
+        Transaction::read(ADDRESS, vec![0x75, 0x30, 0x08]),
+    ]
+}
+
+fn raw_signals(compensation: [u8; 6]) -> [Transaction; 2] {
+    [
+        Transaction::write(ADDRESS, command([0x26, 0x19], compensation)),
+        // 30000 VOC and 15000 NOx ticks
+        Transaction::read(ADDRESS, vec![0x75, 0x30, 0x08, 0x3a, 0x98, 0x5d]),
+    ]
+}
+
+#[test]
+fn conditions_then_reports_indices_after_the_blackout() {
+    let mut expectations = Vec::new();
+    for _ in 0..10 {
+        expectations.extend(conditioning());
+    }
+    for _ in 0..47 {
+        expectations.extend(raw_signals(DEFAULT_COMPENSATION));
+    }
+    let mut i2c = I2cMock::new(&expectations);
+    let mut sgp41 = Sgp41Sensor::new(i2c.clone(), NoDelay, clock);
+
+    for _ in 0..10 + 46 {
+        let measurement = sgp41.measure().unwrap();
+        assert_eq!(measurement.gas_indices, None);
+        assert_eq!(measurement.co2(), None);
+        assert_eq!(measurement.temperature(), None);
+        assert_eq!(measurement.humidity(), None);
+    }
+    let measurement = sgp41.measure().unwrap();
+    assert_eq!(measurement.timestamp, clock());
+    assert_eq!(measurement.gas_indices, Some(GasIndices { voc: 1, nox: 1 }));
+    i2c.done();
+}
+
+#[test]
+fn compensates_for_humidity_and_temperature() {
+    let mut expectations = Vec::new();
+    for _ in 0..10 {
+        expectations.extend(conditioning());
+    }
+    // 60 %RH and 20 °C
+    expectations.extend(raw_signals([0x99, 0x99, 0xbe, 0x5f, 0x15, 0x49]));
+    let mut i2c = I2cMock::new(&expectations);
+    let mut sgp41 = Sgp41Sensor::new(i2c.clone(), NoDelay, clock);
+    for _ in 0..10 {
+        sgp41.measure().unwrap();
+    }
+
+    sgp41.set_compensation(&Measurement::new(
+        clock(),
+        SourceId::UNKNOWN,
+        Data {
+            co2: 400,
+            temperature: 20.0,
+            humidity: 60.0,
+        },
+    ));
+    sgp41.measure().unwrap();
+    i2c.done();
+}
+
+#[test]
+fn rejects_raw_signals_with_wrong_checksum() {
+    let mut i2c = I2cMock::new(&[
+        Transaction::write(ADDRESS, command([0x26, 0x19], DEFAULT_COMPENSATION)),
+        Transaction::read(ADDRESS, vec![0x75, 0x30, 0x08, 0x3a, 0x98, 0x5e]),
+    ]);
+    let mut sgp41 = Sgp41Sensor::new(i2c.clone(), NoDelay, clock);
+
+    assert_eq!(sgp41.measure_raw_signals(), Err(Sgp41Error::Crc));
+    i2c.done();
+}
+
+#[test]
+fn reports_i2c_errors() {
+    let mut i2c =
+        I2cMock::new(&[
+            Transaction::write(ADDRESS, command([0x26, 0x12], DEFAULT_COMPENSATION))
+                .with_error(ErrorKind::Other),
+        ]);
+    let mut sgp41 = Sgp41Sensor::new(i2c.clone(), NoDelay, clock);
+
+    assert_eq!(sgp41.measure(), Err(Sgp41Error::I2c(ErrorKind::Other)));
+    i2c.done();
+}
+
+#[test]
+fn start_up_reads_serial_number_and_runs_self_test() {
+    let mut i2c = I2cMock::new(&[
+        Transaction::write(ADDRESS, vec![0x36, 0x82]),
+        Transaction::read(
+            ADDRESS,
+            vec![0x01, 0x23, 0xa0, 0x45, 0x67, 0x53, 0x89, 0xab, 0x77],
+        ),
+        Transaction::write(ADDRESS, vec![0x28, 0x0e]),
+        Transaction::read(ADDRESS, vec![0xd4, 0x00, 0xc6]),
+    ]);
+    let mut sgp41 = Sgp41Sensor::new(i2c.clone(), NoDelay, clock);
+
+    let info = sgp41.start_up().unwrap();
+
+    assert_eq!(info.kind, SensorKind::Sgp41);
+    assert_eq!(info.serial_number, 0x0123_4567_89ab);
+    i2c.done();
+}
+
+#[test]
+fn start_up_fails_when_self_test_fails() {
+    let mut i2c = I2cMock::new(&[
+        Transaction::write(ADDRESS, vec![0x36, 0x82]),
+        Transaction::read(
+            ADDRESS,
+            vec![0x01, 0x23, 0xa0, 0x45, 0x67, 0x53, 0x89, 0xab, 0x77],
+        ),
+        Transaction::write(ADDRESS, vec![0x28, 0x0e]),
+        Transaction::read(ADDRESS, vec![0x4b, 0x01, 0x23]),
+    ]);
+    let mut sgp41 = Sgp41Sensor::new(i2c.clone(), NoDelay, clock);
+
+    assert_eq!(sgp41.start_up(), Err(Sgp41Error::SelfTestFailed));
+    i2c.done();
+}
+
+#[test]
+fn turns_heater_off() {
+    let mut i2c = I2cMock::new(&[Transaction::write(ADDRESS, vec![0x36, 0x15])]);
+    let mut sgp41 = Sgp41Sensor::new(i2c.clone(), NoDelay, clock);
+
+    sgp41.turn_heater_off().unwrap();
+    i2c.done();
+}
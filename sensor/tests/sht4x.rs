@@ -0,0 +1,134 @@
+mod common;
+
+use airquamon_domain::{Quality, SourceId};
+use common::{clock, NoDelay};
+use embedded_hal::i2c::ErrorKind;
+use embedded_hal_mock::eh1::i2c::{Mock as I2cMock, Transaction};
+use sensor::{Heater, Precision, Sensor, Sht4xError, Sht4xSensor, StartUp};
+
+const ADDRESS: u8 = 0x44;
+
+#[test]
+fn measures_with_high_precision_by_default() {
+    let mut i2c = I2cMock::new(&[
+        Transaction::write(ADDRESS, vec![0xfd]),
+        // 0x6666 is 25 °C, 0x8000 is 56.5 %RH
+        Transaction::read(ADDRESS, vec![0x66, 0x66, 0x93, 0x80, 0x00, 0xa2]),
+    ]);
+    let mut sht4x = Sht4xSensor::new(i2c.clone(), NoDelay, clock);
+
+    let measurement = sht4x.measure().unwrap();
+
+    assert_eq!(measurement.timestamp, clock());
+    assert!((measurement.data.temperature - 25.0).abs() < 0.01);
+    assert!((measurement.data.humidity - 56.5).abs() < 0.01);
+    assert_eq!(measurement.quality.temperature, Quality::Valid);
+    assert_eq!(measurement.quality.humidity, Quality::Valid);
+    assert_eq!(measurement.co2(), None);
+    i2c.done();
+}
+
+#[test]
+fn measures_with_medium_and_low_precision() {
+    let mut i2c = I2cMock::new(&[
+        Transaction::write(ADDRESS, vec![0xf6]),
+        Transaction::read(ADDRESS, vec![0x66, 0x66, 0x93, 0x80, 0x00, 0xa2]),
+        Transaction::write(ADDRESS, vec![0xe0]),
+        Transaction::read(ADDRESS, vec![0x66, 0x66, 0x93, 0x80, 0x00, 0xa2]),
+    ]);
+    let mut sht4x = Sht4xSensor::new(i2c.clone(), NoDelay, clock);
+
+    sht4x.set_precision(Precision::Medium);
+    sht4x.measure().unwrap();
+    sht4x.set_precision(Precision::Low);
+    sht4x.measure().unwrap();
+    i2c.done();
+}
+
+#[test]
+fn clamps_humidity_to_0_to_100_percent() {
+    let mut i2c = I2cMock::new(&[
+        Transaction::write(ADDRESS, vec![0xfd]),
+        Transaction::read(ADDRESS, vec![0x00, 0x00, 0x81, 0xff, 0xff, 0xac]),
+        Transaction::write(ADDRESS, vec![0xfd]),
+        Transaction::read(ADDRESS, vec![0xff, 0xff, 0xac, 0x00, 0x00, 0x81]),
+    ]);
+    let mut sht4x = Sht4xSensor::new(i2c.clone(), NoDelay, clock);
+
+    let measurement = sht4x.measure().unwrap();
+    assert_eq!(measurement.data.temperature, -45.0);
+    assert_eq!(measurement.data.humidity, 100.0);
+
+    let measurement = sht4x.measure().unwrap();
+    assert_eq!(measurement.data.temperature, 130.0);
+    assert_eq!(measurement.data.humidity, 0.0);
+    i2c.done();
+}
+
+#[test]
+fn uses_the_configured_address() {
+    let mut i2c = I2cMock::new(&[
+        Transaction::write(0x45, vec![0xfd]),
+        Transaction::read(0x45, vec![0x66, 0x66, 0x93, 0x80, 0x00, 0xa2]),
+    ]);
+    let mut sht4x = Sht4xSensor::new(i2c.clone(), NoDelay, clock).with_address(0x45);
+
+    sht4x.measure().unwrap();
+    i2c.done();
+}
+
+#[test]
+fn heater_measurement_marks_temperature_suspect() {
+    let mut i2c = I2cMock::new(&[
+        Transaction::write(ADDRESS, vec![0x24]),
+        Transaction::read(ADDRESS, vec![0x66, 0x66, 0x93, 0x80, 0x00, 0xa2]),
+    ]);
+    let mut sht4x = Sht4xSensor::new(i2c.clone(), NoDelay, clock);
+
+    let measurement = sht4x.heat(Heater::Power110mW100ms).unwrap();
+
+    assert_eq!(measurement.quality.temperature, Quality::Suspect);
+    assert_eq!(measurement.quality.humidity, Quality::Valid);
+    i2c.done();
+}
+
+#[test]
+fn rejects_measurement_with_wrong_checksum() {
+    let mut i2c = I2cMock::new(&[
+        Transaction::write(ADDRESS, vec![0xfd]),
+        Transaction::read(ADDRESS, vec![0x66, 0x66, 0x93, 0x80, 0x00, 0xa3]),
+    ]);
+    let mut sht4x = Sht4xSensor::new(i2c.clone(), NoDelay, clock);
+
+    assert_eq!(sht4x.measure(), Err(Sht4xError::Crc));
+    i2c.done();
+}
+
+#[test]
+fn reports_i2c_errors() {
+    let mut i2c =
+        I2cMock::new(&[Transaction::write(ADDRESS, vec![0xfd]).with_error(ErrorKind::Other)]);
+    let mut sht4x = Sht4xSensor::new(i2c.clone(), NoDelay, clock);
+
+    assert_eq!(sht4x.measure(), Err(Sht4xError::I2c(ErrorKind::Other)));
+    i2c.done();
+}
+
+#[test]
+fn start_up_resets_and_tags_measurements_with_the_serial_number() {
+    let mut i2c = I2cMock::new(&[
+        Transaction::write(ADDRESS, vec![0x94]),
+        Transaction::write(ADDRESS, vec![0x89]),
+        Transaction::read(ADDRESS, vec![0x12, 0x34, 0x37, 0x56, 0x78, 0x7d]),
+        Transaction::write(ADDRESS, vec![0xfd]),
+        Transaction::read(ADDRESS, vec![0x66, 0x66, 0x93, 0x80, 0x00, 0xa2]),
+    ]);
+    let mut sht4x = Sht4xSensor::new(i2c.clone(), NoDelay, clock);
+
+    let info = sht4x.start_up().unwrap();
+    assert_eq!(info.serial_number, 0x1234_5678);
+
+    let measurement = sht4x.measure().unwrap();
+    assert_eq!(measurement.source, SourceId(0x1234_5678));
+    i2c.done();
+}
@@ -0,0 +1,156 @@
+mod common;
+
+use airquamon_domain::{ParticulateMatter, SourceId};
+use common::{clock, words, NoDelay};
+use core::time::Duration;
+use embedded_hal::i2c::ErrorKind;
+use embedded_hal_mock::eh1::i2c::{Mock as I2cMock, Transaction};
+use sensor::{Sensor, SensorKind, Sps30Error, Sps30Sensor, StartUp};
+
+const ADDRESS: u8 = 0x69;
+
+#[test]
+fn starts_measuring_and_reads_mass_concentrations() {
+    let mut i2c = I2cMock::new(&[
+        // start measurement with unsigned integer output
+        Transaction::write(ADDRESS, vec![0x00, 0x10, 0x05, 0x00, 0xf6]),
+        Transaction::write(ADDRESS, vec![0x02, 0x02]),
+        Transaction::read(ADDRESS, vec![0x00, 0x00, 0x81]),
+        Transaction::write(ADDRESS, vec![0x02, 0x02]),
+        Transaction::read(ADDRESS, vec![0x00, 0x01, 0xb0]),
+        Transaction::write(ADDRESS, vec![0x03, 0x00]),
+        Transaction::read(ADDRESS, words(&[5, 12, 20, 22, 30, 35, 36, 36, 36, 520])),
+        // keeps measuring
+        Transaction::write(ADDRESS, vec![0x02, 0x02]),
+        Transaction::read(ADDRESS, vec![0x00, 0x01, 0xb0]),
+        Transaction::write(ADDRESS, vec![0x03, 0x00]),
+        Transaction::read(ADDRESS, words(&[5, 12, 20, 22, 30, 35, 36, 36, 36, 520])),
+    ]);
+    let mut sps30 = Sps30Sensor::new(i2c.clone(), NoDelay, clock);
+
+    let measurement = sps30.measure().unwrap();
+    assert_eq!(
+        measurement.particulate_matter,
+        Some(ParticulateMatter {
+            pm1_0: 5.0,
+            pm2_5: 12.0,
+            pm10: 22.0
+        })
+    );
+    assert_eq!(measurement.co2(), None);
+    assert_eq!(measurement.temperature(), None);
+
+    sps30.measure().unwrap();
+    i2c.done();
+}
+
+#[test]
+fn rejects_measurement_with_wrong_checksum() {
+    let mut values = words(&[5, 12, 20, 22, 30, 35, 36, 36, 36, 520]);
+    values[5] ^= 0x01;
+    let mut i2c = I2cMock::new(&[
+        Transaction::write(ADDRESS, vec![0x00, 0x10, 0x05, 0x00, 0xf6]),
+        Transaction::write(ADDRESS, vec![0x02, 0x02]),
+        Transaction::read(ADDRESS, vec![0x00, 0x01, 0xb0]),
+        Transaction::write(ADDRESS, vec![0x03, 0x00]),
+        Transaction::read(ADDRESS, values),
+    ]);
+    let mut sps30 = Sps30Sensor::new(i2c.clone(), NoDelay, clock);
+
+    assert_eq!(sps30.measure(), Err(Sps30Error::Crc));
+    i2c.done();
+}
+
+#[test]
+fn times_out_when_no_measurement_is_ready() {
+    let mut expectations = vec![Transaction::write(
+        ADDRESS,
+        vec![0x00, 0x10, 0x05, 0x00, 0xf6],
+    )];
+    // polled every 100 ms for 3 s
+    for _ in 0..31 {
+        expectations.push(Transaction::write(ADDRESS, vec![0x02, 0x02]));
+        expectations.push(Transaction::read(ADDRESS, vec![0x00, 0x00, 0x81]));
+    }
+    let mut i2c = I2cMock::new(&expectations);
+    let mut sps30 = Sps30Sensor::new(i2c.clone(), NoDelay, clock);
+
+    assert_eq!(sps30.measure(), Err(Sps30Error::Timeout));
+    i2c.done();
+}
+
+#[test]
+fn fan_cleaning_starts_measuring_first() {
+    let mut i2c = I2cMock::new(&[
+        Transaction::write(ADDRESS, vec![0x00, 0x10, 0x05, 0x00, 0xf6]),
+        Transaction::write(ADDRESS, vec![0x56, 0x07]),
+        Transaction::write(ADDRESS, vec![0x01, 0x04]),
+    ]);
+    let mut sps30 = Sps30Sensor::new(i2c.clone(), NoDelay, clock);
+
+    sps30.start_fan_cleaning().unwrap();
+    sps30.stop().unwrap();
+    i2c.done();
+}
+
+#[test]
+fn reads_and_writes_auto_cleaning_interval() {
+    let mut i2c = I2cMock::new(&[
+        Transaction::write(ADDRESS, vec![0x80, 0x04]),
+        // 604800 s, one week
+        Transaction::read(ADDRESS, words(&[0x0009, 0x3a80])),
+        Transaction::write(
+            ADDRESS,
+            vec![0x80, 0x04, 0x00, 0x00, 0x81, 0x00, 0x00, 0x81],
+        ),
+    ]);
+    let mut sps30 = Sps30Sensor::new(i2c.clone(), NoDelay, clock);
+
+    assert_eq!(
+        sps30.auto_cleaning_interval().unwrap(),
+        Some(Duration::from_secs(7 * 24 * 60 * 60))
+    );
+    sps30.set_auto_cleaning_interval(None).unwrap();
+    i2c.done();
+}
+
+#[test]
+fn sleeps_and_wakes_up() {
+    let mut i2c = I2cMock::new(&[
+        Transaction::write(ADDRESS, vec![0x10, 0x01]),
+        // the first wake up command is NACKed while the interface is off
+        Transaction::write(ADDRESS, vec![0x11, 0x03]).with_error(ErrorKind::Other),
+        Transaction::write(ADDRESS, vec![0x11, 0x03]),
+    ]);
+    let mut sps30 = Sps30Sensor::new(i2c.clone(), NoDelay, clock);
+
+    sps30.sleep().unwrap();
+    sps30.wake_up().unwrap();
+    i2c.done();
+}
+
+#[test]
+fn start_up_resets_and_reads_identity() {
+    let serial = b"F1DC9A8C6BC1B7A3";
+    let serial_words: Vec<u16> = serial
+        .chunks(2)
+        .map(|chunk| u16::from_be_bytes([chunk[0], chunk[1]]))
+        .chain([0; 8])
+        .collect();
+    let mut i2c = I2cMock::new(&[
+        Transaction::write(ADDRESS, vec![0xd3, 0x04]),
+        Transaction::write(ADDRESS, vec![0xd0, 0x33]),
+        Transaction::read(ADDRESS, words(&serial_words)),
+        Transaction::write(ADDRESS, vec![0xd1, 0x00]),
+        Transaction::read(ADDRESS, words(&[0x0208])),
+    ]);
+    let mut sps30 = Sps30Sensor::new(i2c.clone(), NoDelay, clock);
+
+    let info = sps30.start_up().unwrap();
+
+    assert_eq!(info.kind, SensorKind::Sps30);
+    assert_eq!(info.serial_number, 0xF1DC_9A8C_6BC1_B7A3);
+    assert_eq!(info.firmware_version, Some((2, 8)));
+    assert_eq!(info.source_id(), SourceId(0xF1DC_9A8C_6BC1_B7A3));
+    i2c.done();
+}
@@ -0,0 +1,267 @@
+use airquamon_domain::{Data, Measurement, Quality, QualityFlags, SourceId, Timestamp};
+use core::time::Duration;
+use sensor::{
+    FaultInjector, FaultRates, PowerManagement, PowerState, Profile, ReplayError, ReplaySensor,
+    Scenario, ScenarioSensor, Sensor, Signal, StabilityMonitor, StabilityPolicy,
+};
+
+fn measurement(co2: u16, temperature: f32) -> Measurement {
+    Measurement::new(
+        Timestamp::from_millis(1_000),
+        SourceId::UNKNOWN,
+        Data {
+            co2,
+            temperature,
+            humidity: 40.0,
+        },
+    )
+}
+
+/// Readings with a different CO2 value each.
+fn changing(n: usize) -> Vec<Measurement> {
+    (0..n)
+        .map(|i| measurement(500 + i as u16, 20.0 + i as f32))
+        .collect()
+}
+
+/// Wakes up from power-down when measuring, like the SCD4x.
+struct Sleepy<'a> {
+    sensor: ReplaySensor<'a>,
+    state: PowerState,
+}
+
+impl Sensor for Sleepy<'_> {
+    type Error = ReplayError;
+
+    fn measure(&mut self) -> Result<Measurement, Self::Error> {
+        self.state = PowerState::Idle;
+        self.sensor.measure()
+    }
+}
+
+impl PowerManagement for Sleepy<'_> {
+    type Error = ();
+
+    fn power_state(&self) -> PowerState {
+        self.state
+    }
+
+    fn set_power_state(&mut self, state: PowerState) -> Result<(), Self::Error> {
+        self.state = state;
+        Ok(())
+    }
+
+    fn supply_current(&self, _state: PowerState) -> u32 {
+        0
+    }
+}
+
+fn policy(warm_up_measurements: u16, discard_warm_up: bool, stale: u16) -> StabilityPolicy {
+    StabilityPolicy {
+        warm_up_measurements,
+        discard_warm_up,
+        stale_measurements: stale,
+    }
+}
+
+fn co2_qualities(sensor: &mut impl Sensor, n: usize) -> Vec<Quality> {
+    (0..n)
+        .map(|_| sensor.measure().ok().unwrap().quality.co2)
+        .collect()
+}
+
+#[test]
+fn flags_readings_while_warming_up() {
+    let recording = changing(5);
+    let mut sensor = StabilityMonitor::new(ReplaySensor::new(&recording), policy(3, false, 10));
+
+    assert!(sensor.is_warming_up());
+    let warming_up = sensor.measure().unwrap();
+    assert_eq!(
+        warming_up.quality,
+        QualityFlags {
+            co2: Quality::WarmingUp,
+            temperature: Quality::WarmingUp,
+            humidity: Quality::WarmingUp,
+        }
+    );
+    assert_eq!(
+        co2_qualities(&mut sensor, 4),
+        [
+            Quality::WarmingUp,
+            Quality::WarmingUp,
+            Quality::Valid,
+            Quality::Valid
+        ]
+    );
+    assert!(!sensor.is_warming_up());
+}
+
+#[test]
+fn missing_values_stay_missing_while_warming_up() {
+    let mut without_co2 = measurement(0, 21.0);
+    without_co2.quality.co2 = Quality::Missing;
+    let recording = [without_co2];
+    let mut sensor = StabilityMonitor::new(ReplaySensor::new(&recording), policy(1, false, 10));
+
+    let measurement = sensor.measure().unwrap();
+
+    assert_eq!(measurement.quality.co2, Quality::Missing);
+    assert_eq!(measurement.quality.temperature, Quality::WarmingUp);
+}
+
+#[test]
+fn discards_readings_while_warming_up() {
+    let recording = changing(5);
+    let mut sensor = StabilityMonitor::new(ReplaySensor::new(&recording), policy(3, true, 10));
+
+    let measurement = sensor.measure().unwrap();
+
+    // the first 3 readings were measured and dropped
+    assert_eq!(measurement.data, recording[3].data);
+    assert_eq!(measurement.quality, QualityFlags::VALID);
+    assert_eq!(sensor.measure().unwrap().data, recording[4].data);
+}
+
+#[test]
+fn restart_warms_up_again() {
+    let recording = changing(4);
+    let mut sensor = StabilityMonitor::new(ReplaySensor::new(&recording), policy(1, false, 10));
+
+    assert_eq!(
+        co2_qualities(&mut sensor, 2),
+        [Quality::WarmingUp, Quality::Valid]
+    );
+    sensor.restart();
+    assert!(sensor.is_warming_up());
+    assert_eq!(
+        co2_qualities(&mut sensor, 2),
+        [Quality::WarmingUp, Quality::Valid]
+    );
+}
+
+#[test]
+fn flags_values_stale_once_repeated_for_the_threshold() {
+    // CO2 freezes for 5 readings, the temperature keeps changing
+    let recording: Vec<_> = [500, 510, 510, 510, 510, 510, 520]
+        .iter()
+        .enumerate()
+        .map(|(i, &co2)| measurement(co2, 20.0 + i as f32))
+        .collect();
+    let mut sensor = StabilityMonitor::new(ReplaySensor::new(&recording), policy(0, false, 4));
+
+    let measurements: Vec<_> = (0..recording.len())
+        .map(|_| sensor.measure().unwrap())
+        .collect();
+
+    let co2: Vec<_> = measurements.iter().map(|m| m.quality.co2).collect();
+    assert_eq!(
+        co2,
+        [
+            Quality::Valid,
+            Quality::Valid,
+            Quality::Valid,
+            Quality::Valid,
+            Quality::Stale,
+            Quality::Stale,
+            Quality::Valid,
+        ]
+    );
+    assert!(measurements
+        .iter()
+        .all(|m| m.quality.temperature == Quality::Valid));
+}
+
+#[test]
+fn stale_check_can_be_disabled() {
+    let recording = [measurement(500, 20.0); 20];
+    let mut sensor = StabilityMonitor::new(ReplaySensor::new(&recording), policy(0, false, 0));
+
+    assert!(co2_qualities(&mut sensor, 20)
+        .iter()
+        .all(|&quality| quality == Quality::Valid));
+}
+
+#[test]
+fn flags_an_injected_stuck_sensor_stale() {
+    const CO2: [Profile; 1] = [Profile::Constant(800.0)];
+    let sensor = ScenarioSensor::new(Scenario {
+        co2: Signal::new(&CO2, 20.0),
+        temperature: Signal::new(&[Profile::Constant(21.5)], 0.2),
+        humidity: Signal::new(&[Profile::Constant(45.0)], 1.0),
+        interval: Duration::from_secs(60),
+        duration: None,
+        dropout_rate: 0.0,
+        seed: 1,
+    });
+    // the first reading gets stuck for the next 12
+    let stuck = FaultRates {
+        stuck: 1.0,
+        stuck_measurements: 12,
+        ..FaultRates::default()
+    };
+    let mut sensor = StabilityMonitor::new(
+        FaultInjector::new(sensor, stuck, 1),
+        StabilityPolicy {
+            warm_up_measurements: 0,
+            ..StabilityPolicy::default()
+        },
+    );
+    sensor.measure().unwrap();
+    sensor.inner_mut().set_rates(FaultRates::default());
+
+    let qualities = co2_qualities(&mut sensor, 14);
+
+    // stale from the 10th identical reading until the sensor recovers
+    assert!(qualities[..8].iter().all(|&q| q == Quality::Valid));
+    assert!(qualities[8..12].iter().all(|&q| q == Quality::Stale));
+    assert!(qualities[12..].iter().all(|&q| q == Quality::Valid));
+}
+
+#[test]
+fn warms_up_again_after_powering_down() {
+    let recording = changing(6);
+    let sensor = Sleepy {
+        sensor: ReplaySensor::new(&recording),
+        state: PowerState::Idle,
+    };
+    let mut sensor = StabilityMonitor::new(sensor, policy(1, false, 10));
+    assert_eq!(
+        co2_qualities(&mut sensor, 2),
+        [Quality::WarmingUp, Quality::Valid]
+    );
+
+    // woken up by the next measurement
+    sensor.set_power_state(PowerState::PowerDown).unwrap();
+    assert!(!sensor.is_warming_up());
+    assert_eq!(
+        co2_qualities(&mut sensor, 2),
+        [Quality::WarmingUp, Quality::Valid]
+    );
+
+    // woken up explicitly
+    sensor.set_power_state(PowerState::PowerDown).unwrap();
+    sensor.set_power_state(PowerState::Idle).unwrap();
+    assert!(sensor.is_warming_up());
+    assert_eq!(
+        co2_qualities(&mut sensor, 2),
+        [Quality::WarmingUp, Quality::Valid]
+    );
+}
+
+#[test]
+fn keeps_warmed_up_when_idling() {
+    let recording = changing(3);
+    let sensor = Sleepy {
+        sensor: ReplaySensor::new(&recording),
+        state: PowerState::Idle,
+    };
+    let mut sensor = StabilityMonitor::new(sensor, policy(1, false, 10));
+    sensor.measure().unwrap();
+
+    sensor.set_power_state(PowerState::Idle).unwrap();
+    sensor.set_power_state(PowerState::Active).unwrap();
+
+    assert!(!sensor.is_warming_up());
+    assert_eq!(sensor.measure().unwrap().quality.co2, Quality::Valid);
+}